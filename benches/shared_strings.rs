@@ -0,0 +1,70 @@
+//! Shows that a view of a million `Rc<str>` cells lays out, draws, and sorts
+//! without copying the strings in its cells.
+//!
+//! Run with `cargo bench --bench shared_strings`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use cursive::vec::Vec2;
+use cursive_spreadsheet_view::{ColumnDef, Record, SpreadsheetView};
+
+const ROWS: usize = 1_000_000;
+
+/// Counts the allocations made by the program.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, printing how long it took and how many allocations it made, and
+/// returns the number of allocations.
+fn measure(name: &str, f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    f();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("{:<8} {:>10.1?} {:>8} allocations", name, start.elapsed(), allocations);
+    allocations
+}
+
+fn main() {
+    let statuses: Vec<Rc<str>> = ["active", "pending", "retired"].iter().map(|&s| Rc::from(s)).collect();
+
+    let mut view = SpreadsheetView::<Rc<str>>::new();
+    view.push_column("status".to_string(), ColumnDef::new("Status"));
+    view.extend_records((0..ROWS).map(|i| {
+        let mut record = Record::new();
+        record.insert("status".to_string(), statuses[i % statuses.len()].clone());
+        record
+    }));
+
+    println!("{} rows sharing {} distinct strings", ROWS, statuses.len());
+
+    // Copying the cells would take an allocation per row. Instead, layout and
+    // drawing only allocate for the visible rows, and sorting for its index of
+    // record positions.
+    let size = Vec2::new(80, 24);
+    let allocations = measure("draw", || { view.render_to_string(size); })
+        + measure("sort", || view.sort_records("status", true))
+        + measure("redraw", || { view.render_to_string(size); });
+    assert!(allocations < ROWS / 100);
+
+    let shared: usize = statuses.iter().map(|s| Rc::strong_count(s) - 1).sum();
+    assert_eq!(shared, ROWS);
+}
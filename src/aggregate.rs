@@ -0,0 +1,184 @@
+//! Summaries of the values of many cells, shown in a row pinned under the
+//! records for every column, and in the status bar for the selection.
+
+use std::fmt::{self, Display};
+use std::rc::Rc;
+
+use unicode_width::UnicodeWidthStr;
+
+use cursive::Printer;
+use cursive::theme::Effect;
+
+use crate::{COLUMN_SEPARATOR, SpreadsheetView};
+use crate::formula::{self, FormulaValue, Sheet};
+use crate::text::{escape_controls, print_aligned};
+
+/// Computes a custom aggregate from the values of the cells, empty ones
+/// included.
+pub type AggregateFunction = Rc<dyn Fn(&[FormulaValue]) -> FormulaValue>;
+
+/// A summary of the values of many cells. Like in formulas, cells holding
+/// text are skipped by the sums and the like, and errors carry through.
+#[derive(Clone)]
+pub enum Aggregate {
+    Sum,
+    Average,
+    Min,
+    Max,
+    /// The number of cells that are not empty.
+    Count,
+    /// Computed by a function of the application, and labelled with the
+    /// given name in the status bar.
+    Custom(String, AggregateFunction),
+}
+
+impl Aggregate {
+    /// Creates a custom aggregate.
+    pub fn custom<F>(label: &str, f: F) -> Self
+    where
+        F: Fn(&[FormulaValue]) -> FormulaValue + 'static,
+    {
+        Self::Custom(label.to_string(), Rc::new(f))
+    }
+
+    /// Returns the label the aggregate is shown with.
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Sum => "Sum",
+            Self::Average => "Average",
+            Self::Min => "Min",
+            Self::Max => "Max",
+            Self::Count => "Count",
+            Self::Custom(label, _) => label,
+        }
+    }
+
+    /// Computes the aggregate of the given values.
+    pub fn compute(&self, values: &[FormulaValue]) -> FormulaValue {
+        match self {
+            Self::Sum => formula::fold_numbers(values, |ns| ns.iter().sum()),
+            Self::Average => formula::fold_numbers(values, |ns| ns.iter().sum::<f64>() / ns.len() as f64),
+            Self::Min => formula::fold_numbers(values, |ns| ns.iter().copied().fold(f64::INFINITY, f64::min)),
+            Self::Max => formula::fold_numbers(values, |ns| ns.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+            Self::Count => {
+                FormulaValue::Number(values.iter().filter(|value| **value != FormulaValue::Empty).count() as f64)
+            },
+            Self::Custom(_, f) => f(values),
+        }
+    }
+}
+
+impl fmt::Debug for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Custom(label, _) => f.debug_tuple("Custom").field(label).finish(),
+            other => f.write_str(other.label()),
+        }
+    }
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Shows the aggregate of the values of the column with the specified key
+    /// in a row pinned under the records, such as the total of a column of
+    /// prices. The aggregate covers the records shown by the filter, and is
+    /// computed again every time the view is drawn, so that it follows edits.
+    /// Records of a source are not aggregated.
+    pub fn set_column_aggregate(&mut self, key: &str, aggregate: Aggregate) {
+        self.column_aggregates.insert(key.to_string(), aggregate);
+    }
+
+    /// Removes the aggregate of the column with the specified key, if any. The
+    /// row of aggregates is hidden once no column has one.
+    pub fn clear_column_aggregate(&mut self, key: &str) -> Option<Aggregate> {
+        self.column_aggregates.remove(key)
+    }
+
+    /// Returns the aggregate of the values of the column with the specified
+    /// key, if it has one.
+    pub fn column_aggregate_value(&self, key: &str) -> Option<FormulaValue> {
+        let aggregate = self.column_aggregates.get(key)?;
+        let x = self.columns.get_index_of(key)?;
+
+        let evaluation = self.evaluation();
+        let values: Vec<FormulaValue> = (0..self.records.len())
+            .filter(|&row| self.is_record_shown(row))
+            .map(|row| evaluation.value((x, row)))
+            .collect();
+        Some(aggregate.compute(&values))
+    }
+
+    /// Returns `true` if the row of aggregates is shown.
+    pub(crate) fn has_column_aggregates(&self) -> bool {
+        !self.column_aggregates.is_empty()
+    }
+
+    /// Draws the row of aggregates, on the first line of the given printer.
+    pub(crate) fn draw_aggregate_row(&self, printer: &Printer) {
+        self.draw_aggregates(printer, |key| self.column_aggregate_value(key));
+    }
+
+    /// Draws the given aggregates of the columns, found by their keys, on the
+    /// first line of the given printer.
+    pub(crate) fn draw_aggregates<F>(&self, printer: &Printer, value: F)
+    where
+        F: Fn(&str) -> Option<FormulaValue>,
+    {
+        printer.print_hline((0, 0), printer.size.x, " ");
+        if self.has_gutter() {
+            self.print_grid(printer, self.gutter_width, self.vertical_separator(true));
+        }
+
+        let mut x = self.gutter_offset();
+        for (n, i) in self.drawn_columns().enumerate() {
+            let ((key, def), width) = (self.columns.get_index(i).unwrap(), self.column_widths[i]);
+            if n > 0 {
+                self.print_grid(printer, x, self.separator_before(i));
+                x += COLUMN_SEPARATOR.width();
+            }
+
+            if let Some(value) = value(key) {
+                let text = escape_controls(&value.to_string());
+                printer.offset((x, 0)).with_effect(Effect::Bold, |p| print_aligned(p, &text, width, &def.alignment));
+            }
+            x += width;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    #[test]
+    fn aggregate_row_follows_edits_and_filter() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        view.push_column("sq".to_string(), ColumnDef::new("Square"));
+        view.extend_records((1..=4).map(|n| {
+            vec![("n".to_string(), n), ("sq".to_string(), n * n)].into_iter().collect()
+        }));
+        view.set_column_aggregate("n", Aggregate::Count);
+        view.set_column_aggregate("sq", Aggregate::Sum);
+        assert_eq!(view.column_aggregate_value("sq"), Some(FormulaValue::Number(30.0)));
+        assert!(view.render_to_string(Vec2::new(20, 8)).ends_with("\n4 │ 30"));
+
+        view.set_cell(0, "sq", 10);
+        view.set_filter(|record| record["n"] != 4);
+        assert!(view.render_to_string(Vec2::new(20, 8)).ends_with("\n3 │ 23"));
+
+        view.set_column_aggregate("sq", Aggregate::custom("Odd", |values| {
+            let odd = values.iter().filter(|v| v.as_number().is_ok_and(|n| n % 2.0 == 1.0)).count();
+            FormulaValue::Number(odd as f64)
+        }));
+        assert_eq!(view.column_aggregate_value("sq"), Some(FormulaValue::Number(1.0)));
+        assert_eq!(format!("{:?}", Aggregate::custom("Odd", |_| FormulaValue::Empty)), "Custom(\"Odd\")");
+
+        view.clear_column_aggregate("n");
+        view.clear_column_aggregate("sq");
+        assert!(view.render_to_string(Vec2::new(20, 8)).trim_end().ends_with("\n3 │ 9"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Sizing columns to their contents, and handing out the width left over in
+//! the view once they are.
+
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+use crate::{ColumnWidth, SpreadsheetView};
+
+/// Where the width left over when the columns are narrower than the view goes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColumnFill {
+    /// Leaves it empty, to the right of the last column. The default.
+    #[default]
+    None,
+    /// Widens the columns sized to their contents, in proportion to their
+    /// widths.
+    Proportional,
+    /// Widens the column with the given key, e.g. a column of descriptions.
+    Flex(String),
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Sets where the width left over when the columns are narrower than the
+    /// view goes. Columns are never widened past the maximum of their bounds,
+    /// and fixed columns and columns the user resized are never widened.
+    pub fn set_column_fill(&mut self, fill: ColumnFill) {
+        self.column_fill = fill;
+    }
+
+    /// Returns where the width left over by the columns goes.
+    pub fn column_fill(&self) -> &ColumnFill {
+        &self.column_fill
+    }
+
+    /// Sets whether columns are sized to fit only the records in view rather
+    /// than every record, so that large tables lay out quickly. Columns then
+    /// change width as the view scrolls, unless they are grow-only. Records
+    /// from a source are always measured this way. Off by default.
+    pub fn set_measure_visible_rows(&mut self, visible_only: bool) {
+        self.measure_visible_rows = visible_only;
+    }
+
+    /// Returns `true` if columns are sized to fit only the records in view.
+    pub fn measures_visible_rows(&self) -> bool {
+        self.measure_visible_rows
+    }
+
+    /// Returns the width the column with the given key was given by the last
+    /// layout or call to `recompute_widths`.
+    pub fn resolved_column_width(&self, key: &str) -> Option<usize> {
+        self.column_widths.get(self.columns.get_index_of(key)?).copied()
+    }
+
+    /// Returns the indices of the records measured to size the columns, or
+    /// `None` if every record is.
+    pub(crate) fn measured_rows(&self) -> Option<RangeInclusive<usize>> {
+        if !self.measure_visible_rows {
+            return None;
+        }
+
+        let start = self.scroll_base.start_line;
+        let rows: Vec<usize> = (start..start + self.scroll_base.view_height)
+            .filter_map(|line| self.line_to_row(line))
+            .map(|(row, _)| row)
+            .filter(|&row| row < self.records.len())
+            .collect();
+        Some(match (rows.iter().min(), rows.iter().max()) {
+            (Some(&first), Some(&last)) => first..=last,
+            // Nothing is in view.
+            #[allow(clippy::reversed_empty_ranges)]
+            _ => 1..=0,
+        })
+    }
+
+    /// Returns `true` if the column at the given index may be widened to fill
+    /// the view.
+    fn is_flexible(&self, x: usize) -> bool {
+        let def = &self.columns[x];
+        !def.hidden && def.resized_width.is_none() && !matches!(def.width, ColumnWidth::Fixed(_))
+    }
+}
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Sizes the columns to their contents again right away, rather than the
+    /// next time the view is laid out, e.g. so that `resolved_column_width`
+    /// reflects records just added.
+    pub fn recompute_widths(&mut self) {
+        if self.measure_visible_rows && self.scroll_base.view_height == 0 {
+            // Nothing has been laid out to be in view yet.
+            self.measure_visible_rows = false;
+            self.compute_column_widths();
+            self.measure_visible_rows = true;
+        }
+        else {
+            self.compute_column_widths();
+        }
+        self.fill_leftover_width();
+    }
+
+    /// Hands out the width the columns leave empty in the body as set by
+    /// `set_column_fill`.
+    pub(crate) fn fill_leftover_width(&mut self) {
+        let mut leftover = match self.body_width().checked_sub(self.content_width()) {
+            Some(leftover) if leftover > 0 => leftover,
+            _ => return,
+        };
+
+        let flexible: Vec<usize> = match &self.column_fill {
+            ColumnFill::None => return,
+            ColumnFill::Proportional => (0..self.columns.len()).filter(|&x| self.is_flexible(x)).collect(),
+            ColumnFill::Flex(key) => self.columns.get_index_of(key).filter(|&x| self.is_flexible(x)).into_iter().collect(),
+        };
+
+        // Columns reaching their maximum width pass the rest of their share
+        // on to the others.
+        let mut widths: Vec<usize> = flexible.iter().map(|&x| self.column_widths[x]).collect();
+        let mut growing: Vec<usize> = (0..flexible.len()).collect();
+        while leftover > 0 && !growing.is_empty() {
+            let total: usize = growing.iter().map(|&i| widths[i].max(1)).sum();
+            let shares: Vec<usize> = growing.iter().map(|&i| leftover * widths[i].max(1) / total).collect();
+            // The width lost to rounding goes to the first columns.
+            let mut rounding = leftover - shares.iter().sum::<usize>();
+
+            let mut still_growing = Vec::new();
+            for (&i, share) in growing.iter().zip(shares) {
+                let share = share + usize::from(rounding > 0);
+                rounding = rounding.saturating_sub(1);
+
+                let max_width = self.columns[flexible[i]].width.bounds().1.unwrap_or(usize::MAX).max(widths[i]);
+                let extra = share.min(max_width - widths[i]);
+                widths[i] += extra;
+                leftover -= extra;
+                if widths[i] < max_width {
+                    still_growing.push(i);
+                }
+            }
+            growing = still_growing;
+        }
+
+        for (x, width) in flexible.into_iter().zip(widths) {
+            self.column_widths[x] = width;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    fn view() -> SpreadsheetView<String> {
+        let mut view = SpreadsheetView::new();
+        view.push_column("a".to_string(), ColumnDef::new("A"));
+        view.push_column("b".to_string(), ColumnDef::new("B").width(ColumnWidth::Max(8)));
+        view.push_column("c".to_string(), ColumnDef::new("C").width(ColumnWidth::Fixed(2)));
+        view.extend_records((0..20).map(|n| {
+            let text = "x".repeat(n + 1);
+            vec![("a".to_string(), text.clone()), ("b".to_string(), text.clone()), ("c".to_string(), text)].into_iter().collect()
+        }));
+        view
+    }
+
+    #[test]
+    fn columns_fit_contents_and_fill_the_view() {
+        let mut view = view();
+        view.recompute_widths();
+        assert_eq!(view.resolved_column_width("a"), Some(20));
+        assert_eq!(view.resolved_column_width("b"), Some(8));
+        assert_eq!(view.resolved_column_width("c"), Some(2));
+
+        view.set_measure_visible_rows(true);
+        view.layout(Vec2::new(40, 6));
+        assert_eq!(view.resolved_column_width("a"), Some(4));
+        assert_eq!(view.resolved_column_width("b"), Some(4));
+
+        view.set_column_fill(ColumnFill::Flex("a".to_string()));
+        view.layout(Vec2::new(40, 6));
+        // The scrollbar and its padding take 2 columns, and the separators 6.
+        assert_eq!(view.resolved_column_width("a"), Some(38 - 6 - 4 - 2));
+
+        view.set_column_fill(ColumnFill::Proportional);
+        view.layout(Vec2::new(40, 6));
+        assert_eq!(view.resolved_column_width("b"), Some(8));
+        assert_eq!(view.resolved_column_width("a"), Some(38 - 6 - 8 - 2));
+        assert_eq!(view.resolved_column_width("c"), Some(2));
+    }
+}
@@ -0,0 +1,442 @@
+//! Mapping of input events to the actions of a spreadsheet.
+
+use indexmap::IndexMap;
+
+use cursive::event::{Event, Key, MouseEvent};
+
+/// An action a spreadsheet can perform in response to input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SpreadsheetAction {
+    /// Moves the cursor one cell up.
+    MoveUp,
+    /// Moves the cursor one cell down.
+    MoveDown,
+    /// Moves the cursor one cell left.
+    MoveLeft,
+    /// Moves the cursor one cell right.
+    MoveRight,
+    /// Moves the cursor up by a screenful of records.
+    PageUp,
+    /// Moves the cursor down by a screenful of records.
+    PageDown,
+    /// Moves the cursor left by a screenful of columns.
+    PageLeft,
+    /// Moves the cursor right by a screenful of columns.
+    PageRight,
+    /// Moves the cursor to the first column.
+    RowStart,
+    /// Moves the cursor to the last column.
+    RowEnd,
+    /// Moves the cursor to the first cell of the first record.
+    FirstCell,
+    /// Moves the cursor to the last cell of the last record.
+    LastCell,
+    /// Moves the cursor one cell up, extending the selection.
+    ExtendUp,
+    /// Moves the cursor one cell down, extending the selection.
+    ExtendDown,
+    /// Moves the cursor one cell left, extending the selection.
+    ExtendLeft,
+    /// Moves the cursor one cell right, extending the selection.
+    ExtendRight,
+    /// Moves the cursor to the first cell, extending the selection.
+    ExtendToFirstCell,
+    /// Moves the cursor to the last cell, extending the selection.
+    ExtendToLastCell,
+    /// Opens the editor suited to the cell under the cursor.
+    EditCell,
+    /// Opens the cell under the cursor in a modal dialog.
+    EditCellInDialog,
+    /// Opens the cell under the cursor in the user's `$EDITOR`.
+    EditCellExternally,
+    /// Moves the cursor to the next cell that fails validation.
+    NextError,
+    /// Moves the column of the cursor one place to the left.
+    MoveColumnLeft,
+    /// Moves the column of the cursor one place to the right.
+    MoveColumnRight,
+    /// Narrows the column of the cursor by one, within its width bounds.
+    NarrowColumn,
+    /// Widens the column of the cursor by one, within its width bounds.
+    WidenColumn,
+    /// Opens a prompt to search the cells, as the search is typed.
+    Search,
+    /// Moves the cursor to the next cell matching the search.
+    SearchNext,
+    /// Moves the cursor to the previous cell matching the search.
+    SearchPrevious,
+    /// Copies the selected cells, or the cell under the cursor.
+    Copy,
+    /// Copies the selected cells, or the cell under the cursor, and empties
+    /// them.
+    Cut,
+    /// Pastes the copied cells at the cursor.
+    Paste,
+    /// Undoes the last change made to the cells, records, or columns.
+    Undo,
+    /// Redoes the last change undone.
+    Redo,
+    /// Adds the cell under the cursor to the selection, or removes it.
+    ToggleSelection,
+    /// Switches between selecting cells, records, and columns.
+    CycleSelectionMode,
+    /// Switches between picking a column and moving the cursor.
+    ToggleColumnSelect,
+    /// Turns editing on or off. Views still need a parser to be edited, so
+    /// that views without one stay read-only.
+    ToggleReadOnly,
+    /// Sorts the records in ascending order by the column of the cursor.
+    SortAscending,
+    /// Sorts the records in descending order by the column of the cursor.
+    SortDescending,
+    /// Sorts the records by the column of the cursor, in ascending order
+    /// unless they are sorted that way already, as clicking its header does.
+    ToggleSort,
+    /// Adds the column of the cursor to the sort as a less significant key,
+    /// or reverses its order if the records are sorted by it already.
+    AddSortKey,
+    /// Collapses the group of the cursor, or expands it, when the records are
+    /// grouped.
+    ToggleGroup,
+    /// Shows a popup listing the bound keys.
+    ShowHelp,
+}
+
+impl SpreadsheetAction {
+    /// Every action, in the order they are listed in the help popup.
+    pub const ALL: &'static [SpreadsheetAction] = &[
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::PageUp,
+        Self::PageDown,
+        Self::PageLeft,
+        Self::PageRight,
+        Self::RowStart,
+        Self::RowEnd,
+        Self::FirstCell,
+        Self::LastCell,
+        Self::ExtendUp,
+        Self::ExtendDown,
+        Self::ExtendLeft,
+        Self::ExtendRight,
+        Self::ExtendToFirstCell,
+        Self::ExtendToLastCell,
+        Self::EditCell,
+        Self::EditCellInDialog,
+        Self::EditCellExternally,
+        Self::NextError,
+        Self::MoveColumnLeft,
+        Self::MoveColumnRight,
+        Self::NarrowColumn,
+        Self::WidenColumn,
+        Self::Search,
+        Self::SearchNext,
+        Self::SearchPrevious,
+        Self::Copy,
+        Self::Cut,
+        Self::Paste,
+        Self::Undo,
+        Self::Redo,
+        Self::ToggleSelection,
+        Self::CycleSelectionMode,
+        Self::ToggleColumnSelect,
+        Self::ToggleReadOnly,
+        Self::SortAscending,
+        Self::SortDescending,
+        Self::ToggleSort,
+        Self::AddSortKey,
+        Self::ToggleGroup,
+        Self::ShowHelp,
+    ];
+
+    /// Returns a short, human-readable description of this action.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::MoveUp => "Move up",
+            Self::MoveDown => "Move down",
+            Self::MoveLeft => "Move left",
+            Self::MoveRight => "Move right",
+            Self::PageUp => "Move up a page",
+            Self::PageDown => "Move down a page",
+            Self::PageLeft => "Move left a page",
+            Self::PageRight => "Move right a page",
+            Self::RowStart => "Move to first column",
+            Self::RowEnd => "Move to last column",
+            Self::FirstCell => "Move to first cell",
+            Self::LastCell => "Move to last cell",
+            Self::ExtendUp => "Extend selection up",
+            Self::ExtendDown => "Extend selection down",
+            Self::ExtendLeft => "Extend selection left",
+            Self::ExtendRight => "Extend selection right",
+            Self::ExtendToFirstCell => "Extend selection to first cell",
+            Self::ExtendToLastCell => "Extend selection to last cell",
+            Self::EditCell => "Edit cell",
+            Self::EditCellInDialog => "Edit cell in a dialog",
+            Self::EditCellExternally => "Edit cell in $EDITOR",
+            Self::NextError => "Jump to next invalid cell",
+            Self::MoveColumnLeft => "Move column left",
+            Self::MoveColumnRight => "Move column right",
+            Self::NarrowColumn => "Narrow column",
+            Self::WidenColumn => "Widen column",
+            Self::Search => "Search cells",
+            Self::SearchNext => "Jump to next match",
+            Self::SearchPrevious => "Jump to previous match",
+            Self::Copy => "Copy cells",
+            Self::Cut => "Cut cells",
+            Self::Paste => "Paste cells",
+            Self::Undo => "Undo",
+            Self::Redo => "Redo",
+            Self::ToggleSelection => "Select or deselect cell",
+            Self::CycleSelectionMode => "Switch between cell, row, and column selection",
+            Self::ToggleColumnSelect => "Pick a column",
+            Self::ToggleReadOnly => "Turn editing on or off",
+            Self::SortAscending => "Sort by column, ascending",
+            Self::SortDescending => "Sort by column, descending",
+            Self::ToggleSort => "Sort by column, toggling order",
+            Self::AddSortKey => "Also sort by column, toggling order",
+            Self::ToggleGroup => "Collapse or expand group",
+            Self::ShowHelp => "Show this help",
+        }
+    }
+}
+
+/// A table mapping input events to spreadsheet actions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    map: IndexMap<Event, SpreadsheetAction>,
+}
+
+impl Default for Bindings {
+    /// Creates the default bindings.
+    fn default() -> Self {
+        let mut bindings = Self::new();
+
+        bindings.bind(Event::Key(Key::Up), SpreadsheetAction::MoveUp);
+        bindings.bind(Event::Key(Key::Down), SpreadsheetAction::MoveDown);
+        bindings.bind(Event::Key(Key::Left), SpreadsheetAction::MoveLeft);
+        bindings.bind(Event::Key(Key::Right), SpreadsheetAction::MoveRight);
+        bindings.bind(Event::Key(Key::PageUp), SpreadsheetAction::PageUp);
+        bindings.bind(Event::Key(Key::PageDown), SpreadsheetAction::PageDown);
+        bindings.bind(Event::Ctrl(Key::Left), SpreadsheetAction::PageLeft);
+        bindings.bind(Event::Ctrl(Key::Right), SpreadsheetAction::PageRight);
+        bindings.bind(Event::Key(Key::Home), SpreadsheetAction::RowStart);
+        bindings.bind(Event::Key(Key::End), SpreadsheetAction::RowEnd);
+        bindings.bind(Event::Ctrl(Key::Home), SpreadsheetAction::FirstCell);
+        bindings.bind(Event::Ctrl(Key::End), SpreadsheetAction::LastCell);
+        bindings.bind(Event::Shift(Key::Up), SpreadsheetAction::ExtendUp);
+        bindings.bind(Event::Shift(Key::Down), SpreadsheetAction::ExtendDown);
+        bindings.bind(Event::Shift(Key::Left), SpreadsheetAction::ExtendLeft);
+        bindings.bind(Event::Shift(Key::Right), SpreadsheetAction::ExtendRight);
+        bindings.bind(Event::CtrlShift(Key::Home), SpreadsheetAction::ExtendToFirstCell);
+        bindings.bind(Event::CtrlShift(Key::End), SpreadsheetAction::ExtendToLastCell);
+        bindings.bind(Event::Key(Key::Enter), SpreadsheetAction::EditCell);
+        bindings.bind(Event::Key(Key::F2), SpreadsheetAction::EditCellInDialog);
+        bindings.bind(Event::CtrlChar('e'), SpreadsheetAction::EditCellExternally);
+        bindings.bind(Event::Key(Key::F8), SpreadsheetAction::NextError);
+        bindings.bind(Event::Alt(Key::Left), SpreadsheetAction::MoveColumnLeft);
+        bindings.bind(Event::Alt(Key::Right), SpreadsheetAction::MoveColumnRight);
+        bindings.bind(Event::AltShift(Key::Left), SpreadsheetAction::NarrowColumn);
+        bindings.bind(Event::AltShift(Key::Right), SpreadsheetAction::WidenColumn);
+        bindings.bind(Event::CtrlChar('f'), SpreadsheetAction::Search);
+        bindings.bind(Event::Key(Key::F3), SpreadsheetAction::SearchNext);
+        bindings.bind(Event::Shift(Key::F3), SpreadsheetAction::SearchPrevious);
+        bindings.bind(Event::CtrlChar('c'), SpreadsheetAction::Copy);
+        bindings.bind(Event::CtrlChar('x'), SpreadsheetAction::Cut);
+        bindings.bind(Event::CtrlChar('v'), SpreadsheetAction::Paste);
+        bindings.bind(Event::CtrlChar('z'), SpreadsheetAction::Undo);
+        bindings.bind(Event::CtrlChar('y'), SpreadsheetAction::Redo);
+        bindings.bind(Event::Key(Key::Ins), SpreadsheetAction::ToggleSelection);
+        bindings.bind(Event::AltChar('v'), SpreadsheetAction::CycleSelectionMode);
+        bindings.bind(Event::AltChar('c'), SpreadsheetAction::ToggleColumnSelect);
+        bindings.bind(Event::CtrlChar('r'), SpreadsheetAction::ToggleReadOnly);
+        bindings.bind(Event::AltChar('s'), SpreadsheetAction::SortAscending);
+        bindings.bind(Event::AltChar('S'), SpreadsheetAction::SortDescending);
+        bindings.bind(Event::AltChar('o'), SpreadsheetAction::ToggleSort);
+        bindings.bind(Event::AltChar('O'), SpreadsheetAction::AddSortKey);
+        bindings.bind(Event::CtrlChar('g'), SpreadsheetAction::ToggleGroup);
+        bindings.bind(Event::Key(Key::F1), SpreadsheetAction::ShowHelp);
+
+        bindings
+    }
+}
+
+impl Bindings {
+    /// Creates a table without any bindings.
+    pub fn new() -> Self {
+        Self { map: IndexMap::new() }
+    }
+
+    /// Creates the default bindings, along with keys in the style of vim:
+    /// `hjkl` to move, `HJKL` to extend the selection, `0` and `$` to go to
+    /// either end of the row, `g` and `G` to the first and the last cell,
+    /// Ctrl+B and Ctrl+F to page, `i` to edit, `y` and `p` to copy and paste,
+    /// `u` and Ctrl+R to undo and redo, `<` and `>` to resize columns, `/`,
+    /// `n`, and `N` to search, Space to select, `v` to switch what is
+    /// selected, `c` to pick a column, `s`, `S`, `o`, and `O` to sort, and `?`
+    /// for help. Editing is turned on and off with Alt+R in place of Ctrl+R.
+    /// The default bindings leave every character to typing over cells, but
+    /// here typing over cells is only left to the characters not bound to
+    /// anything.
+    pub fn vim() -> Self {
+        let mut bindings = Self::default();
+
+        bindings.bind(Event::Char('h'), SpreadsheetAction::MoveLeft);
+        bindings.bind(Event::Char('j'), SpreadsheetAction::MoveDown);
+        bindings.bind(Event::Char('k'), SpreadsheetAction::MoveUp);
+        bindings.bind(Event::Char('l'), SpreadsheetAction::MoveRight);
+        bindings.bind(Event::Char('H'), SpreadsheetAction::ExtendLeft);
+        bindings.bind(Event::Char('J'), SpreadsheetAction::ExtendDown);
+        bindings.bind(Event::Char('K'), SpreadsheetAction::ExtendUp);
+        bindings.bind(Event::Char('L'), SpreadsheetAction::ExtendRight);
+        bindings.bind(Event::Char('0'), SpreadsheetAction::RowStart);
+        bindings.bind(Event::Char('$'), SpreadsheetAction::RowEnd);
+        bindings.bind(Event::Char('g'), SpreadsheetAction::FirstCell);
+        bindings.bind(Event::Char('G'), SpreadsheetAction::LastCell);
+        bindings.bind(Event::CtrlChar('b'), SpreadsheetAction::PageUp);
+        bindings.bind(Event::CtrlChar('f'), SpreadsheetAction::PageDown);
+        bindings.bind(Event::Char('i'), SpreadsheetAction::EditCell);
+        bindings.bind(Event::Char('y'), SpreadsheetAction::Copy);
+        bindings.bind(Event::Char('p'), SpreadsheetAction::Paste);
+        bindings.bind(Event::Char('u'), SpreadsheetAction::Undo);
+        bindings.bind(Event::CtrlChar('r'), SpreadsheetAction::Redo);
+        bindings.bind(Event::AltChar('r'), SpreadsheetAction::ToggleReadOnly);
+        bindings.bind(Event::Char('<'), SpreadsheetAction::NarrowColumn);
+        bindings.bind(Event::Char('>'), SpreadsheetAction::WidenColumn);
+        bindings.bind(Event::Char('/'), SpreadsheetAction::Search);
+        bindings.bind(Event::Char('n'), SpreadsheetAction::SearchNext);
+        bindings.bind(Event::Char('N'), SpreadsheetAction::SearchPrevious);
+        bindings.bind(Event::Char(' '), SpreadsheetAction::ToggleSelection);
+        bindings.bind(Event::Char('v'), SpreadsheetAction::CycleSelectionMode);
+        bindings.bind(Event::Char('c'), SpreadsheetAction::ToggleColumnSelect);
+        bindings.bind(Event::Char('s'), SpreadsheetAction::SortAscending);
+        bindings.bind(Event::Char('S'), SpreadsheetAction::SortDescending);
+        bindings.bind(Event::Char('o'), SpreadsheetAction::ToggleSort);
+        bindings.bind(Event::Char('O'), SpreadsheetAction::AddSortKey);
+        bindings.bind(Event::Char('?'), SpreadsheetAction::ShowHelp);
+
+        bindings
+    }
+
+    /// Binds an event to an action, replacing any action previously bound to
+    /// the same event.
+    pub fn bind(&mut self, event: Event, action: SpreadsheetAction) {
+        self.map.insert(event, action);
+    }
+
+    /// Removes the binding for an event, returning the action it was bound to.
+    pub fn unbind(&mut self, event: &Event) -> Option<SpreadsheetAction> {
+        self.map.shift_remove(event)
+    }
+
+    /// Removes every binding for an action.
+    pub fn unbind_action(&mut self, action: SpreadsheetAction) {
+        self.map.retain(|_, bound| *bound != action);
+    }
+
+    /// Returns the action bound to an event, if any.
+    pub fn action(&self, event: &Event) -> Option<SpreadsheetAction> {
+        self.map.get(event).copied()
+    }
+
+    /// Returns the events bound to an action, in the order they were bound.
+    pub fn events(&self, action: SpreadsheetAction) -> impl Iterator<Item = &Event> {
+        self.map.iter().filter(move |(_, bound)| **bound == action).map(|(event, _)| event)
+    }
+
+    /// Returns every binding, in the order they were bound.
+    pub fn iter(&self) -> impl Iterator<Item = (&Event, SpreadsheetAction)> {
+        self.map.iter().map(|(event, action)| (event, *action))
+    }
+
+    /// Returns a listing of every bound action and its keys, one per line.
+    pub fn help_text(&self) -> String {
+        let lines: Vec<_> = SpreadsheetAction::ALL.iter()
+            .filter_map(|&action| {
+                let keys: Vec<_> = self.events(action).map(describe_event).collect();
+                if keys.is_empty() { None }
+                else { Some((keys.join(", "), action.description())) }
+            })
+            .collect();
+
+        let key_width = lines.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+
+        lines.iter()
+            .map(|(keys, description)| format!("{:width$}  {}", keys, description, width = key_width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Returns a human-readable name for an event, such as `Ctrl+E` or `Space`.
+pub fn describe_event(event: &Event) -> String {
+    match event {
+        Event::Char(' ') => "Space".to_string(),
+        Event::Char(c) => c.to_string(),
+        Event::CtrlChar(c) => format!("Ctrl+{}", c.to_ascii_uppercase()),
+        Event::AltChar(c) => format!("Alt+{}", c),
+        Event::Key(key) => describe_key(*key),
+        Event::Shift(key) => format!("Shift+{}", describe_key(*key)),
+        Event::Alt(key) => format!("Alt+{}", describe_key(*key)),
+        Event::AltShift(key) => format!("Alt+Shift+{}", describe_key(*key)),
+        Event::Ctrl(key) => format!("Ctrl+{}", describe_key(*key)),
+        Event::CtrlShift(key) => format!("Ctrl+Shift+{}", describe_key(*key)),
+        Event::CtrlAlt(key) => format!("Ctrl+Alt+{}", describe_key(*key)),
+        Event::Mouse { event: MouseEvent::Press(button), .. } => format!("{:?} click", button),
+        other => format!("{:?}", other),
+    }
+}
+
+fn describe_key(key: Key) -> String {
+    match key {
+        Key::Ins => "Insert".to_string(),
+        Key::Del => "Delete".to_string(),
+        Key::PageUp => "PgUp".to_string(),
+        Key::PageDown => "PgDn".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_and_unbind() {
+        let mut bindings = Bindings::default();
+        assert_eq!(bindings.action(&Event::Key(Key::F1)), Some(SpreadsheetAction::ShowHelp));
+
+        bindings.bind(Event::Char('?'), SpreadsheetAction::ShowHelp);
+        assert_eq!(bindings.events(SpreadsheetAction::ShowHelp).count(), 2);
+
+        assert_eq!(bindings.unbind(&Event::Key(Key::F1)), Some(SpreadsheetAction::ShowHelp));
+        assert_eq!(bindings.action(&Event::Key(Key::F1)), None);
+
+        bindings.unbind_action(SpreadsheetAction::ShowHelp);
+        assert_eq!(bindings.events(SpreadsheetAction::ShowHelp).count(), 0);
+    }
+
+    #[test]
+    fn vim_bindings_add_to_defaults() {
+        let bindings = Bindings::vim();
+        assert_eq!(bindings.action(&Event::Char('j')), Some(SpreadsheetAction::MoveDown));
+        assert_eq!(bindings.action(&Event::Key(Key::Down)), Some(SpreadsheetAction::MoveDown));
+        assert_eq!(bindings.action(&Event::Char('s')), Some(SpreadsheetAction::SortAscending));
+        assert_eq!(bindings.action(&Event::Char('x')), None);
+        assert!(Bindings::default().iter().all(|(event, _)| !matches!(event, Event::Char(_))));
+    }
+
+    #[test]
+    fn help_lists_bound_actions() {
+        let mut bindings = Bindings::new();
+        bindings.bind(Event::Key(Key::Enter), SpreadsheetAction::EditCell);
+        bindings.bind(Event::CtrlChar('e'), SpreadsheetAction::EditCellExternally);
+        bindings.bind(Event::Char(' '), SpreadsheetAction::EditCell);
+
+        assert_eq!(bindings.help_text(), [
+            "Enter, Space  Edit cell",
+            "Ctrl+E        Edit cell in $EDITOR",
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,137 @@
+//! Copying, cutting, and pasting rectangles of cells inside a view.
+
+use std::fmt::Display;
+
+use crate::{Record, SpreadsheetView};
+use crate::history::CellContents;
+
+/// Cells copied from a view, both as they are and as tab-separated text.
+pub(crate) struct Clipboard<D> {
+    text: String,
+    /// The contents of the copied cells, row by row.
+    cells: Vec<Vec<CellContents<D>>>,
+}
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Returns the cells copied by `copy_selection` or `cut_selection` as
+    /// tab-separated text, one row of cells per line, if any were copied.
+    pub fn clipboard_text(&self) -> Option<&str> {
+        self.clipboard.as_ref().map(|clipboard| clipboard.text.as_str())
+    }
+
+    /// Returns the cells of the smallest rectangle containing the selection,
+    /// or the cell under the cursor if nothing is selected, as tab-separated
+    /// text, one row of cells per line. Formulas are written as they were
+    /// entered. Rows hidden by the filter are left out. This is the text
+    /// `copy_selection` copies, for pushing to the clipboard of the system.
+    pub fn copy_selection_to_string(&self) -> String {
+        self.copied_cells().iter()
+            .map(|cells| {
+                cells.iter()
+                    .map(|contents| {
+                        let text = match (&contents.formula, &contents.value) {
+                            (Some(source), _) => source.clone(),
+                            (None, Some(value)) => value.to_string(),
+                            (None, None) => String::new(),
+                        };
+                        // Tabs and line breaks would split the cell.
+                        text.replace(['\t', '\r', '\n'], " ")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Copies the cells `copy_selection_to_string` returns the text of into the
+    /// clipboard of this view, replacing what was copied before. Returns
+    /// `false` if there is nothing to copy.
+    pub fn copy_selection(&mut self) -> bool {
+        let cells = self.copied_cells();
+        if cells.is_empty() {
+            return false;
+        }
+
+        let text = self.copy_selection_to_string();
+        self.clipboard = Some(Clipboard { text, cells });
+        true
+    }
+
+    /// Copies the cells like `copy_selection`, then empties the ones that are
+    /// not read-only. Returns
+    /// `false` if there is nothing to cut or the view is read-only.
+    pub fn cut_selection(&mut self) -> bool {
+        if self.read_only || self.parser.is_none() || !self.copy_selection() {
+            return false;
+        }
+
+        let cells = self.copied_positions();
+        self.update(|view| {
+            for (x, y) in cells.into_iter().flatten() {
+                if let Some(key) = view.columns.get_index(x).map(|(key, _)| key.clone()) {
+                    if !view.is_cell_read_only(y, &key) {
+                        view.write_cell_contents(y, &key, CellContents { value: None, formula: None });
+                    }
+                }
+            }
+        });
+        true
+    }
+
+    /// Pastes the cells in the clipboard of this view into the cells starting
+    /// at the cursor, as they were copied and without parsing them again.
+    /// Records are added as needed to fit every row, while cells going past
+    /// the last column or into read-only cells are dropped. Returns `false` if
+    /// nothing was copied, there is no cursor, or the view is read-only.
+    pub fn paste_clipboard(&mut self) -> bool {
+        let (column, row) = match self.cursor_pos {
+            Some(pos) if !self.read_only && self.parser.is_some() => pos,
+            _ => return false,
+        };
+        let cells = match self.clipboard.as_ref() {
+            Some(clipboard) => clipboard.cells.clone(),
+            None => return false,
+        };
+
+        let keys: Vec<String> = self.columns.keys().skip(column).cloned().collect();
+        self.update(|view| {
+            while view.records.len() < row + cells.len() {
+                view.push_record(Record::new());
+            }
+
+            for (y, cells) in (row..).zip(cells) {
+                for (key, contents) in keys.iter().zip(cells) {
+                    if !view.is_cell_read_only(y, key) {
+                        view.write_cell_contents(y, key, contents);
+                    }
+                }
+            }
+        });
+        true
+    }
+
+    /// Returns the `(column, row)` positions of the cells to copy, row by row.
+    fn copied_positions(&self) -> Vec<Vec<(usize, usize)>> {
+        let (start, end) = match (self.selection(), self.cursor_pos) {
+            (Some(range), _) => (range.start(), range.end()),
+            (None, Some(pos)) => (pos, pos),
+            (None, None) => return Vec::new(),
+        };
+
+        (start.1..=end.1)
+            .filter(|&y| self.is_record_shown(y))
+            .map(|y| (start.0..=end.0).map(|x| (x, y)).collect())
+            .collect()
+    }
+
+    fn copied_cells(&self) -> Vec<Vec<CellContents<D>>> {
+        self.copied_positions().into_iter()
+            .map(|cells| {
+                cells.into_iter()
+                    .filter_map(|(x, y)| self.columns.get_index(x).map(|(key, _)| self.cell_contents(y, key)))
+                    .collect()
+            })
+            .collect()
+    }
+}
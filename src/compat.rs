@@ -0,0 +1,193 @@
+//! Adapters for moving tables written for `cursive_table_view` over to
+//! spreadsheets.
+//!
+//! Items implementing `TableViewItem` here the same way they would for
+//! `cursive_table_view` are shown one per record, with a cell per column that
+//! displays and sorts the way the table view did.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::{ColumnDef, ColumnWidth, Record, SpreadsheetView};
+
+/// An item shown as a row of a table, with columns of type `H`, which is
+/// usually an enum. Mirrors the trait of the same name in
+/// `cursive_table_view`, so existing implementations can be reused as they
+/// are.
+pub trait TableViewItem<H>: Clone
+where
+    H: Eq + Hash + Copy + Clone + 'static,
+{
+    /// Returns the text of the given column of this item.
+    fn to_column(&self, column: H) -> String;
+
+    /// Compares this item to another by the given column.
+    fn cmp(&self, other: &Self, column: H) -> Ordering;
+}
+
+/// A cell of a spreadsheet made from table view items: one column of one
+/// item, which is shared between the cells of the record.
+pub struct TableCell<T, H> {
+    item: Rc<T>,
+    column: H,
+}
+
+impl<T, H> TableCell<T, H>
+where
+    T: TableViewItem<H>,
+    H: Eq + Hash + Copy + Clone + 'static,
+{
+    /// Returns the item this cell is part of.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+
+    /// Returns the column of the item this cell shows.
+    pub fn column(&self) -> H {
+        self.column
+    }
+}
+
+impl<T, H: Copy> Clone for TableCell<T, H> {
+    fn clone(&self) -> Self {
+        Self { item: self.item.clone(), column: self.column }
+    }
+}
+
+impl<T, H> fmt::Display for TableCell<T, H>
+where
+    T: TableViewItem<H>,
+    H: Eq + Hash + Copy + Clone + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.item.to_column(self.column))
+    }
+}
+
+impl<T, H> PartialEq for TableCell<T, H>
+where
+    T: TableViewItem<H>,
+    H: Eq + Hash + Copy + Clone + 'static,
+{
+    fn eq(&self, other: &Self) -> bool {
+        Ord::cmp(self, other) == Ordering::Equal
+    }
+}
+
+impl<T, H> Eq for TableCell<T, H>
+where
+    T: TableViewItem<H>,
+    H: Eq + Hash + Copy + Clone + 'static,
+{}
+
+impl<T, H> PartialOrd for TableCell<T, H>
+where
+    T: TableViewItem<H>,
+    H: Eq + Hash + Copy + Clone + 'static,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(Ord::cmp(self, other))
+    }
+}
+
+impl<T, H> Ord for TableCell<T, H>
+where
+    T: TableViewItem<H>,
+    H: Eq + Hash + Copy + Clone + 'static,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        TableViewItem::cmp(&*self.item, &other.item, self.column)
+    }
+}
+
+impl<T, H> SpreadsheetView<TableCell<T, H>>
+where
+    T: TableViewItem<H>,
+    H: Eq + Hash + Copy + Clone + 'static,
+{
+    /// Creates a view showing items written for `cursive_table_view`, with the
+    /// given columns and their titles, which also serve as the column keys.
+    /// Columns with a width are fixed to it, as with `TableColumn::width`.
+    pub fn from_table_view_columns<I>(columns: &[(H, &str, Option<usize>)], items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut view = Self::new();
+        for &(_, title, width) in columns {
+            let mut def = ColumnDef::new(title);
+            if let Some(width) = width {
+                def.width = ColumnWidth::Fixed(width);
+            }
+            view.push_column(title.to_string(), def);
+        }
+
+        view.extend_records(items.into_iter().map(|item| {
+            let item = Rc::new(item);
+            columns.iter()
+                .map(|&(column, title, _)| (title.to_string(), TableCell { item: item.clone(), column }))
+                .collect::<Record<_>>()
+        }));
+
+        view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::vec::Vec2;
+
+    #[derive(Copy, Clone, PartialEq, Eq, Hash)]
+    enum FileColumn {
+        Name,
+        Size,
+    }
+
+    #[derive(Clone)]
+    struct File {
+        name: &'static str,
+        size: u64,
+    }
+
+    impl TableViewItem<FileColumn> for File {
+        fn to_column(&self, column: FileColumn) -> String {
+            match column {
+                FileColumn::Name => self.name.to_string(),
+                FileColumn::Size => format!("{} KB", self.size),
+            }
+        }
+
+        fn cmp(&self, other: &Self, column: FileColumn) -> Ordering {
+            match column {
+                FileColumn::Name => self.name.cmp(other.name),
+                FileColumn::Size => self.size.cmp(&other.size),
+            }
+        }
+    }
+
+    #[test]
+    fn shows_and_sorts_table_view_items() {
+        let files = vec![
+            File { name: "a.txt", size: 120 },
+            File { name: "b.txt", size: 9 },
+        ];
+        let columns = [(FileColumn::Name, "Name", None), (FileColumn::Size, "Size", Some(6))];
+        let mut view = SpreadsheetView::from_table_view_columns(&columns, files);
+
+        // Sizes sort as numbers, not as the text shown.
+        view.sort_records("Size", true);
+        assert_eq!(view.render_to_string(Vec2::new(20, 4)), [
+            "Name  │ Size ▲",
+            "────────────────────",
+            "b.txt │ 9 KB",
+            "a.txt │ 120 KB",
+        ].join("\n"));
+
+        let cell = &view.records[0]["Name"];
+        assert_eq!(cell.item().size, 9);
+        assert!(cell.column() == FileColumn::Name);
+    }
+}
@@ -0,0 +1,172 @@
+//! Reading and writing records as CSV.
+//!
+//! The first record of a CSV file holds the column keys, and every record
+//! after it one record of the view. Empty fields stand for missing cells.
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::mem;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{ColumnDef, Record, SpreadsheetView};
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Creates a view from CSV data, with one column per header. The headers
+    /// are used as both the keys and the titles of the columns, and the
+    /// fields are parsed into cell values with `FromStr`.
+    pub fn from_csv_reader<R: Read>(mut reader: R) -> io::Result<Self>
+    where
+        D: FromStr,
+        D::Err: Display,
+    {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        let mut records = split_records(&text).map(parse_record);
+        let headers = records.next().unwrap_or_default();
+
+        let mut view = Self::new();
+        for header in &headers {
+            view.push_column(header.clone(), ColumnDef::new(header));
+        }
+
+        for (i, fields) in records.enumerate() {
+            if fields.len() > headers.len() {
+                let message = format!("Record {} has {} fields, but there are only {} columns", i + 1, fields.len(), headers.len());
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            }
+
+            let mut record = Record::new();
+            for (key, field) in headers.iter().zip(fields).filter(|(_, field)| !field.is_empty()) {
+                let value = field.parse::<D>().map_err(|err| {
+                    let message = format!("Record {}, column {}: {}", i + 1, key, err);
+                    io::Error::new(io::ErrorKind::InvalidData, message)
+                })?;
+                record.insert(key.clone(), value);
+            }
+            view.push_record(record);
+        }
+
+        Ok(view)
+    }
+
+    /// Writes the records as CSV, in their current order, preceded by a
+    /// header of column keys.
+    pub fn to_csv_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let keys: Vec<&str> = self.columns.keys().map(String::as_str).collect();
+        write_record(&mut writer, keys.iter().copied())?;
+
+        for record in &self.records {
+            let fields: Vec<String> = keys.iter()
+                .map(|&key| record.get(key).map(|value| value.to_string()).unwrap_or_default())
+                .collect();
+            write_record(&mut writer, fields.iter().map(String::as_str))?;
+        }
+
+        writer.flush()
+    }
+
+    /// Writes the records as CSV to the file at the given path, replacing it.
+    /// Handy for binding to a key through `Cursive::call_on_name`.
+    pub fn export_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.to_csv_writer(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Splits CSV text into its records. Line breaks inside quoted fields do not
+/// end records, and blank lines are skipped.
+fn split_records(text: &str) -> impl Iterator<Item = &str> {
+    let mut in_quotes = false;
+    text.split(move |c| {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        c == '\n' && !in_quotes
+    })
+    .filter(|record| !record.trim_end_matches('\r').is_empty())
+}
+
+/// Splits one CSV record into its fields, unquoting them.
+pub(crate) fn parse_record(record: &str) -> Vec<String> {
+    let record = record.strip_suffix('\n').unwrap_or(record);
+    let record = record.strip_suffix('\r').unwrap_or(record);
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = record.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            },
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    fields.push(field);
+    fields
+}
+
+/// Writes one CSV record, quoting the fields that need it.
+fn write_record<'a, W, I>(writer: &mut W, fields: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a str>,
+{
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        }
+        else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+
+    writer.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_quoted_fields() {
+        assert_eq!(parse_record("a,\"b,c\",\"say \"\"hi\"\"\"\r\n"), ["a", "b,c", "say \"hi\""]);
+        assert_eq!(parse_record(",,"), ["", "", ""]);
+    }
+
+    #[test]
+    fn round_trips_records() {
+        let text = "name,note\nalpha,\"one, two\"\nbeta,\n\"gam\"\"ma\",\"multi\nline\"\n";
+        let view = SpreadsheetView::<String>::from_csv_reader(text.as_bytes()).unwrap();
+        assert_eq!(view.columns["note"].title, "note");
+        assert_eq!(view.records.len(), 3);
+        assert_eq!(view.records[2]["name"], "gam\"ma");
+        assert!(!view.records[1].contains_key("note"));
+
+        let mut out = Vec::new();
+        view.to_csv_writer(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), text);
+    }
+
+    #[test]
+    fn reports_bad_fields() {
+        let err = SpreadsheetView::<u32>::from_csv_reader("n\n1\nx\n".as_bytes()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("Record 2, column n: "));
+
+        assert!(SpreadsheetView::<u32>::from_csv_reader("n\n1,2\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+//! Browsing CSV files too large to load into memory.
+//!
+//! Opening a file only indexes where each of its records starts; the records
+//! themselves are read from disk as they scroll into view.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+
+use cursive::Printer;
+use cursive::align::HAlign;
+use cursive::direction::Direction;
+use cursive::event::{Event, EventResult, Key};
+use cursive::theme::ColorStyle;
+use cursive::vec::Vec2;
+use cursive::view::View;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{COLUMN_SEPARATOR, HEADER_HEIGHT};
+use crate::csv::parse_record;
+use crate::text::{display_text, print_aligned};
+
+/// Number of records at the start of the file used to size the columns.
+const SAMPLE_RECORDS: usize = 100;
+
+/// Widest a column is sized to fit its contents.
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// A CSV file on disk, with an index of where each of its records starts.
+/// The first record holds the column titles.
+pub struct CsvFile {
+    reader: RefCell<BufReader<File>>,
+    headers: Vec<String>,
+    /// The byte offset of every record after the header, followed by the
+    /// length of the file.
+    offsets: Vec<u64>,
+}
+
+impl CsvFile {
+    /// Opens and indexes the CSV file at the given path, reading the column
+    /// titles from its first record.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let offsets = index_records(&mut reader)?;
+
+        let mut file = Self { reader: RefCell::new(reader), headers: Vec::new(), offsets };
+        if !file.is_empty() {
+            file.headers = file.read_record(0)?;
+            file.offsets.remove(0);
+        }
+        Ok(file)
+    }
+
+    /// Returns the column titles.
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Returns the number of records after the header.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns `true` if there are no records after the header.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the fields of the record at the given index.
+    pub fn read_record(&self, index: usize) -> io::Result<Vec<String>> {
+        Ok(self.read_records(index..index + 1)?.pop().unwrap_or_default())
+    }
+
+    /// Reads the fields of the records in the given range of indices, which is
+    /// cut short at the end of the file.
+    pub fn read_records(&self, range: Range<usize>) -> io::Result<Vec<Vec<String>>> {
+        let end = range.end.min(self.len());
+        if range.start >= end {
+            return Ok(Vec::new());
+        }
+
+        let (from, to) = (self.offsets[range.start], self.offsets[end]);
+        let mut bytes = Vec::with_capacity((to - from) as usize);
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(from))?;
+        reader.by_ref().take(to - from).read_to_end(&mut bytes)?;
+
+        Ok(self.offsets[range.start..=end].windows(2)
+            .map(|w| {
+                let record = &bytes[(w[0] - from) as usize..(w[1] - from) as usize];
+                parse_record(&String::from_utf8_lossy(record))
+            })
+            .collect())
+    }
+}
+
+/// Returns the byte offset of every record in a CSV file, followed by the
+/// length of the file. Line breaks inside quoted fields do not end records,
+/// and a trailing line break does not start one.
+fn index_records<R: BufRead>(reader: &mut R) -> io::Result<Vec<u64>> {
+    let mut offsets = vec![0];
+    let (mut position, mut in_quotes) = (0u64, false);
+
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+
+        for &byte in buffer {
+            position += 1;
+            match byte {
+                b'"' => in_quotes = !in_quotes,
+                b'\n' if !in_quotes => offsets.push(position),
+                _ => {},
+            }
+        }
+
+        let len = buffer.len();
+        reader.consume(len);
+    }
+
+    if offsets.last() != Some(&position) {
+        offsets.push(position);
+    }
+    Ok(offsets)
+}
+
+/// A read-only view of a `CsvFile`, which reads only the records it shows.
+/// Up, Down, PgUp, PgDn, Home, and End move the cursor between records.
+pub struct CsvFileView {
+    file: CsvFile,
+    widths: Vec<usize>,
+    /// The index of the first record shown.
+    top: usize,
+    cursor: usize,
+    /// The number of records shown, as of the last layout.
+    height: usize,
+    /// The records read for the last draw, starting at the given index, or
+    /// the error reading them.
+    window: RefCell<(usize, io::Result<Vec<Vec<String>>>)>,
+}
+
+impl CsvFileView {
+    /// Creates a view of the given file, sizing its columns to fit their
+    /// titles and the first records.
+    pub fn new(file: CsvFile) -> Self {
+        let mut widths: Vec<usize> = file.headers().iter().map(|title| display_text(title).width()).collect();
+        if let Ok(records) = file.read_records(0..SAMPLE_RECORDS) {
+            for record in records {
+                for (i, field) in record.iter().enumerate() {
+                    if i < widths.len() {
+                        widths[i] = widths[i].max(display_text(field).width().min(MAX_COLUMN_WIDTH));
+                    }
+                }
+            }
+        }
+
+        Self {
+            file,
+            widths,
+            top: 0,
+            cursor: 0,
+            height: 0,
+            window: RefCell::new((0, Ok(Vec::new()))),
+        }
+    }
+
+    /// Returns the file shown by this view.
+    pub fn file(&self) -> &CsvFile {
+        &self.file
+    }
+
+    /// Returns the index of the record under the cursor.
+    pub fn cursor_row(&self) -> usize {
+        self.cursor
+    }
+
+    /// Moves the cursor to the record at the given index, scrolling it into
+    /// view.
+    pub fn set_cursor_row(&mut self, row: usize) {
+        self.cursor = row.min(self.file.len().saturating_sub(1));
+        if self.cursor < self.top {
+            self.top = self.cursor;
+        }
+        else if self.height > 0 && self.cursor >= self.top + self.height {
+            self.top = self.cursor + 1 - self.height;
+        }
+    }
+
+    /// Sets the width of the column at the given index.
+    pub fn set_column_width(&mut self, index: usize, width: usize) {
+        if let Some(w) = self.widths.get_mut(index) {
+            *w = width;
+        }
+    }
+
+    /// Reads the records shown, unless they were read for the last draw.
+    fn refresh_window(&self) {
+        let mut window = self.window.borrow_mut();
+        let visible = self.top..(self.top + self.height).min(self.file.len());
+        let current = match &window.1 {
+            Ok(records) => window.0 == visible.start && records.len() == visible.len(),
+            Err(_) => false,
+        };
+
+        if !current {
+            *window = (visible.start, self.file.read_records(visible));
+        }
+    }
+
+    fn draw_fields<S: AsRef<str>>(&self, printer: &Printer, fields: &[S]) {
+        let mut x = 0;
+        for (i, &width) in self.widths.iter().enumerate() {
+            if i > 0 {
+                printer.print((x, 0), COLUMN_SEPARATOR);
+                x += COLUMN_SEPARATOR.width();
+            }
+
+            let text = fields.get(i).map(|field| display_text(field.as_ref())).unwrap_or_default();
+            print_aligned(&printer.offset((x, 0)), &text, width, &HAlign::Left);
+            x += width;
+        }
+    }
+}
+
+impl View for CsvFileView {
+    fn draw(&self, printer: &Printer) {
+        self.draw_fields(printer, self.file.headers());
+        printer.print_hline((0, 1), printer.size.x, "─");
+
+        self.refresh_window();
+        let window = self.window.borrow();
+        let records = match &window.1 {
+            Ok(records) => records,
+            Err(err) => {
+                printer.print((0, HEADER_HEIGHT), &format!("Error reading file: {}", err));
+                return;
+            },
+        };
+
+        for (i, record) in records.iter().enumerate() {
+            let row = window.0 + i;
+            let printer = printer.offset((0, HEADER_HEIGHT + i));
+            if row == self.cursor {
+                let style = if printer.focused { ColorStyle::highlight() } else { ColorStyle::highlight_inactive() };
+                printer.with_color(style, |p| self.draw_fields(p, record));
+            }
+            else {
+                self.draw_fields(&printer, record);
+            }
+        }
+    }
+
+    fn layout(&mut self, size: Vec2) {
+        self.height = size.y.saturating_sub(HEADER_HEIGHT);
+        self.set_cursor_row(self.cursor);
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        let separators = self.widths.len().saturating_sub(1) * COLUMN_SEPARATOR.width();
+        let width = self.widths.iter().sum::<usize>() + separators;
+        Vec2::new(width, HEADER_HEIGHT + self.file.len()).or_min(constraint)
+    }
+
+    fn take_focus(&mut self, _: Direction) -> bool {
+        !self.file.is_empty()
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        let page = self.height.max(1);
+        let row = match event {
+            Event::Key(Key::Up) if self.cursor > 0 => self.cursor - 1,
+            Event::Key(Key::Down) if self.cursor + 1 < self.file.len() => self.cursor + 1,
+            Event::Key(Key::PageUp) => self.cursor.saturating_sub(page),
+            Event::Key(Key::PageDown) => self.cursor + page,
+            Event::Key(Key::Home) => 0,
+            Event::Key(Key::End) => self.file.len(),
+            _ => return EventResult::Ignored,
+        };
+
+        self.set_cursor_row(row);
+        EventResult::Consumed(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    use crate::render::render_to_string;
+
+    #[test]
+    fn reads_only_visible_records() {
+        let path = env::temp_dir().join(format!("spreadsheet-csv-file-{}.csv", std::process::id()));
+        let mut text = String::from("id,note\n1,\"two\nlines\"\n");
+        for i in 2..=50 {
+            text.push_str(&format!("{},n{}\n", i, i));
+        }
+        fs::write(&path, text).unwrap();
+
+        let file = CsvFile::open(&path).unwrap();
+        assert_eq!(file.headers(), ["id", "note"]);
+        assert_eq!(file.len(), 50);
+        assert_eq!(file.read_record(0).unwrap(), ["1", "two\nlines"]);
+        assert_eq!(file.read_record(49).unwrap(), ["50", "n50"]);
+        assert!(file.read_records(49..60).unwrap().len() == 1);
+
+        let mut view = CsvFileView::new(file);
+        view.set_column_width(1, 4);
+        view.on_event(Event::Key(Key::End));
+        assert_eq!(render_to_string(&mut view, Vec2::new(12, 4)), [
+            "id │ note",
+            "────────────",
+            "49 │ n49",
+            "50 │ n50",
+        ].join("\n"));
+        assert_eq!(view.window.borrow().1.as_ref().unwrap().len(), 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
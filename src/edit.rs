@@ -0,0 +1,528 @@
+//! Editors used to change the contents of a single cell.
+
+use cursive::Printer;
+use cursive::event::{Event, EventResult, Key};
+use cursive::traits::{Finder, Nameable, Resizable};
+use cursive::vec::Vec2;
+use cursive::view::View;
+use cursive::views::{Dialog, DialogFocus, EditView, LinearLayout, TextArea, TextView};
+use unicode_width::UnicodeWidthStr;
+
+const TEXT_AREA_NAME: &str = "spreadsheet_edit_text";
+const ERROR_NAME: &str = "spreadsheet_edit_error";
+
+/// The result of feeding an event to an editor.
+pub(crate) enum EditOutcome {
+    /// The editor stays open, having handled the event with this result.
+    Pending(EventResult),
+    /// The user asked to write the given text back into the cell.
+    Commit(String),
+    /// The user abandoned the edit.
+    Cancel,
+}
+
+/// An editor that is currently open on a cell.
+pub(crate) struct ActiveEdit {
+    pub row: usize,
+    pub key: String,
+    pub editor: Editor,
+    /// The message shown next to an inline editor whose text was rejected,
+    /// until the next key is pressed in it.
+    pub error: Option<String>,
+}
+
+pub(crate) enum Editor {
+    Dialog(DialogEditor),
+    Text(TextEditor),
+    Spinner(SpinnerEditor),
+    #[cfg(feature = "chrono")]
+    Date(DateEditor),
+}
+
+impl Editor {
+    /// Returns `true` if this editor is drawn inside the cell being edited,
+    /// rather than over the whole view.
+    pub fn is_inline(&self) -> bool {
+        match self {
+            Self::Dialog(_) => false,
+            Self::Text(_) => true,
+            Self::Spinner(_) => true,
+            #[cfg(feature = "chrono")]
+            Self::Date(_) => true,
+        }
+    }
+
+    /// Lays out this editor. Inline editors are given the size of the cell
+    /// being edited, others the size of the whole body of the view.
+    pub fn layout(&mut self, available: Vec2) {
+        match self {
+            Self::Dialog(e) => e.layout(available),
+            Self::Text(e) => e.layout(available),
+            Self::Spinner(_) => {},
+            #[cfg(feature = "chrono")]
+            Self::Date(_) => {},
+        }
+    }
+
+    /// Draws this editor. Inline editors are given a printer for the cell
+    /// being edited, others a printer for the whole body of the view.
+    pub fn draw(&self, printer: &Printer) {
+        match self {
+            Self::Dialog(e) => e.draw(printer),
+            Self::Text(e) => e.draw(printer),
+            Self::Spinner(e) => e.draw(printer),
+            #[cfg(feature = "chrono")]
+            Self::Date(e) => e.draw(printer),
+        }
+    }
+
+    pub fn on_event(&mut self, event: Event) -> EditOutcome {
+        match self {
+            Self::Dialog(e) => e.on_event(event),
+            Self::Text(e) => e.on_event(event),
+            Self::Spinner(e) => e.on_event(event),
+            #[cfg(feature = "chrono")]
+            Self::Date(e) => e.on_event(event),
+        }
+    }
+
+    /// Shows an error, e.g. from a failed parse, and keeps the editor open.
+    pub fn set_error(&mut self, message: &str) {
+        match self {
+            Self::Dialog(e) => e.set_error(message),
+            // There is no room to show a message inside a cell.
+            Self::Text(_) | Self::Spinner(_) => {},
+            #[cfg(feature = "chrono")]
+            Self::Date(_) => {},
+        }
+    }
+}
+
+/// A modal dialog holding the full value of a cell in a multi-line editor.
+///
+/// The dialog is hosted by the spreadsheet itself and drawn centered over it,
+/// so that committing writes straight back into the cell being edited.
+pub(crate) struct DialogEditor {
+    dialog: Dialog,
+    offset: Vec2,
+    size: Vec2,
+}
+
+impl DialogEditor {
+    pub fn new(title: &str, content: String, available: Vec2) -> Self {
+        // Leave room for the dialog borders, padding, and the button row.
+        let longest = content.lines().map(UnicodeWidthStr::width).max().unwrap_or(0);
+        let num_lines = content.lines().count();
+        let width = (longest + 1).max(30).min(available.x.saturating_sub(6).max(1));
+        let height = (num_lines + 1).max(3).min(available.y.saturating_sub(8).max(1));
+
+        let text_area = TextArea::new()
+            .content(content)
+            .with_name(TEXT_AREA_NAME)
+            .min_size((width, height));
+
+        let dialog = Dialog::around(
+            LinearLayout::vertical()
+                .child(text_area)
+                .child(TextView::new("").with_name(ERROR_NAME)),
+        )
+        .title(title)
+        .button("OK", |_| {})
+        .button("Cancel", |_| {});
+
+        Self {
+            dialog,
+            offset: Vec2::zero(),
+            size: Vec2::zero(),
+        }
+    }
+
+    /// Returns the text currently in the editor.
+    pub fn content(&mut self) -> String {
+        self.dialog
+            .call_on_name(TEXT_AREA_NAME, |t: &mut TextArea| t.get_content().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn set_error(&mut self, message: &str) {
+        self.dialog.call_on_name(ERROR_NAME, |t: &mut TextView| t.set_content(message));
+    }
+
+    pub fn layout(&mut self, available: Vec2) {
+        let size = self.dialog.required_size(available).or_min(available);
+        self.offset = available.saturating_sub(size) / 2;
+        self.size = size;
+        self.dialog.layout(size);
+    }
+
+    pub fn draw(&self, printer: &Printer) {
+        self.dialog.draw(&printer.offset(self.offset).cropped(self.size));
+    }
+
+    pub fn on_event(&mut self, event: Event) -> EditOutcome {
+        if event == Event::Key(Key::Esc) {
+            return EditOutcome::Cancel;
+        }
+
+        let result = self.dialog.on_event(event.relativized(self.offset));
+
+        // The buttons themselves do nothing: pressing one (by key or mouse)
+        // just yields its callback while the button has focus.
+        match (&result, self.dialog.focus()) {
+            (EventResult::Consumed(Some(_)), DialogFocus::Button(0)) => {
+                EditOutcome::Commit(self.content())
+            },
+            (EventResult::Consumed(Some(_)), DialogFocus::Button(_)) => EditOutcome::Cancel,
+            _ => EditOutcome::Pending(result),
+        }
+    }
+}
+
+/// An inline editor for the text of a single-line cell, drawn inside the cell.
+/// Enter and Tab commit the edit, and Esc abandons it.
+pub(crate) struct TextEditor {
+    edit_view: EditView,
+}
+
+impl TextEditor {
+    /// Creates a text editor starting out with the given text, with the
+    /// cursor at its end.
+    pub fn new(content: String, width: usize) -> Self {
+        let mut edit_view = EditView::new().content(content);
+        edit_view.layout(Vec2::new(width, 1));
+        Self { edit_view }
+    }
+
+    pub fn layout(&mut self, available: Vec2) {
+        self.edit_view.layout(Vec2::new(available.x, 1));
+    }
+
+    pub fn draw(&self, printer: &Printer) {
+        self.edit_view.draw(printer);
+    }
+
+    pub fn on_event(&mut self, event: Event) -> EditOutcome {
+        match event {
+            Event::Key(Key::Enter) | Event::Key(Key::Tab) => {
+                EditOutcome::Commit(self.edit_view.get_content().to_string())
+            },
+            Event::Key(Key::Esc) => EditOutcome::Cancel,
+            event => EditOutcome::Pending(self.edit_view.on_event(event)),
+        }
+    }
+}
+
+/// Settings for editing a numeric column by stepping its value up and down,
+/// instead of retyping it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spinner {
+    step: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl Default for Spinner {
+    /// Creates a spinner that steps by 1 without any bounds.
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl Spinner {
+    /// Creates a spinner that steps by the given amount without any bounds.
+    pub fn new(step: f64) -> Self {
+        Self { step, min: None, max: None }
+    }
+
+    /// Sets the lowest value the spinner can reach.
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Sets the highest value the spinner can reach.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Returns the amount added or removed by each step.
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    fn clamp(&self, value: f64) -> f64 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
+/// Marker drawn at the end of a cell being edited with a spinner.
+const SPINNER_MARKER: &str = "↕";
+
+/// An inline editor that steps a numeric value up and down.
+pub(crate) struct SpinnerEditor {
+    spinner: Spinner,
+    value: f64,
+    decimals: usize,
+}
+
+impl SpinnerEditor {
+    /// Creates a spinner editor starting at the value in `text`. An empty cell
+    /// starts at zero, clamped to the bounds of the spinner. Returns `None` if
+    /// the text is not a number.
+    pub fn new(spinner: Spinner, text: &str) -> Option<Self> {
+        let text = text.trim();
+        let value = if text.is_empty() { 0.0 } else { text.parse::<f64>().ok()? };
+
+        // Keep as many decimals as the more precise of the value and the step.
+        let decimals = decimals(text).max(decimals(&spinner.step.to_string()));
+
+        Some(Self { spinner, value: spinner.clamp(value), decimals })
+    }
+
+    /// Returns the current value, formatted for writing back into the cell.
+    pub fn content(&self) -> String {
+        format!("{:.*}", self.decimals, self.value)
+    }
+
+    fn nudge(&mut self, steps: f64) {
+        self.value = self.spinner.clamp(self.value + steps * self.spinner.step);
+    }
+
+    pub fn draw(&self, printer: &Printer) {
+        let marker_width = SPINNER_MARKER.width();
+        let text_width = printer.size.x.saturating_sub(marker_width);
+        let content = self.content();
+
+        printer.print_hline((0, 0), printer.size.x, " ");
+        printer.print((text_width.saturating_sub(content.width()), 0), &content);
+        printer.print((text_width, 0), SPINNER_MARKER);
+    }
+
+    pub fn on_event(&mut self, event: Event) -> EditOutcome {
+        match event {
+            Event::Key(Key::Up) | Event::Char('+') => self.nudge(1.0),
+            Event::Key(Key::Down) | Event::Char('-') => self.nudge(-1.0),
+            Event::Key(Key::PageUp) => self.nudge(10.0),
+            Event::Key(Key::PageDown) => self.nudge(-10.0),
+            Event::Key(Key::Enter) => return EditOutcome::Commit(self.content()),
+            Event::Key(Key::Esc) => return EditOutcome::Cancel,
+            _ => return EditOutcome::Pending(EventResult::Ignored),
+        }
+
+        EditOutcome::Pending(EventResult::Consumed(None))
+    }
+}
+
+/// Settings for editing a date column with a structured year/month/day picker.
+#[cfg(feature = "chrono")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatePicker {
+    format: String,
+}
+
+#[cfg(feature = "chrono")]
+impl Default for DatePicker {
+    /// Creates a date picker that reads and writes ISO 8601 dates.
+    fn default() -> Self {
+        Self::new("%Y-%m-%d")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl DatePicker {
+    /// Creates a date picker that reads and writes dates using the given
+    /// `chrono` format string.
+    pub fn new<S: Into<String>>(format: S) -> Self {
+        Self { format: format.into() }
+    }
+
+    /// Returns the format string used to read and write dates.
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum DateField {
+    Year,
+    Month,
+    Day,
+}
+
+/// An inline editor that changes a date one field at a time: Left/Right pick
+/// the year, month, or day, and Up/Down (or +/-) step the picked field.
+#[cfg(feature = "chrono")]
+pub(crate) struct DateEditor {
+    picker: DatePicker,
+    date: chrono::NaiveDate,
+    field: DateField,
+}
+
+#[cfg(feature = "chrono")]
+impl DateEditor {
+    /// Creates a date editor starting at the date in `text`. An empty cell
+    /// starts at today's date. Returns `None` if the text is not a date in the
+    /// format of the picker.
+    pub fn new(picker: DatePicker, text: &str) -> Option<Self> {
+        let text = text.trim();
+        let date = if text.is_empty() { chrono::Local::now().date_naive() }
+        else { chrono::NaiveDate::parse_from_str(text, &picker.format).ok()? };
+
+        Some(Self { picker, date, field: DateField::Day })
+    }
+
+    /// Returns the current date, formatted for writing back into the cell.
+    pub fn content(&self) -> String {
+        self.date.format(&self.picker.format).to_string()
+    }
+
+    fn nudge(&mut self, steps: i32) {
+        use chrono::Datelike;
+
+        let (year, month) = (self.date.year(), self.date.month() as i32);
+        let nudged = match self.field {
+            DateField::Day => self.date.checked_add_signed(chrono::Duration::days(steps.into())),
+            DateField::Month => {
+                let months = year * 12 + month - 1 + steps;
+                with_clamped_day(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, self.date.day())
+            },
+            DateField::Year => with_clamped_day(year + steps, month as u32, self.date.day()),
+        };
+
+        if let Some(date) = nudged {
+            self.date = date;
+        }
+    }
+
+    pub fn draw(&self, printer: &Printer) {
+        use chrono::Datelike;
+
+        let fields = [
+            (DateField::Year, format!("{:04}", self.date.year())),
+            (DateField::Month, format!("{:02}", self.date.month())),
+            (DateField::Day, format!("{:02}", self.date.day())),
+        ];
+
+        printer.print_hline((0, 0), printer.size.x, " ");
+
+        let mut x = 0;
+        for (i, (field, text)) in fields.iter().enumerate() {
+            if i > 0 {
+                printer.print((x, 0), "-");
+                x += 1;
+            }
+
+            if *field == self.field {
+                printer.with_effect(cursive::theme::Effect::Reverse, |p| p.print((x, 0), text));
+            }
+            else {
+                printer.print((x, 0), text);
+            }
+
+            x += text.width();
+        }
+    }
+
+    pub fn on_event(&mut self, event: Event) -> EditOutcome {
+        match event {
+            Event::Key(Key::Up) | Event::Char('+') => self.nudge(1),
+            Event::Key(Key::Down) | Event::Char('-') => self.nudge(-1),
+            Event::Key(Key::Left) => {
+                self.field = match self.field {
+                    DateField::Day => DateField::Month,
+                    _ => DateField::Year,
+                };
+            },
+            Event::Key(Key::Right) => {
+                self.field = match self.field {
+                    DateField::Year => DateField::Month,
+                    _ => DateField::Day,
+                };
+            },
+            Event::Key(Key::Enter) => return EditOutcome::Commit(self.content()),
+            Event::Key(Key::Esc) => return EditOutcome::Cancel,
+            _ => return EditOutcome::Pending(EventResult::Ignored),
+        }
+
+        EditOutcome::Pending(EventResult::Consumed(None))
+    }
+}
+
+/// Builds a date, moving the day back to the end of the month if needed (e.g.
+/// stepping the month of January 31st lands on the last day of February).
+#[cfg(feature = "chrono")]
+fn with_clamped_day(year: i32, month: u32, day: u32) -> Option<chrono::NaiveDate> {
+    (1..=day).rev().find_map(|d| chrono::NaiveDate::from_ymd_opt(year, month, d))
+}
+
+/// Returns the number of digits after the decimal point in `text`.
+fn decimals(text: &str) -> usize {
+    text.find('.').map_or(0, |i| text.len() - i - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut SpinnerEditor, event: Event, times: usize) {
+        for _ in 0..times {
+            editor.on_event(event.clone());
+        }
+    }
+
+    #[test]
+    fn spinner_steps_and_clamps() {
+        let spinner = Spinner::new(5.0).min(0.0).max(20.0);
+        let mut editor = SpinnerEditor::new(spinner, "12").unwrap();
+
+        press(&mut editor, Event::Key(Key::Up), 1);
+        assert_eq!(editor.content(), "17");
+
+        press(&mut editor, Event::Char('+'), 3);
+        assert_eq!(editor.content(), "20");
+
+        press(&mut editor, Event::Char('-'), 10);
+        assert_eq!(editor.content(), "0");
+    }
+
+    #[test]
+    fn spinner_keeps_precision() {
+        let mut editor = SpinnerEditor::new(Spinner::new(0.25), "1.5").unwrap();
+        press(&mut editor, Event::Key(Key::Down), 1);
+        assert_eq!(editor.content(), "1.25");
+
+        let mut editor = SpinnerEditor::new(Spinner::new(1.0), "2.50").unwrap();
+        press(&mut editor, Event::Key(Key::Up), 1);
+        assert_eq!(editor.content(), "3.50");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn date_editor_steps_fields() {
+        let mut editor = DateEditor::new(DatePicker::new("%d/%m/%Y"), "31/01/2020").unwrap();
+
+        editor.on_event(Event::Key(Key::Left));
+        editor.on_event(Event::Key(Key::Up));
+        assert_eq!(editor.content(), "29/02/2020");
+
+        editor.on_event(Event::Key(Key::Left));
+        editor.on_event(Event::Char('+'));
+        assert_eq!(editor.content(), "28/02/2021");
+
+        editor.on_event(Event::Key(Key::Right));
+        editor.on_event(Event::Key(Key::Right));
+        editor.on_event(Event::Key(Key::Down));
+        assert_eq!(editor.content(), "27/02/2021");
+
+        assert!(DateEditor::new(DatePicker::default(), "31/01/2020").is_none());
+    }
+
+    #[test]
+    fn spinner_rejects_non_numbers() {
+        assert!(SpinnerEditor::new(Spinner::default(), "abc").is_none());
+        assert_eq!(SpinnerEditor::new(Spinner::default().min(3.0), "").unwrap().content(), "3");
+    }
+}
@@ -0,0 +1,105 @@
+//! Reaching views added to a Cursive root by name, without looking them up and
+//! naming their type at every call.
+
+use std::fmt::Display;
+
+use cursive::Cursive;
+
+use crate::{Record, SpreadsheetView};
+
+/// Shortcuts for updating a named `SpreadsheetView` from callbacks elsewhere in
+/// the UI, implemented for `Cursive`. Each one does nothing and returns `None`
+/// or `false` if there is no view of the given name holding values of type `D`.
+pub trait SpreadsheetViewExt {
+    /// Calls the given closure on the view with the given name.
+    fn call_on_spreadsheet<D, R, F>(&mut self, name: &str, f: F) -> Option<R>
+    where
+        D: Display + Ord + Clone + 'static,
+        F: FnOnce(&mut SpreadsheetView<D>) -> R;
+
+    /// Replaces all records of the view with the given name, as
+    /// `SpreadsheetView::set_records` does.
+    fn set_spreadsheet_records<D>(&mut self, name: &str, records: Vec<Record<D>>) -> bool
+    where
+        D: Display + Ord + Clone + 'static,
+    {
+        self.call_on_spreadsheet(name, |view: &mut SpreadsheetView<D>| view.set_records(records)).is_some()
+    }
+
+    /// Adds a record to the end of the view with the given name.
+    fn push_spreadsheet_record<D>(&mut self, name: &str, record: Record<D>) -> bool
+    where
+        D: Display + Ord + Clone + 'static,
+    {
+        self.call_on_spreadsheet(name, |view: &mut SpreadsheetView<D>| view.push_record(record)).is_some()
+    }
+
+    /// Replaces or adds a record of the view with the given name, as
+    /// `SpreadsheetView::upsert_record` does.
+    fn upsert_spreadsheet_record<D>(&mut self, name: &str, record: Record<D>) -> bool
+    where
+        D: Display + Ord + Clone + 'static,
+    {
+        self.call_on_spreadsheet(name, |view: &mut SpreadsheetView<D>| { view.upsert_record(record); }).is_some()
+    }
+
+    /// Sets the value of a cell of the view with the given name, returning the
+    /// value it replaces, as `SpreadsheetView::set_cell` does.
+    fn set_spreadsheet_cell<D>(&mut self, name: &str, row: usize, key: &str, value: D) -> Option<D>
+    where
+        D: Display + Ord + Clone + 'static,
+    {
+        self.call_on_spreadsheet(name, |view: &mut SpreadsheetView<D>| view.set_cell(row, key, value)).flatten()
+    }
+
+    /// Returns a copy of the record under the cursor of the view with the
+    /// given name, if there is a cursor.
+    fn spreadsheet_cursor_record<D>(&mut self, name: &str) -> Option<Record<D>>
+    where
+        D: Display + Ord + Clone + 'static,
+    {
+        self.call_on_spreadsheet(name, |view: &mut SpreadsheetView<D>| view.cursor_record().cloned()).flatten()
+    }
+}
+
+impl SpreadsheetViewExt for Cursive {
+    fn call_on_spreadsheet<D, R, F>(&mut self, name: &str, f: F) -> Option<R>
+    where
+        D: Display + Ord + Clone + 'static,
+        F: FnOnce(&mut SpreadsheetView<D>) -> R,
+    {
+        self.call_on_name(name, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::view::Nameable;
+
+    use crate::ColumnDef;
+
+    fn record(n: u32) -> Record<u32> {
+        vec![("n".to_string(), n)].into_iter().collect()
+    }
+
+    #[test]
+    fn named_views_are_updated() {
+        let mut siv = Cursive::dummy();
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        siv.add_layer(view.with_name("numbers"));
+
+        assert!(siv.set_spreadsheet_records("numbers", vec![record(1), record(2)]));
+        assert!(siv.push_spreadsheet_record("numbers", record(3)));
+        assert_eq!(siv.set_spreadsheet_cell("numbers", 0, "n", 10_u32), Some(1));
+        siv.call_on_spreadsheet("numbers", |view: &mut SpreadsheetView<u32>| view.set_cursor_pos(0, 0));
+        assert_eq!(siv.spreadsheet_cursor_record("numbers"), Some(record(10)));
+        assert_eq!(siv.call_on_spreadsheet("numbers", |view: &mut SpreadsheetView<u32>| view.len_records()), Some(3));
+
+        assert!(!siv.push_spreadsheet_record("missing", record(4)));
+        // The view holds numbers, not text.
+        assert!(!siv.set_spreadsheet_records::<String>("numbers", Vec::new()));
+    }
+}
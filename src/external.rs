@@ -0,0 +1,100 @@
+//! Editing cell contents in an external program, such as the user's `$EDITOR`.
+
+use std::env;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+use std::time::SystemTime;
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` are set.
+const FALLBACK_EDITOR: &str = "vi";
+
+/// Returns the command line of the user's preferred editor, taken from
+/// `$VISUAL` or `$EDITOR`.
+pub fn editor_command() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|cmd| !cmd.trim().is_empty())
+        .unwrap_or_else(|| FALLBACK_EDITOR.to_string())
+}
+
+/// Opens `text` in the user's preferred editor via a temporary file, waits for
+/// the editor to exit, and returns the edited text.
+///
+/// The trailing newline most editors add on save is removed again, unless the
+/// original text ended with one too.
+pub fn edit_in_external_editor(text: &str) -> io::Result<String> {
+    edit_with_command(&editor_command(), text)
+}
+
+/// Like `edit_in_external_editor`, but using the given editor command line,
+/// e.g. `"code --wait"`.
+pub fn edit_with_command(command: &str, text: &str) -> io::Result<String> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "empty editor command")
+    })?;
+
+    let path = temp_path();
+    let result = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .and_then(|_| Command::new(program).args(words).arg(&path).status())
+        .and_then(|status| {
+            if status.success() { fs::read_to_string(&path) }
+            else {
+                Err(io::Error::other(format!("editor exited with {}", status)))
+            }
+        });
+
+    // The file is only ours to clean up, so a failure here is not interesting.
+    let _ = fs::remove_file(&path);
+
+    let mut edited = result?;
+    if !text.ends_with('\n') && edited.ends_with('\n') {
+        edited.pop();
+        if edited.ends_with('\r') {
+            edited.pop();
+        }
+    }
+
+    Ok(edited)
+}
+
+/// Returns a fresh path in the temporary directory to hold the text of a cell.
+fn temp_path() -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+
+    env::temp_dir().join(format!("cursive-spreadsheet-{}-{}.txt", process::id(), nanos))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_edits_temp_file() {
+        // `sed -i` stands in for an interactive editor.
+        let edited = edit_with_command("sed -i s/old/new/", "old value").unwrap();
+        assert_eq!(edited, "new value");
+
+        let edited = edit_with_command("true", "kept\n").unwrap();
+        assert_eq!(edited, "kept\n");
+    }
+
+    #[test]
+    fn failing_command_is_an_error() {
+        assert!(edit_with_command("false", "text").is_err());
+        assert!(edit_with_command("  ", "text").is_err());
+    }
+}
@@ -0,0 +1,905 @@
+//! A small formula language for computed cells, in the style of desktop
+//! spreadsheets: `=SUM(B2:B10) * 2`, `=A1 & " items"`, or `=AVERAGE(prices)`.
+//!
+//! Cells are addressed by column letter (`A` being the first column of the
+//! view) and 1-based row number, optionally with `$` markers, which are
+//! accepted but carry no meaning.
+//!
+//! Cells can also be addressed by the key of their column, which keeps working
+//! when columns are moved: `[price]` is the cell of the `price` column in the
+//! same record as the formula, `[price@-1]` the one in the record above, and
+//! `[price@3]` the one in the third record. `SUM([price@1]:[price@-1])` adds
+//! up the prices of every record above.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+/// The result of evaluating a formula, or the value of a cell referenced by one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormulaValue {
+    /// An empty cell.
+    Empty,
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Error(FormulaError),
+}
+
+impl FormulaValue {
+    /// Interprets the text of a plain cell: numbers become numbers, and the
+    /// empty string an empty cell.
+    pub fn from_text(text: &str) -> Self {
+        let trimmed = text.trim();
+        if trimmed.is_empty() { Self::Empty }
+        else {
+            match trimmed.parse::<f64>() {
+                Ok(n) if n.is_finite() => Self::Number(n),
+                _ => Self::Text(text.to_string()),
+            }
+        }
+    }
+
+    /// Returns this value as a number, if it can be used as one. Empty cells
+    /// count as 0, and booleans as 1 or 0.
+    pub fn as_number(&self) -> Result<f64, FormulaError> {
+        match self {
+            Self::Empty => Ok(0.0),
+            Self::Number(n) => Ok(*n),
+            Self::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+            Self::Text(text) => text.trim().parse().map_err(|_| FormulaError::Value),
+            Self::Error(err) => Err(*err),
+        }
+    }
+
+    /// Returns this value as a boolean, if it can be used as one. Numbers are
+    /// true unless they are 0.
+    pub fn as_bool(&self) -> Result<bool, FormulaError> {
+        match self {
+            Self::Bool(b) => Ok(*b),
+            Self::Text(text) if text.eq_ignore_ascii_case("true") => Ok(true),
+            Self::Text(text) if text.eq_ignore_ascii_case("false") => Ok(false),
+            Self::Text(_) => Err(FormulaError::Value),
+            other => other.as_number().map(|n| n != 0.0),
+        }
+    }
+
+    /// Returns this value as text, as it would be displayed.
+    pub fn as_text(&self) -> Result<String, FormulaError> {
+        match self {
+            Self::Error(err) => Err(*err),
+            other => Ok(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FormulaValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => Ok(()),
+            Self::Number(n) => write!(f, "{}", n),
+            Self::Text(text) => f.write_str(text),
+            Self::Bool(b) => f.write_str(if *b { "TRUE" } else { "FALSE" }),
+            Self::Error(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<FormulaError> for FormulaValue {
+    fn from(err: FormulaError) -> Self {
+        Self::Error(err)
+    }
+}
+
+/// An error produced while evaluating a formula, shown in place of its value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FormulaError {
+    /// The formula could not be parsed.
+    Parse,
+    /// A value has the wrong type for the operation applied to it.
+    Value,
+    /// A number was divided by zero.
+    DivZero,
+    /// A reference points outside of the view.
+    Ref,
+    /// A function or named range does not exist.
+    Name,
+    /// The formula refers back to its own cell.
+    Cycle,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Parse => "#PARSE!",
+            Self::Value => "#VALUE!",
+            Self::DivZero => "#DIV/0!",
+            Self::Ref => "#REF!",
+            Self::Name => "#NAME?",
+            Self::Cycle => "#CYCLE!",
+        })
+    }
+}
+
+/// A rectangular block of cells, as `(column, row)` positions from `start` to
+/// `end` inclusive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CellRange {
+    start: (usize, usize),
+    end: (usize, usize),
+}
+
+impl CellRange {
+    /// Creates the range spanning two corner cells, given as `(column, row)`.
+    pub fn new(a: (usize, usize), b: (usize, usize)) -> Self {
+        Self {
+            start: (a.0.min(b.0), a.1.min(b.1)),
+            end: (a.0.max(b.0), a.1.max(b.1)),
+        }
+    }
+
+    /// Parses a range written like `C2:C100`, or a single cell like `B7`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().splitn(2, ':');
+        let start = parse_cell(parts.next()?)?;
+        let end = match parts.next() {
+            Some(part) => parse_cell(part)?,
+            None => start,
+        };
+        Some(Self::new(start, end))
+    }
+
+    /// Returns the top left cell of this range.
+    pub fn start(&self) -> (usize, usize) {
+        self.start
+    }
+
+    /// Returns the bottom right cell of this range.
+    pub fn end(&self) -> (usize, usize) {
+        self.end
+    }
+
+    /// Returns `true` if the cell at the given `(column, row)` is in this range.
+    pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+        (self.start.0..=self.end.0).contains(&x) && (self.start.1..=self.end.1).contains(&y)
+    }
+
+    /// Returns every cell in this range, by row and then by column.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (start, end) = (self.start, self.end);
+        (start.1..=end.1).flat_map(move |y| (start.0..=end.0).map(move |x| (x, y)))
+    }
+
+    /// Returns this range with its rows limited to those for which `f` gives
+    /// a new index, as happens when records are inserted or removed. Returns
+    /// `None` if none of its rows are left.
+    pub(crate) fn remap_rows<F>(&self, f: F) -> Option<Self>
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        let rows = self.start.1..=self.end.1;
+        let first = rows.clone().find_map(&f)?;
+        let last = rows.rev().find_map(&f)?;
+        Some(Self::new((self.start.0, first), (self.end.0, last)))
+    }
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", column_name(self.start.0), self.start.1 + 1)?;
+        if self.start != self.end {
+            write!(f, ":{}{}", column_name(self.end.0), self.end.1 + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the letters naming the column at the given index: `A` to `Z`,
+/// then `AA` and so on.
+pub fn column_name(mut x: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (x % 26) as u8);
+        if x < 26 {
+            break;
+        }
+        x = x / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Parses a cell address like `B7` or `$B$7` into a `(column, row)` position.
+fn parse_cell(text: &str) -> Option<(usize, usize)> {
+    let text = text.trim().replace('$', "");
+    let split = text.find(|c: char| !c.is_ascii_alphabetic())?;
+    let (letters, digits) = text.split_at(split);
+
+    if letters.is_empty() || letters.len() > 3 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let x = letters.bytes()
+        .fold(0, |x, b| x * 26 + (b.to_ascii_uppercase() - b'A') as usize + 1) - 1;
+    let y = digits.parse::<usize>().ok()?.checked_sub(1)?;
+    Some((x, y))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A reference to a cell, as written in a formula.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum CellRef {
+    /// An address like `B7`, as a `(column, row)` position.
+    Address((usize, usize)),
+    /// A cell of the column with the given key, like `[price@-1]`.
+    Column { key: String, row: RowRef },
+}
+
+/// The row of a cell referred to by column key.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum RowRef {
+    /// The row this many rows below that of the formula, or above if negative.
+    Relative(isize),
+    /// The row at this index.
+    Absolute(usize),
+}
+
+impl CellRef {
+    /// Returns the `(column, row)` position of the cell referred to from a
+    /// formula at `origin`, if there is such a cell.
+    fn resolve(&self, sheet: &dyn Sheet, origin: (usize, usize)) -> Option<(usize, usize)> {
+        match self {
+            Self::Address(cell) => Some(*cell),
+            Self::Column { key, row } => {
+                let x = sheet.column_index(key)?;
+                let y = match *row {
+                    RowRef::Relative(offset) => origin.1.checked_add_signed(offset)?,
+                    RowRef::Absolute(y) => y,
+                };
+                Some((x, y))
+            },
+        }
+    }
+}
+
+/// Parses the inside of a column reference like `[price@-1]`.
+fn parse_column_ref(text: &str) -> Option<CellRef> {
+    let (key, row) = match text.rsplit_once('@') {
+        Some((key, row)) => {
+            let row = row.trim();
+            let row = if row.starts_with(['+', '-']) { RowRef::Relative(row.parse().ok()?) }
+                else { RowRef::Absolute(row.parse::<usize>().ok()?.checked_sub(1)?) };
+            (key, row)
+        },
+        None => (text, RowRef::Relative(0)),
+    };
+
+    let key = key.trim();
+    if key.is_empty() { None } else { Some(CellRef::Column { key: key.to_string(), row }) }
+}
+
+/// A parsed formula.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expr {
+    Value(FormulaValue),
+    Cell(CellRef),
+    Range(CellRef, CellRef),
+    Name(String),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Ident(String),
+    /// A reference by column key, without the brackets around it.
+    Column(String),
+    Op(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    const OPS: &[&str] = &["<>", "<=", ">=", "+", "-", "*", "/", "^", "&", "=", "<", ">", ":"];
+
+    let mut tokens = Vec::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        }
+        else if c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            let mut end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+            // An exponent, as in `1.5e3` or `2E-4`.
+            let after = &rest[end..];
+            if after.starts_with(['e', 'E']) {
+                let digits = after[1..].strip_prefix(['+', '-']).unwrap_or(&after[1..]);
+                if digits.starts_with(|c: char| c.is_ascii_digit()) {
+                    let skipped = after.len() - digits.len();
+                    end += skipped + digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+                }
+            }
+            let number = rest[..end].parse().map_err(|_| format!("Invalid number: {}", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        }
+        else if c == '"' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => {
+                        if rest[i + 2..].starts_with('"') { value.push('"'); chars.next(); }
+                        else { break i + 2; }
+                    },
+                    Some((_, c)) => value.push(c),
+                    None => return Err("Unterminated string".to_string()),
+                }
+            };
+            tokens.push(Token::Text(value));
+            rest = &rest[end..];
+        }
+        else if c.is_alphabetic() || c == '_' || c == '$' {
+            let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$' || c == '.')).unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        else if c == '[' {
+            let end = rest.find(']').ok_or_else(|| "Unterminated column reference".to_string())?;
+            tokens.push(Token::Column(rest[1..end].to_string()));
+            rest = &rest[end + 1..];
+        }
+        else if c == '(' { tokens.push(Token::Open); rest = &rest[1..]; }
+        else if c == ')' { tokens.push(Token::Close); rest = &rest[1..]; }
+        else if c == ',' || c == ';' { tokens.push(Token::Comma); rest = &rest[1..]; }
+        else {
+            match OPS.iter().find(|op| rest.starts_with(*op)) {
+                Some(op) => { tokens.push(Token::Op(op)); rest = &rest[op.len()..]; },
+                None => return Err(format!("Unexpected character: {}", c)),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            },
+            _ => None,
+        }
+    }
+
+    fn binary<F>(&mut self, ops: &[&'static str], mut operand: F) -> Result<Expr, String>
+    where
+        F: FnMut(&mut Self) -> Result<Expr, String>,
+    {
+        let mut lhs = operand(self)?;
+        while let Some(op) = self.eat_op(ops) {
+            let rhs = operand(self)?;
+            let op = match op {
+                "+" => BinaryOp::Add,
+                "-" => BinaryOp::Sub,
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                "&" => BinaryOp::Concat,
+                "=" => BinaryOp::Eq,
+                "<>" => BinaryOp::Ne,
+                "<" => BinaryOp::Lt,
+                "<=" => BinaryOp::Le,
+                ">" => BinaryOp::Gt,
+                _ => BinaryOp::Ge,
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        self.binary(&["=", "<>", "<", "<=", ">", ">="], Self::concat)
+    }
+
+    fn concat(&mut self) -> Result<Expr, String> {
+        self.binary(&["&"], Self::additive)
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        self.binary(&["+", "-"], Self::term)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        self.binary(&["*", "/"], Self::power)
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.unary()?;
+        if self.eat_op(&["^"]).is_some() {
+            let exponent = self.power()?;
+            return Ok(Expr::Binary(BinaryOp::Pow, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.eat_op(&["-", "+"]) {
+            Some("-") => Ok(Expr::Negate(Box::new(self.unary()?))),
+            Some(_) => self.unary(),
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Value(FormulaValue::Number(n))),
+            Some(Token::Text(text)) => Ok(Expr::Value(FormulaValue::Text(text))),
+            Some(Token::Open) => {
+                let expr = self.comparison()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("Expected )".to_string()),
+                }
+            },
+            Some(Token::Column(text)) => match parse_column_ref(&text) {
+                Some(start) => self.cell_or_range(start, &format!("[{}]", text)),
+                None => Err(format!("Invalid column reference: [{}]", text)),
+            },
+            Some(Token::Ident(ident)) => {
+                if self.peek() == Some(&Token::Open) {
+                    self.pos += 1;
+                    return Ok(Expr::Call(ident.to_ascii_uppercase(), self.arguments()?));
+                }
+
+                if let Some(start) = parse_cell(&ident) {
+                    return self.cell_or_range(CellRef::Address(start), &ident);
+                }
+
+                if ident.eq_ignore_ascii_case("true") { Ok(Expr::Value(FormulaValue::Bool(true))) }
+                else if ident.eq_ignore_ascii_case("false") { Ok(Expr::Value(FormulaValue::Bool(false))) }
+                else { Ok(Expr::Name(ident)) }
+            },
+            Some(token) => Err(format!("Unexpected {:?}", token)),
+            None => Err("Unexpected end of formula".to_string()),
+        }
+    }
+
+    /// Parses what follows the reference to the cell `start`, written as
+    /// `text`: the end of a range, if any.
+    fn cell_or_range(&mut self, start: CellRef, text: &str) -> Result<Expr, String> {
+        if self.eat_op(&[":"]).is_none() {
+            return Ok(Expr::Cell(start));
+        }
+
+        let end = match self.next() {
+            Some(Token::Ident(end)) => parse_cell(&end).map(CellRef::Address),
+            Some(Token::Column(end)) => parse_column_ref(&end),
+            _ => None,
+        };
+        match end {
+            Some(end) => Ok(Expr::Range(start, end)),
+            None => Err(format!("Invalid range after {}:", text)),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Expr>, String> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::Close) {
+            self.pos += 1;
+            return Ok(args);
+        }
+
+        loop {
+            args.push(self.comparison()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::Close) => return Ok(args),
+                _ => return Err("Expected , or )".to_string()),
+            }
+        }
+    }
+}
+
+/// Parses a formula, with or without its leading `=`.
+pub(crate) fn parse(text: &str) -> Result<Expr, String> {
+    let text = text.trim();
+    let text = text.strip_prefix('=').unwrap_or(text);
+
+    let mut parser = Parser { tokens: tokenize(text)?, pos: 0 };
+    let expr = parser.comparison()?;
+
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(format!("Unexpected {:?}", token)),
+    }
+}
+
+/// The cells and named ranges that formulas are evaluated against.
+pub(crate) trait Sheet {
+    /// Returns the value of the cell at the given `(column, row)`, or a
+    /// `#REF!` error if there is no such cell.
+    fn value(&self, cell: (usize, usize)) -> FormulaValue;
+
+    /// Returns the range with the given name, if any.
+    fn named_range(&self, name: &str) -> Option<CellRange>;
+
+    /// Returns the index of the column with the given key, if any.
+    fn column_index(&self, key: &str) -> Option<usize>;
+}
+
+/// The values of formula cells computed so far, along with the cells each of
+/// them read, so that a change to a cell only discards the values that depend
+/// on it. Cells are given as `(column, row)` positions.
+#[derive(Default)]
+pub(crate) struct FormulaCache {
+    values: HashMap<(usize, usize), FormulaValue>,
+    dependents: HashMap<(usize, usize), HashSet<(usize, usize)>>,
+}
+
+impl FormulaCache {
+    pub(crate) fn get(&self, cell: (usize, usize)) -> Option<&FormulaValue> {
+        self.values.get(&cell)
+    }
+
+    pub(crate) fn insert(&mut self, cell: (usize, usize), value: FormulaValue) {
+        self.values.insert(cell, value);
+    }
+
+    /// Records that the formula in `dependent` read the cell `cell`.
+    pub(crate) fn add_dependency(&mut self, cell: (usize, usize), dependent: (usize, usize)) {
+        self.dependents.entry(cell).or_default().insert(dependent);
+    }
+
+    /// Discards the value of a cell, and of every formula depending on it,
+    /// directly or not.
+    pub(crate) fn invalidate(&mut self, cell: (usize, usize)) {
+        let mut pending = vec![cell];
+        while let Some(cell) = pending.pop() {
+            self.values.remove(&cell);
+            if let Some(dependents) = self.dependents.remove(&cell) {
+                pending.extend(dependents);
+            }
+        }
+    }
+
+    /// Discards every value, e.g. after cells have moved around.
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+        self.dependents.clear();
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+/// A function callable from formulas. Ranges passed to it are flattened into
+/// the values of their cells.
+pub(crate) type FormulaFunction = Rc<dyn Fn(&[FormulaValue]) -> FormulaValue>;
+
+/// Returns the functions every formula can call.
+pub(crate) fn builtin_functions() -> HashMap<String, FormulaFunction> {
+    let mut functions: HashMap<String, FormulaFunction> = HashMap::new();
+
+    functions.insert("SUM".to_string(), Rc::new(|args| fold_numbers(args, |ns| ns.iter().sum())));
+    let average: FormulaFunction = Rc::new(|args| {
+        fold_numbers(args, |ns| ns.iter().sum::<f64>() / ns.len() as f64)
+    });
+    functions.insert("AVERAGE".to_string(), average.clone());
+    functions.insert("AVG".to_string(), average);
+    functions.insert("MIN".to_string(), Rc::new(|args| {
+        fold_numbers(args, |ns| ns.iter().copied().fold(f64::INFINITY, f64::min))
+    }));
+    functions.insert("MAX".to_string(), Rc::new(|args| {
+        fold_numbers(args, |ns| ns.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+    }));
+    functions.insert("COUNT".to_string(), Rc::new(|args| {
+        FormulaValue::Number(args.iter().filter(|arg| matches!(arg, FormulaValue::Number(_))).count() as f64)
+    }));
+    functions.insert("IF".to_string(), Rc::new(|args| match args {
+        [condition, then] => if_else(condition, then, &FormulaValue::Bool(false)),
+        [condition, then, otherwise] => if_else(condition, then, otherwise),
+        _ => FormulaError::Value.into(),
+    }));
+    functions.insert("CONCAT".to_string(), Rc::new(|args| {
+        match args.iter().map(FormulaValue::as_text).collect::<Result<String, _>>() {
+            Ok(text) => FormulaValue::Text(text),
+            Err(err) => err.into(),
+        }
+    }));
+    functions.insert("LEN".to_string(), Rc::new(|args| match args {
+        [arg] => match arg.as_text() {
+            Ok(text) => FormulaValue::Number(text.chars().count() as f64),
+            Err(err) => err.into(),
+        },
+        _ => FormulaError::Value.into(),
+    }));
+    functions.insert("ROUND".to_string(), Rc::new(|args| {
+        let (n, digits) = match args {
+            [n] => (n.as_number(), Ok(0.0)),
+            [n, digits] => (n.as_number(), digits.as_number()),
+            _ => return FormulaError::Value.into(),
+        };
+
+        match (n, digits) {
+            (Ok(n), Ok(digits)) => {
+                let scale = 10f64.powi(digits.trunc() as i32);
+                number((n * scale).round() / scale)
+            },
+            (Err(err), _) | (_, Err(err)) => err.into(),
+        }
+    }));
+
+    functions
+}
+
+/// Applies `f` to the numbers among `args`, skipping text and empty cells.
+/// Gives 0 if there are no numbers, and the first error among `args` if any.
+pub(crate) fn fold_numbers<F>(args: &[FormulaValue], f: F) -> FormulaValue
+where
+    F: Fn(&[f64]) -> f64,
+{
+    let mut numbers = Vec::new();
+    for arg in args {
+        match arg {
+            FormulaValue::Number(n) => numbers.push(*n),
+            FormulaValue::Bool(b) => numbers.push(if *b { 1.0 } else { 0.0 }),
+            FormulaValue::Error(err) => return FormulaValue::Error(*err),
+            FormulaValue::Empty | FormulaValue::Text(_) => {},
+        }
+    }
+
+    if numbers.is_empty() { FormulaValue::Number(0.0) }
+    else { number(f(&numbers)) }
+}
+
+/// Picks `then` or `otherwise` depending on `condition`.
+fn if_else(condition: &FormulaValue, then: &FormulaValue, otherwise: &FormulaValue) -> FormulaValue {
+    match condition.as_bool() {
+        Ok(true) => then.clone(),
+        Ok(false) => otherwise.clone(),
+        Err(err) => err.into(),
+    }
+}
+
+/// Wraps a computed number, turning infinities and NaN into errors.
+fn number(n: f64) -> FormulaValue {
+    if n.is_finite() { FormulaValue::Number(n) }
+    else { FormulaValue::Error(FormulaError::Value) }
+}
+
+/// Evaluates a parsed formula in the cell at `origin` against a sheet.
+pub(crate) fn evaluate(
+    expr: &Expr,
+    sheet: &dyn Sheet,
+    functions: &HashMap<String, FormulaFunction>,
+    origin: (usize, usize),
+) -> FormulaValue {
+    let eval = |expr: &Expr| evaluate(expr, sheet, functions, origin);
+
+    match expr {
+        Expr::Value(value) => value.clone(),
+        Expr::Cell(cell) => match cell.resolve(sheet, origin) {
+            Some(cell) => sheet.value(cell),
+            None => FormulaError::Ref.into(),
+        },
+        // A range only makes sense as an argument to a function.
+        Expr::Range(..) => FormulaError::Value.into(),
+        Expr::Name(name) => match sheet.named_range(name) {
+            Some(range) if range.start == range.end => sheet.value(range.start),
+            Some(_) => FormulaError::Value.into(),
+            None => FormulaError::Name.into(),
+        },
+        Expr::Negate(inner) => match eval(inner).as_number() {
+            Ok(n) => number(-n),
+            Err(err) => err.into(),
+        },
+        Expr::Binary(op, lhs, rhs) => binary(*op, eval(lhs), eval(rhs)),
+        Expr::Call(name, args) => {
+            let function = match functions.get(name) {
+                Some(function) => function,
+                None => return FormulaError::Name.into(),
+            };
+
+            let mut values = Vec::new();
+            for arg in args {
+                let range = match arg {
+                    Expr::Range(start, end) => match (start.resolve(sheet, origin), end.resolve(sheet, origin)) {
+                        (Some(start), Some(end)) => Some(CellRange::new(start, end)),
+                        _ => return FormulaError::Ref.into(),
+                    },
+                    Expr::Name(name) => sheet.named_range(name),
+                    _ => None,
+                };
+
+                match range {
+                    Some(range) => values.extend(range.cells().map(|cell| sheet.value(cell))),
+                    None if matches!(arg, Expr::Name(_)) => return FormulaError::Name.into(),
+                    None => values.push(eval(arg)),
+                }
+            }
+
+            function(&values)
+        },
+    }
+}
+
+fn binary(op: BinaryOp, lhs: FormulaValue, rhs: FormulaValue) -> FormulaValue {
+    use self::BinaryOp::*;
+
+    match op {
+        Concat => match (lhs.as_text(), rhs.as_text()) {
+            (Ok(a), Ok(b)) => FormulaValue::Text(a + &b),
+            (Err(err), _) | (_, Err(err)) => err.into(),
+        },
+        Eq | Ne | Lt | Le | Gt | Ge => {
+            let ordering = match (&lhs, &rhs) {
+                (FormulaValue::Error(err), _) | (_, FormulaValue::Error(err)) => return (*err).into(),
+                (FormulaValue::Text(a), FormulaValue::Text(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+                (FormulaValue::Text(_), _) | (_, FormulaValue::Text(_)) => None,
+                _ => match (lhs.as_number(), rhs.as_number()) {
+                    (Ok(a), Ok(b)) => a.partial_cmp(&b),
+                    _ => None,
+                },
+            };
+
+            let result = match ordering {
+                Some(o) => match op {
+                    Eq => o.is_eq(),
+                    Ne => o.is_ne(),
+                    Lt => o.is_lt(),
+                    Le => o.is_le(),
+                    Gt => o.is_gt(),
+                    _ => o.is_ge(),
+                },
+                // Text never equals a number.
+                None => op == Ne,
+            };
+            FormulaValue::Bool(result)
+        },
+        _ => {
+            let (a, b) = match (lhs.as_number(), rhs.as_number()) {
+                (Ok(a), Ok(b)) => (a, b),
+                (Err(err), _) | (_, Err(err)) => return err.into(),
+            };
+
+            match op {
+                Add => number(a + b),
+                Sub => number(a - b),
+                Mul => number(a * b),
+                Div if b == 0.0 => FormulaError::DivZero.into(),
+                Div => number(a / b),
+                _ => number(a.powf(b)),
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Grid(Vec<Vec<FormulaValue>>);
+
+    impl Sheet for Grid {
+        fn value(&self, (x, y): (usize, usize)) -> FormulaValue {
+            self.0.get(y).and_then(|row| row.get(x)).cloned().unwrap_or(FormulaError::Ref.into())
+        }
+
+        fn named_range(&self, name: &str) -> Option<CellRange> {
+            if name == "firsts" { CellRange::parse("A1:A3") } else { None }
+        }
+
+        fn column_index(&self, key: &str) -> Option<usize> {
+            ["n", "t"].iter().position(|&k| k == key)
+        }
+    }
+
+    fn eval(text: &str) -> FormulaValue {
+        let grid = Grid((1..=3).map(|y| vec![
+            FormulaValue::Number(y as f64),
+            FormulaValue::Text(format!("t{}", y)),
+        ]).collect());
+
+        match parse(text) {
+            // As if in the second row of a third column.
+            Ok(expr) => evaluate(&expr, &grid, &builtin_functions(), (2, 1)),
+            Err(_) => FormulaError::Parse.into(),
+        }
+    }
+
+    #[test]
+    fn ranges_and_addresses() {
+        assert_eq!(CellRange::parse("C2:C100"), Some(CellRange::new((2, 1), (2, 99))));
+        assert_eq!(CellRange::parse("$b$7"), Some(CellRange::new((1, 6), (1, 6))));
+        assert_eq!(CellRange::parse("A0"), None);
+        assert_eq!(CellRange::parse("B3:A1").map(|r| r.to_string()), Some("A1:B3".to_string()));
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(parse_cell("AB1"), Some((27, 0)));
+    }
+
+    #[test]
+    fn evaluates_formulas() {
+        assert_eq!(eval("=1 + 2 * 3 ^ 2"), FormulaValue::Number(19.0));
+        assert_eq!(eval("=-(A1 + A3) / 2"), FormulaValue::Number(-2.0));
+        assert_eq!(eval("=SUM(A1:A3) & \" total\""), FormulaValue::Text("6 total".to_string()));
+        assert_eq!(eval("=AVERAGE(firsts)"), FormulaValue::Number(2.0));
+        assert_eq!(eval("=max(A1:B3, 10)"), FormulaValue::Number(10.0));
+        assert_eq!(eval("=A2 >= 2"), FormulaValue::Bool(true));
+        assert_eq!(eval("=B1 = \"T1\""), FormulaValue::Bool(true));
+        assert_eq!(eval("=1 / (A1 - 1)"), FormulaError::DivZero.into());
+        assert_eq!(eval("=B1 + 1"), FormulaError::Value.into());
+        assert_eq!(eval("=C9"), FormulaError::Ref.into());
+        assert_eq!(eval("=nope + 1"), FormulaError::Name.into());
+        assert_eq!(eval("=NOPE(1)"), FormulaError::Name.into());
+        assert_eq!(eval("=1 +"), FormulaError::Parse.into());
+        assert_eq!(eval("=\"say \"\"hi\"\"\""), FormulaValue::Text("say \"hi\"".to_string()));
+    }
+
+    #[test]
+    fn cells_by_column_key() {
+        assert_eq!(eval("=[n] * 10"), FormulaValue::Number(20.0));
+        assert_eq!(eval("=[n@-1] + [ n @+1 ]"), FormulaValue::Number(4.0));
+        assert_eq!(eval("=[t@3]"), FormulaValue::Text("t3".to_string()));
+        assert_eq!(eval("=AVG([n@1]:[n@+1])"), FormulaValue::Number(2.0));
+        assert_eq!(eval("=SUM([n@-1]:B2)"), FormulaValue::Number(3.0));
+        assert_eq!(eval("=[n@-2]"), FormulaError::Ref.into());
+        assert_eq!(eval("=SUM([nope@1]:[n])"), FormulaError::Ref.into());
+        assert_eq!(eval("=[n@0]"), FormulaError::Parse.into());
+        assert_eq!(eval("=[n"), FormulaError::Parse.into());
+    }
+
+    #[test]
+    fn builtin_functions_over_ranges_and_scalars() {
+        assert_eq!(eval("=COUNT(A1:B3, 4, \"x\")"), FormulaValue::Number(4.0));
+        assert_eq!(eval("=IF(A2 > 1, \"big\", \"small\")"), FormulaValue::Text("big".to_string()));
+        assert_eq!(eval("=IF(0, 1)"), FormulaValue::Bool(false));
+        assert_eq!(eval("=IF(B1, 1, 2)"), FormulaError::Value.into());
+        assert_eq!(eval("=CONCAT(B1:B3, \"!\")"), FormulaValue::Text("t1t2t3!".to_string()));
+        assert_eq!(eval("=LEN(\"héllo\")"), FormulaValue::Number(5.0));
+        assert_eq!(eval("=LEN(A1:A2)"), FormulaError::Value.into());
+        assert_eq!(eval("=ROUND(2.5)"), FormulaValue::Number(3.0));
+        assert_eq!(eval("=ROUND(1234.567, 2)"), FormulaValue::Number(1234.57));
+        assert_eq!(eval("=ROUND(1234.567, -2)"), FormulaValue::Number(1200.0));
+    }
+}
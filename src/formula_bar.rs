@@ -0,0 +1,155 @@
+//! A companion view showing the raw contents of the cell under the cursor of a
+//! spreadsheet, in the style of the formula bar of desktop spreadsheets.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use unicode_width::UnicodeWidthStr;
+
+use cursive::Cursive;
+use cursive::Printer;
+use cursive::align::HAlign;
+use cursive::direction::Direction;
+use cursive::event::{Event, EventResult, Key};
+use cursive::vec::Vec2;
+use cursive::view::View;
+use cursive::views::EditView;
+
+use crate::text::{escape_controls, print_aligned};
+
+/// Separator drawn between the address of the cell and its contents.
+const SEPARATOR: &str = " │ ";
+
+/// Narrowest the address of the cell is drawn, so that the contents do not
+/// jump around as the cursor moves.
+const MIN_ADDRESS_WIDTH: usize = 4;
+
+/// The cell under the cursor of a spreadsheet, as last laid out.
+#[derive(Default)]
+pub(crate) struct FormulaBarState {
+    /// The row and column key of the cell, if there is a cursor.
+    pub(crate) cell: Option<(usize, String)>,
+    /// The address of the cell, like `B7`.
+    pub(crate) address: String,
+    /// The formula in the cell, or its value as text.
+    pub(crate) text: String,
+}
+
+/// Writes text into the cell at the given row and column of the linked
+/// spreadsheet.
+type CommitCallback = Rc<dyn Fn(&mut Cursive, usize, &str, &str)>;
+
+/// A one-line view showing the address and the raw value or formula of the
+/// cell under the cursor of a linked spreadsheet. Pressing Enter edits the
+/// cell right there, pressing it again writes the edit back to the
+/// spreadsheet, and Esc abandons the edit.
+///
+/// Made by `SpreadsheetView::formula_bar`.
+pub struct FormulaBarView {
+    state: Rc<RefCell<FormulaBarState>>,
+    on_commit: CommitCallback,
+    /// The cell being edited, along with its editor.
+    editing: Option<((usize, String), EditView)>,
+    last_size: Vec2,
+}
+
+impl FormulaBarView {
+    pub(crate) fn new<F>(state: Rc<RefCell<FormulaBarState>>, on_commit: F) -> Self
+    where
+        F: Fn(&mut Cursive, usize, &str, &str) + 'static,
+    {
+        Self {
+            state,
+            on_commit: Rc::new(on_commit),
+            editing: None,
+            last_size: Vec2::new(0, 0),
+        }
+    }
+
+    /// Returns `true` if the cell is being edited in this view.
+    pub fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    /// Returns the width taken up by the address and the separator.
+    fn text_offset(&self) -> usize {
+        self.state.borrow().address.width().max(MIN_ADDRESS_WIDTH) + SEPARATOR.width()
+    }
+
+    fn editor_size(&self) -> Vec2 {
+        Vec2::new(self.last_size.x.saturating_sub(self.text_offset()), 1)
+    }
+}
+
+impl View for FormulaBarView {
+    fn draw(&self, printer: &Printer) {
+        let state = self.state.borrow();
+        let offset = self.text_offset();
+
+        print_aligned(printer, &state.address, offset - SEPARATOR.width(), &HAlign::Left);
+        printer.print((offset - SEPARATOR.width(), 0), SEPARATOR);
+
+        let printer = &printer.offset((offset, 0));
+        match self.editing.as_ref() {
+            Some((_, editor)) => editor.draw(printer),
+            None => print_aligned(printer, &escape_controls(&state.text), printer.size.x, &HAlign::Left),
+        }
+    }
+
+    fn layout(&mut self, size: Vec2) {
+        self.last_size = size;
+        let editor_size = self.editor_size();
+        if let Some((_, editor)) = self.editing.as_mut() {
+            editor.layout(editor_size);
+        }
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        Vec2::new(constraint.x, 1)
+    }
+
+    fn take_focus(&mut self, _: Direction) -> bool {
+        self.state.borrow().cell.is_some()
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        if self.editing.is_none() {
+            if event != Event::Key(Key::Enter) {
+                return EventResult::Ignored;
+            }
+
+            let state = self.state.borrow();
+            let cell = match state.cell.clone() {
+                Some(cell) => cell,
+                None => return EventResult::Ignored,
+            };
+
+            let mut editor = EditView::new().content(state.text.clone());
+            drop(state);
+
+            editor.layout(self.editor_size());
+            self.editing = Some((cell, editor));
+            return EventResult::Consumed(None);
+        }
+
+        match event {
+            Event::Key(Key::Enter) => {
+                let ((row, key), editor) = match self.editing.take() {
+                    Some(editing) => editing,
+                    None => return EventResult::Ignored,
+                };
+                let text = editor.get_content();
+                let on_commit = self.on_commit.clone();
+                EventResult::with_cb(move |s| on_commit(s, row, &key, &text))
+            },
+            Event::Key(Key::Esc) => {
+                self.editing = None;
+                EventResult::Consumed(None)
+            },
+            event => match self.editing.as_mut() {
+                Some((_, editor)) => editor.on_event(event),
+                None => EventResult::Ignored,
+            },
+        }
+    }
+}
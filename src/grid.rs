@@ -0,0 +1,189 @@
+//! Which lines of the grid are drawn between cells, and with which glyphs.
+
+use std::fmt::Display;
+
+use cursive::Printer;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{COLUMN_SEPARATOR, FROZEN_SEPARATOR, SpreadsheetView};
+
+/// Separators drawn between columns in place of `COLUMN_SEPARATOR` and
+/// `FROZEN_SEPARATOR`, all as wide as them.
+const COLUMN_SEPARATOR_ASCII: &str = " | ";
+const FROZEN_SEPARATOR_ASCII: &str = " # ";
+const BLANK_SEPARATOR: &str = "   ";
+
+/// Which lines of the grid are drawn. The space taken up by the lines left out
+/// stays blank, so that switching modes does not move the cells around.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GridMode {
+    /// Draws lines between the columns and under the titles. The default.
+    #[default]
+    Columns,
+    /// Draws no lines at all, for a minimal table of columns.
+    None,
+    /// Draws lines under the titles and between records.
+    HorizontalOnly,
+    /// Draws lines between the columns.
+    VerticalOnly,
+    /// Draws lines between the columns, under the titles, and between records,
+    /// crossing where they meet, for a dense grid in which every cell has a
+    /// border.
+    Full,
+    /// Draws the line under the titles.
+    HeaderSeparatorOnly,
+}
+
+impl GridMode {
+    /// Returns `true` if lines are drawn between the columns.
+    pub fn has_vertical_lines(self) -> bool {
+        matches!(self, Self::Columns | Self::VerticalOnly | Self::Full)
+    }
+
+    /// Returns `true` if lines are drawn between records.
+    pub fn has_row_lines(self) -> bool {
+        matches!(self, Self::HorizontalOnly | Self::Full)
+    }
+
+    /// Returns `true` if a line is drawn under the titles.
+    pub fn has_header_line(self) -> bool {
+        matches!(self, Self::Columns | Self::HorizontalOnly | Self::Full | Self::HeaderSeparatorOnly)
+    }
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Sets which lines of the grid are drawn. Lines between records take up a
+    /// line of their own each, like the lines drawn by `Banding::Line`.
+    pub fn set_grid_mode(&mut self, mode: GridMode) {
+        self.grid_mode = mode;
+    }
+
+    /// Adds which lines of the grid are drawn, and returns this view.
+    pub fn grid_mode(mut self, mode: GridMode) -> Self {
+        self.set_grid_mode(mode);
+        self
+    }
+
+    /// Returns which lines of the grid are drawn.
+    pub fn get_grid_mode(&self) -> GridMode {
+        self.grid_mode
+    }
+
+    /// Sets whether the grid is drawn with ASCII characters rather than the
+    /// box-drawing characters of Unicode, for terminals and fonts without
+    /// them. Off by default.
+    pub fn set_ascii_grid(&mut self, ascii: bool) {
+        self.ascii_grid = ascii;
+    }
+
+    /// Returns `true` if the grid is drawn with ASCII characters.
+    pub fn is_ascii_grid(&self) -> bool {
+        self.ascii_grid
+    }
+
+    /// Returns the separator drawn between two columns, or between the frozen
+    /// columns and the others if `frozen` is set.
+    pub(crate) fn vertical_separator(&self, frozen: bool) -> &'static str {
+        match (self.grid_mode.has_vertical_lines(), self.ascii_grid, frozen) {
+            (false, _, _) => BLANK_SEPARATOR,
+            (true, false, false) => COLUMN_SEPARATOR,
+            (true, false, true) => FROZEN_SEPARATOR,
+            (true, true, false) => COLUMN_SEPARATOR_ASCII,
+            (true, true, true) => FROZEN_SEPARATOR_ASCII,
+        }
+    }
+
+    /// Returns the glyph horizontal lines are drawn with, or the bold version
+    /// drawn under the titles by `FocusIndicator::Delimiter`.
+    pub(crate) fn horizontal_glyph(&self, bold: bool) -> &'static str {
+        match (self.ascii_grid || self.monochrome, bold) {
+            (false, false) => "─",
+            (false, true) => "━",
+            (true, false) => "-",
+            (true, true) => "=",
+        }
+    }
+
+    /// Returns the positions of the vertical lines of the columns drawn, along
+    /// with whether each one separates the frozen columns from the others.
+    fn vertical_line_positions(&self) -> Vec<(usize, bool)> {
+        let mut positions = Vec::new();
+        if self.has_gutter() {
+            positions.push((self.gutter_width + 1, true));
+        }
+
+        let mut x = self.gutter_offset();
+        for (n, i) in self.drawn_columns().enumerate() {
+            if n > 0 {
+                positions.push((x + 1, self.separator_before(i) == self.vertical_separator(true)));
+                x += COLUMN_SEPARATOR.width();
+            }
+            x += self.column_widths[i];
+        }
+        positions
+    }
+
+    /// Draws a horizontal line of the grid across the given printer, on the
+    /// line at `y`, crossing the vertical lines if every line is drawn.
+    pub(crate) fn draw_grid_rule(&self, printer: &Printer, y: usize, bold: bool) {
+        self.print_grid_hline(printer, y, self.horizontal_glyph(bold));
+        if self.grid_mode != GridMode::Full {
+            return;
+        }
+
+        for (x, frozen) in self.vertical_line_positions() {
+            let crossing = match (self.ascii_grid || self.monochrome, frozen) {
+                (false, false) => "┼",
+                (false, true) => "╫",
+                (true, false) => "+",
+                (true, true) => "#",
+            };
+            self.print_grid(&printer.offset((0, y)), x, crossing);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    fn view() -> SpreadsheetView<u32> {
+        let mut view = SpreadsheetView::new();
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        view.push_column("sq".to_string(), ColumnDef::new("Square"));
+        view.extend_records((1..=2).map(|n| vec![("n".to_string(), n), ("sq".to_string(), n * n)].into_iter().collect()));
+        view
+    }
+
+    #[test]
+    fn grid_modes_choose_lines() {
+        let render = |view: &mut SpreadsheetView<u32>| {
+            view.layout(Vec2::new(10, 8));
+            view.render_to_string(Vec2::new(10, 8)).lines().map(str::trim_end).collect::<Vec<_>>().join("\n")
+        };
+
+        let mut view = view();
+        assert_eq!(view.get_grid_mode(), GridMode::Columns);
+        assert!(render(&mut view).starts_with("N │ Square\n──────────\n1 │ 1"));
+
+        view.set_grid_mode(GridMode::None);
+        assert!(render(&mut view).starts_with("N   Square\n\n1   1\n2   4"));
+
+        view.set_grid_mode(GridMode::HeaderSeparatorOnly);
+        assert!(render(&mut view).starts_with("N   Square\n──────────\n1   1\n2   4"));
+
+        view.set_grid_mode(GridMode::Full);
+        assert!(render(&mut view).starts_with("N │ Square\n──┼───────\n1 │ 1\n──┼───────\n2 │ 4"));
+
+        view.set_ascii_grid(true);
+        assert!(render(&mut view).starts_with("N | Square\n--+-------\n1 | 1\n--+-------\n2 | 4"));
+
+        view.set_grid_mode(GridMode::HorizontalOnly);
+        assert!(render(&mut view).starts_with("N   Square\n----------\n1   1\n----------\n2   4"));
+    }
+}
@@ -0,0 +1,412 @@
+//! Grouping records by the value of a column, under headers that collapse to
+//! hide the records of their group.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use cursive::Printer;
+use cursive::event::EventResult;
+use cursive::theme::{Effect, Style};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{Aggregate, FormulaValue, SortOrder, SpreadsheetView};
+use crate::formula::Sheet;
+
+/// Glyphs drawn before the labels of expanded and collapsed groups, and their
+/// ASCII versions.
+const EXPANDED_MARKER: &str = "▾";
+const COLLAPSED_MARKER: &str = "▸";
+const EXPANDED_MARKER_ASCII: &str = "v";
+const COLLAPSED_MARKER_ASCII: &str = ">";
+
+/// The column records are grouped by, and the groups collapsed.
+pub(crate) struct Grouping {
+    pub key: String,
+    /// The labels of the collapsed groups.
+    pub collapsed: HashSet<String>,
+}
+
+/// A run of records with the same value in the column records are grouped by,
+/// as of the last time the records shown were worked out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Group {
+    /// The shown position of the first record of the group.
+    pub position: usize,
+    /// The text of the value the records of the group share.
+    pub label: String,
+    /// The indices of the records of the group, including the ones hidden by
+    /// collapsing it.
+    pub rows: Vec<usize>,
+    pub collapsed: bool,
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Groups the records by their value in the column with the given key,
+    /// sorting them by it so that each group is in one place. Every group gets
+    /// a header showing its value and how many records it has, which the keys
+    /// bound to `ToggleGroup` (Ctrl+G) or a click collapse to hide the
+    /// records of the group. On the header of a collapsed group, the keys
+    /// bound to editing (Enter) and to selecting (Insert) expand it again.
+    /// Sorting by other columns sorts the records within their groups.
+    ///
+    /// Records from a source are not grouped.
+    pub fn group_by(&mut self, key: &str) {
+        if !self.columns.contains_key(key) {
+            return;
+        }
+
+        self.grouping = Some(Grouping { key: key.to_string(), collapsed: HashSet::new() });
+        let mut spec: Vec<(String, SortOrder)> = self.sort_spec.iter().filter(|(sorted, _)| sorted != key).cloned().collect();
+        spec.insert(0, (key.to_string(), SortOrder::Ascending));
+        self.sort_by_spec(&spec);
+        self.refilter();
+    }
+
+    /// Stops grouping the records, showing every record again.
+    pub fn clear_grouping(&mut self) {
+        if self.grouping.take().is_some() {
+            self.refilter();
+        }
+    }
+
+    /// Returns the key of the column the records are grouped by, if any.
+    pub fn grouped_by(&self) -> Option<&str> {
+        self.grouping.as_ref().map(|grouping| grouping.key.as_str())
+    }
+
+    /// Collapses or expands the group with the given label, which is the text
+    /// its records show in the column they are grouped by.
+    pub fn set_group_collapsed(&mut self, label: &str, collapsed: bool) {
+        if let Some(grouping) = self.grouping.as_mut() {
+            let changed = if collapsed { grouping.collapsed.insert(label.to_string()) } else { grouping.collapsed.remove(label) };
+            if changed {
+                self.refilter();
+            }
+        }
+    }
+
+    /// Returns `true` if the group with the given label is collapsed.
+    pub fn is_group_collapsed(&self, label: &str) -> bool {
+        self.grouping.as_ref().is_some_and(|grouping| grouping.collapsed.contains(label))
+    }
+
+    /// Collapses every group.
+    pub fn collapse_all_groups(&mut self) {
+        let labels: Vec<String> = self.groups.iter().map(|group| group.label.clone()).collect();
+        if let Some(grouping) = self.grouping.as_mut() {
+            grouping.collapsed.extend(labels);
+            self.refilter();
+        }
+    }
+
+    /// Expands every group.
+    pub fn expand_all_groups(&mut self) {
+        if let Some(grouping) = self.grouping.as_mut() {
+            grouping.collapsed.clear();
+            self.refilter();
+        }
+    }
+
+    /// Works out the groups of the given records, in the order they are shown
+    /// in, and returns the records left shown once the collapsed groups hide
+    /// all but their first record, which their header is drawn over.
+    pub(crate) fn group_rows(&mut self, rows: Vec<usize>) -> Vec<usize> {
+        let grouping = match self.grouping.as_ref() {
+            Some(grouping) => grouping,
+            None => return rows,
+        };
+
+        let labels: Vec<String> = rows.iter().map(|&row| self.cell_text(row, &grouping.key)).collect();
+        let (mut shown, mut groups) = (Vec::with_capacity(rows.len()), Vec::new());
+        let (mut header_positions, mut footer_ends) = (Vec::new(), Vec::new());
+        let mut start = 0;
+        while start < rows.len() {
+            let end = (start..rows.len()).find(|&i| labels[i] != labels[start]).unwrap_or(rows.len());
+            let collapsed = grouping.collapsed.contains(&labels[start]);
+            let position = shown.len();
+            if collapsed {
+                shown.push(rows[start]);
+            }
+            else {
+                header_positions.push(position);
+                shown.extend_from_slice(&rows[start..end]);
+                if !self.group_aggregates.is_empty() {
+                    footer_ends.push(shown.len());
+                }
+            }
+
+            groups.push(Group { position, label: labels[start].clone(), rows: rows[start..end].to_vec(), collapsed });
+            start = end;
+        }
+
+        self.groups = groups;
+        self.group_header_positions = header_positions;
+        self.group_footer_ends = footer_ends;
+        shown
+    }
+
+    /// Shows the aggregate of the values of the column with the specified key
+    /// in a line under the records of every expanded group, such as the
+    /// subtotal of a column of prices. While the records of a group are
+    /// scrolled through, the line stays pinned to the bottom of the view until
+    /// the end of the group is reached.
+    pub fn set_group_aggregate(&mut self, key: &str, aggregate: Aggregate) {
+        self.group_aggregates.insert(key.to_string(), aggregate);
+        self.refilter();
+    }
+
+    /// Removes the group aggregate of the column with the specified key, if
+    /// any. The lines of aggregates are hidden once no column has one.
+    pub fn clear_group_aggregate(&mut self, key: &str) -> Option<Aggregate> {
+        let aggregate = self.group_aggregates.remove(key);
+        self.refilter();
+        aggregate
+    }
+
+    /// Returns the group aggregate of the values of the column with the
+    /// specified key in the group with the given label, if the column has one.
+    /// The aggregate covers the records of the group shown by the filter,
+    /// including the ones hidden by collapsing the group.
+    pub fn group_aggregate_value(&self, label: &str, key: &str) -> Option<FormulaValue> {
+        let group = self.groups.iter().find(|group| group.label == label)?;
+        self.group_aggregate_of(group, key)
+    }
+
+    fn group_aggregate_of(&self, group: &Group, key: &str) -> Option<FormulaValue> {
+        let aggregate = self.group_aggregates.get(key)?;
+        let x = self.columns.get_index_of(key)?;
+
+        let evaluation = self.evaluation();
+        let values: Vec<FormulaValue> = group.rows.iter().map(|&row| evaluation.value((x, row))).collect();
+        Some(aggregate.compute(&values))
+    }
+
+    /// Returns the number of header lines drawn above the record shown at the
+    /// given position and the ones before it. Collapsed groups draw their
+    /// header over their first record instead.
+    pub(crate) fn group_headers_through(&self, position: usize) -> usize {
+        self.group_header_positions.partition_point(|&header| header <= position)
+    }
+
+    /// Returns the number of footer lines drawn above the record shown at the
+    /// given position.
+    pub(crate) fn group_footers_before(&self, position: usize) -> usize {
+        self.group_footer_ends.partition_point(|&end| end <= position)
+    }
+
+    /// Returns the group of the record shown at the given position.
+    pub(crate) fn group_at(&self, position: usize) -> Option<&Group> {
+        let index = self.groups.partition_point(|group| group.position <= position).checked_sub(1)?;
+        self.groups.get(index)
+    }
+
+    /// Returns the collapsed group whose header is drawn over the record at
+    /// the given index, if any.
+    pub(crate) fn collapsed_group_of_row(&self, row: usize) -> Option<&Group> {
+        let position = self.shown_position(row);
+        self.group_at(position).filter(|group| group.collapsed && group.position == position && group.rows[0] == row)
+    }
+
+    /// Returns `true` if the cursor is on the header of a collapsed group.
+    pub(crate) fn cursor_on_collapsed_group(&self) -> bool {
+        self.cursor_pos.is_some_and(|(_, y)| self.collapsed_group_of_row(y).is_some())
+    }
+
+    /// Returns the expanded group whose header is on the given line of the
+    /// body, if any.
+    pub(crate) fn group_header_at_line(&self, line: usize) -> Option<&Group> {
+        let next = self.position_at_line(line).map_or(0, |position| position + 1);
+        if self.group_header_positions.binary_search(&next).is_err() || self.position_to_line(next) != line + 1 {
+            return None;
+        }
+        self.group_at(next)
+    }
+
+    /// Returns the expanded group whose footer is on the given line of the
+    /// body, if any.
+    pub(crate) fn group_footer_at_line(&self, line: usize) -> Option<&Group> {
+        let position = self.position_at_line(line)?;
+        let below = self.position_to_line(position) + self.record_height_at(position);
+        if self.group_footer_ends.binary_search(&(position + 1)).is_err() || below != line {
+            return None;
+        }
+        self.group_at(position)
+    }
+
+    /// Returns the expanded group whose footer is pinned to the given line,
+    /// which it is if the line is the last one in view and is taken up by the
+    /// group, while its own footer is further down.
+    pub(crate) fn pinned_group_footer(&self, line: usize) -> Option<&Group> {
+        if self.group_footer_ends.is_empty() || line + 1 != self.scroll_base.start_line + self.scroll_base.view_height {
+            return None;
+        }
+
+        let group = self.position_at_line(line).and_then(|position| self.group_at(position))?;
+        let last = group.position + group.rows.len() - 1;
+        let footer = self.position_to_line(last) + self.record_height_at(last);
+        Some(group).filter(|group| !group.collapsed && footer > line)
+    }
+
+    /// Collapses the group with the given label, or expands it, on behalf of
+    /// the user. The cursor moves to the header of the group.
+    pub(crate) fn toggle_group(&mut self, label: &str) -> EventResult {
+        let (first_row, collapsed) = match self.groups.iter().find(|group| group.label == label) {
+            Some(group) => (group.rows[0], group.collapsed),
+            None => return EventResult::Ignored,
+        };
+
+        self.set_group_collapsed(label, !collapsed);
+        let x = self.cursor_pos.map_or(0, |(x, _)| x);
+        self.set_cursor_pos(x, first_row);
+        self.scroll_to_row(first_row);
+        EventResult::Consumed(None)
+    }
+
+    /// Collapses the group of the cursor, or expands it, on behalf of the user.
+    pub(crate) fn toggle_cursor_group(&mut self) -> EventResult {
+        let label = self.cursor_pos
+            .and_then(|(_, y)| self.group_at(self.shown_position(y)))
+            .map(|group| group.label.clone());
+        match label {
+            Some(label) => self.toggle_group(&label),
+            None => EventResult::Ignored,
+        }
+    }
+
+    /// Returns the text of the header of the given group.
+    fn group_header_text(&self, group: &Group) -> String {
+        let ascii = self.monochrome || self.ascii_grid;
+        let marker = match (group.collapsed, ascii) {
+            (false, false) => EXPANDED_MARKER,
+            (true, false) => COLLAPSED_MARKER,
+            (false, true) => EXPANDED_MARKER_ASCII,
+            (true, true) => COLLAPSED_MARKER_ASCII,
+        };
+        let label = if group.label.is_empty() { "(empty)" } else { group.label.as_str() };
+        format!("{} {} ({})", marker, label, group.rows.len())
+    }
+
+    /// Draws the footer of the given group, with its aggregates, on the first
+    /// line of the given printer.
+    pub(crate) fn draw_group_footer(&self, printer: &Printer, group: &Group) {
+        self.draw_aggregates(printer, |key| self.group_aggregate_of(group, key));
+    }
+
+    /// Draws the header of the given group across the given printer, in the
+    /// style of the cursor if it is on the header.
+    pub(crate) fn draw_group_header(&self, printer: &Printer, group: &Group, cursor: bool) {
+        let mut style = Style::from(Effect::Bold);
+        if cursor {
+            style = style.combine(self.cell_style(printer.focused, false, true, false, false, false));
+        }
+
+        let text = self.group_header_text(group);
+        printer.with_style(style, |p| {
+            p.print_hline((0, 0), text.width().max(p.size.x), " ");
+            p.print((0, 0), &text);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::event::{Event, Key, MouseButton, MouseEvent};
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    fn inventory() -> SpreadsheetView<String> {
+        let mut view = SpreadsheetView::new();
+        view.push_column("kind".to_string(), ColumnDef::new("Kind"));
+        view.push_column("name".to_string(), ColumnDef::new("Name"));
+        let items = [("fruit", "pear"), ("veg", "leek"), ("fruit", "fig"), ("veg", "kale"), ("fruit", "lime")];
+        view.extend_records(items.iter().map(|(kind, name)| {
+            vec![("kind".to_string(), kind.to_string()), ("name".to_string(), name.to_string())].into_iter().collect()
+        }));
+        view
+    }
+
+    fn render(view: &mut SpreadsheetView<String>) -> Vec<String> {
+        let size = Vec2::new(20, 12);
+        view.layout(size);
+        view.render_to_string(size).lines().skip(2).map(|line| line.trim_end().to_string()).filter(|line| !line.is_empty()).collect()
+    }
+
+    #[test]
+    fn removing_the_group_column_ungroups() {
+        let mut view = inventory();
+        view.group_by("kind");
+        view.remove_column("kind");
+        assert_eq!(view.grouped_by(), None);
+
+        view.sort_records("name", true);
+        assert_eq!(view.sorted_by(), Some(("name", true)));
+        assert_eq!(render(&mut view)[0], "fig");
+    }
+
+    #[test]
+    fn groups_collapse_and_expand() {
+        let mut view = inventory();
+        view.group_by("kind");
+        assert_eq!(view.grouped_by(), Some("kind"));
+        assert_eq!(render(&mut view), [
+            "▾ fruit (3)", "fruit  │ pear", "fruit  │ fig", "fruit  │ lime",
+            "▾ veg (2)", "veg    │ leek", "veg    │ kale",
+        ]);
+        assert_eq!(view.line_to_row(0), None);
+        assert_eq!(view.line_to_row(1), Some((0, 0)));
+        assert_eq!(view.row_to_line(3), 5);
+
+        // Collapsing moves the cursor to the header, which skips the group.
+        view.set_cursor_pos(1, 1);
+        view.on_event(Event::CtrlChar('g'));
+        assert!(view.is_group_collapsed("fruit"));
+        assert_eq!(view.cursor_pos(), Some((1, 0)));
+        assert_eq!(render(&mut view), ["▸ fruit (3)", "▾ veg (2)", "veg    │ leek", "veg    │ kale"]);
+        view.on_event(Event::Key(Key::Down));
+        assert_eq!(view.cursor_pos(), Some((1, 3)));
+        view.on_event(Event::Key(Key::Up));
+        view.on_event(Event::Key(Key::Ins));
+        assert!(!view.is_group_collapsed("fruit"));
+
+        // Sorting keeps the groups together.
+        view.sort_records("name", true);
+        assert_eq!(render(&mut view)[1..4], ["fruit  │ fig", "fruit  │ lime", "fruit  │ pear"]);
+
+        // Clicking a header collapses its group.
+        let click = Event::Mouse { offset: Vec2::zero(), position: Vec2::new(0, 6), event: MouseEvent::Press(MouseButton::Left) };
+        view.on_event(click);
+        assert!(view.is_group_collapsed("veg"));
+
+        view.clear_grouping();
+        assert_eq!(render(&mut view).len(), 5);
+    }
+
+    #[test]
+    fn groups_have_pinned_subtotals() {
+        let mut view = inventory();
+        view.group_by("kind");
+        view.set_group_aggregate("name", Aggregate::Count);
+        assert_eq!(view.group_aggregate_value("fruit", "name"), Some(FormulaValue::Number(3.0)));
+        assert_eq!(render(&mut view), [
+            "▾ fruit (3)", "fruit  │ pear", "fruit  │ fig", "fruit  │ lime", "       │ 3",
+            "▾ veg (2)", "veg    │ leek", "veg    │ kale", "       │ 2",
+        ]);
+        assert_eq!(view.line_to_row(4), None);
+        assert_eq!(view.row_to_line(3), 6);
+
+        // The subtotal of a group stays in view while scrolling through it.
+        let size = Vec2::new(20, 5);
+        view.layout(size);
+        let text = view.render_to_string(size);
+        let lines: Vec<&str> = text.lines().skip(2).collect();
+        assert!(lines[0].starts_with("▾ fruit (3) ") && lines[1].starts_with("fruit  │ pear "));
+        assert!(lines[2].starts_with("       │ 3 "));
+
+        view.clear_group_aggregate("name");
+        assert_eq!(render(&mut view).len(), 7);
+    }
+}
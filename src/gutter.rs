@@ -0,0 +1,180 @@
+//! The gutter left of the columns, labelling every record with its number or
+//! a label of the application's choosing.
+
+use std::fmt::Display;
+use std::rc::Rc;
+
+use unicode_width::UnicodeWidthStr;
+
+use cursive::Printer;
+use cursive::align::HAlign;
+use cursive::theme::{ColorStyle, Style};
+
+use crate::{FROZEN_SEPARATOR, SpreadsheetView};
+use crate::text::{escape_controls, print_aligned};
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Sets whether a gutter left of the columns shows the number of every
+    /// record, counting from 1, e.g. so that users can tell each other about
+    /// "row 431". The numbers are those of the records rather than of their
+    /// position on screen, so they skip the records hidden by the filter. Like
+    /// the frozen columns, the gutter does not scroll horizontally. Off by
+    /// default.
+    pub fn set_row_numbers(&mut self, row_numbers: bool) {
+        if row_numbers {
+            self.set_row_labels(|row| (row + 1).to_string());
+        }
+        else {
+            self.clear_row_labels();
+        }
+    }
+
+    /// Builder version of `set_row_numbers`.
+    pub fn row_numbers(mut self, row_numbers: bool) -> Self {
+        self.set_row_numbers(row_numbers);
+        self
+    }
+
+    /// Shows a gutter like `set_row_numbers` does, with the label `cb` returns
+    /// for the record at each index in place of its number.
+    pub fn set_row_labels<F>(&mut self, cb: F)
+    where
+        F: Fn(usize) -> String + 'static,
+    {
+        self.row_labels = Some(Rc::new(cb));
+    }
+
+    /// Hides the gutter.
+    pub fn clear_row_labels(&mut self) {
+        self.row_labels = None;
+        self.gutter_width = 0;
+    }
+
+    /// Returns `true` if the gutter is shown.
+    pub fn has_gutter(&self) -> bool {
+        self.row_labels.is_some()
+    }
+
+    /// Sets the style the gutter is drawn in. By default, it is drawn in the
+    /// secondary color, or without any style in monochrome.
+    pub fn set_gutter_style(&mut self, style: Style) {
+        self.gutter_style = Some(style);
+    }
+
+    /// Returns the label of the record at the given index in the gutter, if
+    /// the gutter is shown.
+    pub fn row_label(&self, row: usize) -> Option<String> {
+        self.row_labels.as_ref().map(|cb| escape_controls(&cb(row)))
+    }
+
+    /// Returns the offset of the first column from the left edge of this view,
+    /// which is the width of the gutter and the separator to its right.
+    pub(crate) fn gutter_offset(&self) -> usize {
+        if self.has_gutter() { self.gutter_width + FROZEN_SEPARATOR.width() } else { 0 }
+    }
+
+    /// Recalculates the width of the gutter from the labels of the records.
+    /// Like the column widths, only the records of a source in view are
+    /// measured.
+    pub(crate) fn compute_gutter_width(&mut self) {
+        let rows = if self.has_source() { self.source_rows_in_view() } else { 0..self.records.len() };
+        self.gutter_width = rows
+            .filter_map(|row| self.row_label(row))
+            .map(|label| label.width())
+            .max()
+            .unwrap_or(0);
+    }
+
+    fn gutter_style(&self) -> Style {
+        match self.gutter_style {
+            Some(style) => style,
+            None if self.monochrome => Style::none(),
+            None => ColorStyle::secondary().into(),
+        }
+    }
+
+    /// Draws the part of the header above the gutter, which is blank.
+    pub(crate) fn draw_gutter_header(&self, printer: &Printer) {
+        if self.has_gutter() {
+            printer.print_hline((0, 0), self.gutter_width, " ");
+            self.print_grid(printer, self.gutter_width, self.vertical_separator(true));
+        }
+    }
+
+    /// Draws the part of a line of the body in the gutter, with the label of
+    /// the record at the given index if it is the line the values are on.
+    pub(crate) fn draw_gutter_line(&self, printer: &Printer, row: usize, is_text_line: bool) {
+        if !self.has_gutter() {
+            return;
+        }
+
+        let label = if is_text_line { self.row_label(row).unwrap_or_default() } else { String::new() };
+        printer.with_style(self.gutter_style(), |p| print_aligned(p, &label, self.gutter_width, &HAlign::Right));
+        self.print_grid(printer, self.gutter_width, self.vertical_separator(true));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::event::{Event, Key, MouseButton, MouseEvent};
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    fn view(count: u32) -> SpreadsheetView<u32> {
+        let mut view = SpreadsheetView::new().row_numbers(true);
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        view.push_column("sq".to_string(), ColumnDef::new("Square"));
+        view.extend_records((1..=count).map(|n| {
+            vec![("n".to_string(), n), ("sq".to_string(), n * n)].into_iter().collect()
+        }));
+        view
+    }
+
+    #[test]
+    fn gutter_labels_records() {
+        let mut view = view(10);
+        view.set_filter(|record| record["n"] % 3 != 0);
+        let text = view.render_to_string(Vec2::new(20, 6));
+        assert!(text.starts_with("   ║ N  │ Square\n"));
+        assert!(text.contains("\n 1 ║ 1  │ 1"));
+        assert!(text.contains("\n 4 ║ 4  │ 16"));
+
+        view.set_row_labels(|row| format!("r{}", row));
+        assert!(view.render_to_string(Vec2::new(20, 6)).contains("\nr0 ║ 1  │ 1"));
+
+        view.set_row_numbers(false);
+        assert!(!view.has_gutter());
+        assert!(view.render_to_string(Vec2::new(20, 6)).starts_with("N  │ Square\n"));
+    }
+
+    #[test]
+    fn gutter_stays_put_and_is_not_clicked() {
+        let mut view = view(2);
+        view.push_column("cube".to_string(), ColumnDef::new("Cube"));
+        view.render_to_string(Vec2::new(16, 4));
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::Key(Key::End));
+        assert_eq!(view.render_to_string(Vec2::new(16, 4)), [
+            "  ║ Cube",
+            "────────────────",
+            "1 ║",
+            "2 ║",
+        ].join("\n"));
+
+        view.on_event(Event::Key(Key::Home));
+        view.render_to_string(Vec2::new(16, 4));
+        let click = |x| Event::Mouse {
+            offset: Vec2::zero(),
+            position: Vec2::new(x, 3),
+            event: MouseEvent::Press(MouseButton::Left),
+        };
+        view.on_event(click(0));
+        assert_eq!(view.cursor_pos(), Some((0, 0)));
+        view.on_event(click(4));
+        assert_eq!(view.cursor_pos(), Some((0, 1)));
+    }
+}
@@ -0,0 +1,346 @@
+//! Undoing and redoing changes to cells, records, and columns.
+//!
+//! Changes refer to records by the position they were added in rather than
+//! by their index, so that sorting the records in between does not stop
+//! them from being undone.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::Range;
+use std::slice;
+
+use crate::{ChangeEvent, ColumnDef, Record, RowMeta, SpreadsheetView};
+
+/// Most changes kept to be undone. The oldest changes are dropped first.
+const MAX_CHANGES: usize = 1000;
+
+/// The value and the formula of a cell.
+#[derive(Clone)]
+pub(crate) struct CellContents<D> {
+    pub value: Option<D>,
+    pub formula: Option<String>,
+}
+
+impl<D: PartialEq> PartialEq for CellContents<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.formula == other.formula
+    }
+}
+
+/// A change that can be undone, by making the opposite change.
+pub(crate) enum Change<D> {
+    /// The contents of a cell were changed from `old` to `new`.
+    Cell { seq: u64, key: String, old: CellContents<D>, new: CellContents<D> },
+    /// Records were added.
+    RecordsInserted { seqs: Vec<u64> },
+    /// Records were removed from the given indices, in ascending order.
+    RecordsRemoved { records: Vec<(usize, Record<D>, RowMeta)> },
+    /// A column was added.
+    ColumnInserted { key: String },
+    /// A column was removed from the given index.
+    ColumnRemoved { index: usize, key: String, def: ColumnDef },
+}
+
+/// The changes that can be undone and redone, most recent last.
+pub(crate) struct UndoHistory<D> {
+    undo: Vec<Change<D>>,
+    redo: Vec<Change<D>>,
+    /// Clones records, so that removing them can be undone even where the
+    /// view does not know that its values can be cloned.
+    clone_record: fn(&Record<D>) -> Record<D>,
+}
+
+impl<D: Clone> UndoHistory<D> {
+    fn new() -> Self {
+        Self { undo: Vec::new(), redo: Vec::new(), clone_record: Record::clone }
+    }
+}
+
+impl<D> UndoHistory<D> {
+    /// Records a change made by the user or the application, which cannot
+    /// be redone past.
+    pub fn record(&mut self, change: Change<D>) {
+        if self.undo.len() == MAX_CHANGES {
+            self.undo.remove(0);
+        }
+        self.undo.push(change);
+        self.redo.clear();
+    }
+
+    pub fn clone_record(&self, record: &Record<D>) -> Record<D> {
+        (self.clone_record)(record)
+    }
+
+    /// Forgets every change, both those to undo and those to redo.
+    fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Records a change to be undone, if undoing is enabled.
+    pub(crate) fn record_change<F>(&mut self, change: F)
+    where
+        F: FnOnce(&UndoHistory<D>) -> Change<D>,
+    {
+        if let Some(history) = self.undo_history.as_mut() {
+            let change = change(history);
+            history.record(change);
+        }
+    }
+
+    /// Records that the records at the given indices were added.
+    pub(crate) fn record_insertion(&mut self, rows: Range<usize>) {
+        if self.undo_history.is_some() {
+            let seqs = self.row_meta[rows].iter().map(|meta| meta.seq).collect();
+            self.record_change(|_| Change::RecordsInserted { seqs });
+        }
+    }
+
+    /// Records that the given record was removed from `index`.
+    pub(crate) fn record_removal(&mut self, index: usize, record: &Record<D>, meta: Option<RowMeta>) {
+        self.record_removals(index, slice::from_ref(record), meta.map(|meta| vec![meta]));
+    }
+
+    /// Records that the given records were removed from `start` on.
+    pub(crate) fn record_removals(&mut self, start: usize, records: &[Record<D>], metas: Option<Vec<RowMeta>>) {
+        if let Some(metas) = metas {
+            self.record_change(|history| Change::RecordsRemoved {
+                records: records.iter().zip(metas).enumerate()
+                    .map(|(i, (record, meta))| (start + i, history.clone_record(record), meta))
+                    .collect(),
+            });
+        }
+    }
+
+    /// Records that the record at `index` replaced `old`, as one change per
+    /// cell that differs. `old_formulas` holds the sources of the formulas the
+    /// old record had, by column key.
+    pub(crate) fn record_replacement(&mut self, index: usize, old: &Record<D>, mut old_formulas: HashMap<String, String>) {
+        let (history, seq) = match (self.undo_history.as_mut(), self.row_meta.get(index)) {
+            (Some(history), Some(meta)) => (history, meta.seq),
+            _ => return,
+        };
+
+        let (mut old, mut new) = (history.clone_record(old), history.clone_record(&self.records[index]));
+        for key in self.columns.keys() {
+            let old = CellContents { value: old.remove(key), formula: old_formulas.remove(key) };
+            let new = CellContents { value: new.remove(key), formula: None };
+            if old != new {
+                history.record(Change::Cell { seq, key: key.clone(), old, new });
+            }
+        }
+    }
+
+    /// Forgets the changes recorded to be undone and redone, e.g. once every
+    /// record is replaced at once, leaving undoing enabled if it was.
+    pub(crate) fn forget_undo_history(&mut self) {
+        if let Some(history) = self.undo_history.as_mut() {
+            history.clear();
+        }
+    }
+
+    /// Returns the bookkeeping of the record at `index`, if it is needed to
+    /// undo removing the record.
+    pub(crate) fn meta_for_undo(&self, index: usize) -> Option<RowMeta> {
+        self.undo_history.as_ref().and(self.row_meta.get(index).cloned())
+    }
+
+    pub(crate) fn cell_contents(&self, row: usize, key: &str) -> CellContents<D>
+    where
+        D: Clone,
+    {
+        CellContents {
+            value: self.records.get(row).and_then(|record| record.get(key)).cloned(),
+            formula: self.formula(row, key).map(str::to_string),
+        }
+    }
+
+    /// Records that the contents of a cell were changed from `old`, unless
+    /// they are the same as before.
+    pub(crate) fn record_cell_change(&mut self, row: usize, key: &str, old: CellContents<D>)
+    where
+        D: Clone,
+    {
+        if self.undo_history.is_none() {
+            return;
+        }
+
+        let new = self.cell_contents(row, key);
+        if let Some(seq) = self.row_meta.get(row).map(|meta| meta.seq).filter(|_| old != new) {
+            let key = key.to_string();
+            self.record_change(|_| Change::Cell { seq, key, old, new });
+        }
+    }
+
+    /// Puts a value and a formula into a cell as they are, without parsing
+    /// them or calling the edit callbacks. Returns `false` if there is no such
+    /// cell or the formula is invalid.
+    pub(crate) fn write_cell_contents(&mut self, row: usize, key: &str, contents: CellContents<D>) -> bool
+    where
+        D: Clone,
+    {
+        if row >= self.records.len() || !self.columns.contains_key(key) {
+            return false;
+        }
+
+        let old = self.undo_history.as_ref().map(|_| self.cell_contents(row, key));
+        match contents.formula {
+            Some(source) => {
+                if self.set_formula(row, key, &source).is_err() {
+                    return false;
+                }
+            },
+            None => {
+                self.formulas.remove(&(row, key.to_string()));
+                match contents.value {
+                    Some(value) => self.records[row].insert(key.to_string(), value),
+                    None => self.records[row].remove(key),
+                };
+                self.invalidate_cell(row, key);
+                self.refilter();
+                self.notify(|| ChangeEvent::CellChanged { row, key: key.to_string() });
+            },
+        }
+
+        self.forget_sort_by(key);
+        if let Some(old) = old {
+            self.record_cell_change(row, key, old);
+        }
+        true
+    }
+
+    fn row_of_seq(&self, seq: u64) -> Option<usize> {
+        self.row_meta.iter().position(|meta| meta.seq == seq)
+    }
+}
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Sets whether edits to cells, added, removed, and replaced records,
+    /// removed columns, and columns added by pasting are recorded so that they
+    /// can be undone. Off by default, so that filling the view is not
+    /// recorded; turn it on once the initial records are in. Turning it off
+    /// forgets the recorded changes, as does replacing every record at once
+    /// with `set_records` or `reset_records`.
+    pub fn set_undo_enabled(&mut self, enabled: bool) {
+        match (enabled, self.undo_history.is_some()) {
+            (true, false) => self.undo_history = Some(UndoHistory::new()),
+            (false, true) => self.undo_history = None,
+            _ => {},
+        }
+    }
+
+    /// Returns `true` if changes are recorded so that they can be undone.
+    pub fn undo_enabled(&self) -> bool {
+        self.undo_history.is_some()
+    }
+
+    /// Returns `true` if there is a change to undo.
+    pub fn can_undo(&self) -> bool {
+        self.undo_history.as_ref().is_some_and(|history| !history.undo.is_empty())
+    }
+
+    /// Returns `true` if there is an undone change to redo.
+    pub fn can_redo(&self) -> bool {
+        self.undo_history.as_ref().is_some_and(|history| !history.redo.is_empty())
+    }
+
+    /// Undoes the last change, returning `false` if there was nothing to
+    /// undo. Changes to records that are gone since are skipped.
+    pub fn undo(&mut self) -> bool {
+        self.step_history(true)
+    }
+
+    /// Redoes the last change undone, returning `false` if there was nothing
+    /// to redo.
+    pub fn redo(&mut self) -> bool {
+        self.step_history(false)
+    }
+
+    fn step_history(&mut self, undo: bool) -> bool {
+        // Taken out so that applying the change does not record it again.
+        let mut history = match self.undo_history.take() {
+            Some(history) => history,
+            None => return false,
+        };
+
+        let mut applied = false;
+        loop {
+            let change = if undo { history.undo.pop() } else { history.redo.pop() };
+            let change = match change {
+                Some(change) => change,
+                None => break,
+            };
+
+            if let Some(inverse) = self.revert_change(change) {
+                if undo { history.redo.push(inverse) } else { history.undo.push(inverse) }
+                applied = true;
+                break;
+            }
+        }
+
+        self.undo_history = Some(history);
+        applied
+    }
+
+    /// Reverts a change, returning the change made by reverting it, or `None`
+    /// if it no longer applies.
+    fn revert_change(&mut self, change: Change<D>) -> Option<Change<D>> {
+        match change {
+            Change::Cell { seq, key, old, new } => {
+                let row = self.row_of_seq(seq)?;
+                if !self.write_cell_contents(row, &key, old.clone()) {
+                    return None;
+                }
+                Some(Change::Cell { seq, key, old: new, new: old })
+            },
+            Change::RecordsInserted { seqs } => {
+                let mut rows: Vec<usize> = seqs.iter().filter_map(|&seq| self.row_of_seq(seq)).collect();
+                rows.sort_unstable();
+
+                let mut records = Vec::with_capacity(rows.len());
+                for &row in rows.iter().rev() {
+                    let meta = self.row_meta[row].clone();
+                    let record = self.remove_record(row)?;
+                    records.push((row, record, meta));
+                }
+                records.reverse();
+
+                if records.is_empty() { None }
+                else { Some(Change::RecordsRemoved { records }) }
+            },
+            Change::RecordsRemoved { records } => {
+                let mut seqs = Vec::with_capacity(records.len());
+                for (index, record, meta) in records {
+                    let index = index.min(self.records.len());
+                    self.records.insert(index, record);
+                    self.remap_rows(|row| Some(if row >= index { row + 1 } else { row }));
+                    seqs.push(meta.seq);
+                    self.row_meta[index] = meta;
+                    self.notify(|| ChangeEvent::RowsInserted { start: index, count: 1 });
+                }
+
+                self.sort_spec.clear();
+                Some(Change::RecordsInserted { seqs })
+            },
+            Change::ColumnInserted { key } => {
+                let (index, _, _) = self.columns.get_full(&key)?;
+                let def = self.remove_column(&key)?;
+                Some(Change::ColumnRemoved { index, key, def })
+            },
+            Change::ColumnRemoved { index, key, def } => {
+                if self.columns.contains_key(&key) {
+                    return None;
+                }
+
+                let old_keys = self.column_keys();
+                let mut columns: Vec<_> = self.columns.drain(..).collect();
+                columns.insert(index.min(columns.len()), (key.clone(), def));
+                self.columns = columns.into_iter().collect();
+                self.reconcile_columns(&old_keys);
+                Some(Change::ColumnInserted { key })
+            },
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Sharing one copy of each distinct string among many cells.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::SpreadsheetView;
+
+/// A pool of shared strings. Cells of type `Rc<str>` made through the same
+/// interner share one copy of each distinct string, which greatly cuts the
+/// memory taken up by columns with few distinct values, like statuses or
+/// categories.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    /// Creates an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of the given string, adding it to the pool if
+    /// it is not there yet.
+    pub fn intern(&mut self, text: &str) -> Rc<str> {
+        match self.strings.get(text) {
+            Some(shared) => shared.clone(),
+            None => {
+                let shared: Rc<str> = Rc::from(text);
+                self.strings.insert(shared.clone());
+                shared
+            },
+        }
+    }
+
+    /// Returns the number of distinct strings in the pool.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if the pool is empty.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Drops the strings no cell uses anymore from the pool.
+    pub fn shrink(&mut self) {
+        self.strings.retain(|shared| Rc::strong_count(shared) > 1);
+    }
+}
+
+impl SpreadsheetView<Rc<str>> {
+    /// Replaces the values in the column with the given key by their shared
+    /// copies from the interner, e.g. after loading records whose strings were
+    /// not interned.
+    pub fn intern_column(&mut self, key: &str, interner: &mut Interner) {
+        for value in self.records.iter_mut().filter_map(|record| record.get_mut(key)) {
+            *value = interner.intern(value);
+        }
+    }
+
+    /// Uses the text of an edit as the new value, sharing it through the
+    /// interner.
+    pub fn set_interning_parser(&mut self, interner: Rc<RefCell<Interner>>) {
+        self.set_parser(move |s| Ok(interner.borrow_mut().intern(s)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ColumnDef;
+
+    #[test]
+    fn interned_strings_are_shared() {
+        let mut interner = Interner::new();
+        let a = interner.intern("open");
+        let b = interner.intern(&String::from("open"));
+        let c = interner.intern("closed");
+        assert!(Rc::ptr_eq(&a, &b));
+        assert_eq!(interner.len(), 2);
+
+        drop(c);
+        interner.shrink();
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn views_share_interned_cells() {
+        let mut view = SpreadsheetView::<Rc<str>>::new();
+        view.push_column("status".to_string(), ColumnDef::new("Status"));
+        view.extend_records((0..4).map(|_| {
+            vec![("status".to_string(), Rc::from("open"))].into_iter().collect()
+        }));
+
+        let interner = Rc::new(RefCell::new(Interner::new()));
+        view.intern_column("status", &mut interner.borrow_mut());
+        assert!(Rc::ptr_eq(&view.records[0]["status"], &view.records[3]["status"]));
+
+        view.set_read_only(false);
+        view.set_interning_parser(interner.clone());
+        view.write_cell_text(1, "status", "open").unwrap();
+        assert_eq!(Rc::strong_count(&view.records[0]["status"]), 5);
+        assert_eq!(interner.borrow().len(), 1);
+    }
+}
@@ -0,0 +1,100 @@
+//! Reading and writing records as JSON lines.
+//!
+//! Every line holds one record as a JSON object, mapping column keys to cell
+//! values, so that views can be fed from and into tools like `jq`. Missing
+//! keys and `null` values stand for missing cells.
+
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::{ColumnDef, Record, SpreadsheetView};
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Creates a view from JSON lines, with one column per key found in any
+    /// of the objects, in the order they are first found. The keys are used
+    /// as both the keys and the titles of the columns. Blank lines are
+    /// skipped.
+    pub fn from_jsonl<R: BufRead>(reader: R) -> io::Result<Self>
+    where
+        D: DeserializeOwned,
+    {
+        let mut view = Self::new();
+        let mut records = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let object: IndexMap<String, Option<D>> = serde_json::from_str(&line).map_err(|err| {
+                let message = format!("Line {}: {}", i + 1, err);
+                io::Error::new(io::ErrorKind::InvalidData, message)
+            })?;
+
+            let mut record = Record::new();
+            for (key, value) in object {
+                if !view.columns.contains_key(&key) {
+                    view.push_column(key.clone(), ColumnDef::new(&key));
+                }
+                if let Some(value) = value {
+                    record.insert(key, value);
+                }
+            }
+            records.push(record);
+        }
+
+        view.extend_records(records);
+        Ok(view)
+    }
+
+    /// Writes the records as JSON lines, in their current order. The keys of
+    /// each object follow the order of the columns, and missing cells are
+    /// left out.
+    pub fn to_jsonl<W: Write>(&self, mut writer: W) -> io::Result<()>
+    where
+        D: Serialize,
+    {
+        for record in &self.records {
+            writer.write_all(b"{")?;
+            let cells = self.columns.keys().filter_map(|key| record.get(key).map(|value| (key, value)));
+            for (i, (key, value)) in cells.enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut writer, key)?;
+                writer.write_all(b":")?;
+                serde_json::to_writer(&mut writer, value)?;
+            }
+            writer.write_all(b"}\n")?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_records() {
+        let text = "{\"id\":1,\"n\":10}\n\n{\"n\":20,\"extra\":null}\n{\"extra\":3,\"id\":2}\n";
+        let view = SpreadsheetView::<u32>::from_jsonl(text.as_bytes()).unwrap();
+        assert_eq!(view.column_keys(), ["id", "n", "extra"]);
+        assert_eq!(view.columns["extra"].title, "extra");
+        assert_eq!(view.records.len(), 3);
+        assert!(!view.records[1].contains_key("extra"));
+
+        let mut out = Vec::new();
+        view.to_jsonl(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"id\":1,\"n\":10}\n{\"n\":20}\n{\"id\":2,\"extra\":3}\n");
+
+        let err = SpreadsheetView::<u32>::from_jsonl("{\"n\":1}\n[2]\n".as_bytes()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("Line 2: "));
+    }
+}
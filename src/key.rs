@@ -0,0 +1,61 @@
+//! Typed column keys, as an alternative to spelling out the string keys of
+//! columns everywhere.
+
+use crate::Record;
+
+/// A type naming the columns of a view, usually a field-less enum, so that
+/// typos in column keys are caught by the compiler. Column keys are turned into
+/// string keys with `key`, e.g. to sort by them.
+///
+/// Callbacks still receive the string keys of columns, which `from_key` turns
+/// back into typed keys.
+pub trait ColumnKey: Copy + 'static {
+    /// Every column key, in the order the columns are usually shown.
+    const ALL: &'static [Self];
+
+    /// Returns the string key of this column.
+    fn key(self) -> &'static str;
+
+    /// Returns the column key with the given string key, if any.
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|column| column.key() == key)
+    }
+
+    /// Builds a record out of the values of its columns.
+    fn record<D, I>(cells: I) -> Record<D>
+    where
+        I: IntoIterator<Item = (Self, D)>,
+    {
+        cells.into_iter().map(|(column, value)| (column.key().to_string(), value)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Col {
+        Name,
+        Price,
+    }
+
+    impl ColumnKey for Col {
+        const ALL: &'static [Self] = &[Col::Name, Col::Price];
+
+        fn key(self) -> &'static str {
+            match self {
+                Col::Name => "name",
+                Col::Price => "price",
+            }
+        }
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        let record = Col::record(vec![(Col::Name, "tea"), (Col::Price, "4")]);
+        assert_eq!(record["price"], "4");
+        assert_eq!(Col::from_key("name"), Some(Col::Name));
+        assert_eq!(Col::from_key("Name"), None);
+    }
+}
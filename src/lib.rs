@@ -1,18 +1,137 @@
+mod aggregate;
+mod autosize;
+mod bindings;
+mod clipboard;
+mod compat;
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "csv-file")]
+mod csv_file;
+mod edit;
+mod ext;
+pub mod external;
+mod formula;
+mod formula_bar;
+mod grid;
+mod group;
+mod gutter;
+mod history;
+mod intern;
+#[cfg(feature = "serde_json")]
+mod jsonl;
+mod key;
+mod loader;
+mod overflow;
+mod read_only;
+mod render;
+mod renderer;
+mod search;
+mod source;
+mod state;
+mod status;
+mod style;
+mod text;
+mod value;
+mod visibility;
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::rc::Rc;
 use std::fmt::Display;
+use std::io;
+use std::mem;
+use std::ops::{Bound, Range, RangeBounds};
+use std::str::FromStr;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant, SystemTime};
 
 use indexmap::IndexMap;
+use unicode_width::UnicodeWidthStr;
 
 use cursive::Cursive;
+use cursive::Printer;
 use cursive::align::HAlign;
+use cursive::direction::Direction;
+use cursive::event::{Event, EventResult, Key, MouseButton, MouseEvent};
+use cursive::theme::{BaseColor, Color, ColorStyle, Effect, PaletteColor, Style};
 use cursive::vec::Vec2;
-use cursive::view::ScrollBase;
+use cursive::view::{ScrollBase, View};
+use cursive::views::{Dialog, TextView};
+
+use crate::formula::{Expr, FormulaCache, FormulaFunction, Sheet};
+use crate::formula_bar::FormulaBarState;
+use crate::clipboard::Clipboard;
+use crate::group::{Group, Grouping};
+use crate::history::{Change, UndoHistory};
+use crate::overflow::display_lines;
+use crate::search::{Search, SearchPrompt};
+use crate::text::{display_text, display_width, ellipsize_end, ellipsize_middle, escape_controls, escape_lines, print_aligned, split_tsv, widest_line};
+use crate::edit::{ActiveEdit, DialogEditor, EditOutcome, Editor, SpinnerEditor, TextEditor};
+#[cfg(feature = "chrono")]
+use crate::edit::DateEditor;
+
+pub use crate::aggregate::{Aggregate, AggregateFunction};
+pub use crate::autosize::ColumnFill;
+pub use crate::bindings::{Bindings, SpreadsheetAction, describe_event};
+pub use crate::compat::{TableCell, TableViewItem};
+pub use crate::edit::Spinner;
+pub use crate::ext::SpreadsheetViewExt;
+pub use crate::formula::{CellRange, FormulaError, FormulaValue, column_name};
+pub use crate::formula_bar::FormulaBarView;
+pub use crate::grid::GridMode;
+pub use crate::intern::Interner;
+pub use crate::key::ColumnKey;
+pub use crate::loader::RecordLoader;
+pub use crate::overflow::Overflow;
+pub use crate::renderer::{CellRenderer, DisplayRenderer};
+pub use crate::source::SpreadsheetSource;
+pub use crate::state::SpreadsheetState;
+pub use crate::style::SpreadsheetStyle;
+pub use crate::value::CellValue;
+#[cfg(feature = "chrono")]
+pub use crate::edit::DatePicker;
+#[cfg(feature = "csv-file")]
+pub use crate::csv_file::{CsvFile, CsvFileView};
+
+/// Separator drawn between adjacent columns.
+const COLUMN_SEPARATOR: &str = " │ ";
+
+/// Separator drawn between the frozen columns and the ones scrolled past them,
+/// as wide as `COLUMN_SEPARATOR`.
+const FROZEN_SEPARATOR: &str = " ║ ";
+
+/// Number of lines taken up by the header and its delimiter.
+const HEADER_HEIGHT: usize = 2;
+
+/// Marker drawn at the end of cells whose value fails validation.
+const ERROR_MARKER: &str = "!";
+
+/// Marker drawn in the corner of focused views, and its monochrome version.
+const FOCUS_MARKER: &str = "◆";
+const FOCUS_MARKER_ASCII: &str = "*";
+
+/// Glyphs drawn after the title of the column the records are sorted by, and
+/// their monochrome versions.
+const SORT_ASCENDING: &str = "▲";
+const SORT_DESCENDING: &str = "▼";
+const SORT_ASCENDING_ASCII: &str = "^";
+const SORT_DESCENDING_ASCII: &str = "v";
+
+/// Number of lines scrolled by one step of the mouse wheel.
+const WHEEL_SCROLL_LINES: usize = 3;
+
+/// Longest time between two clicks on a cell for them to count as a
+/// double-click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
 
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColumnWidth {
     Auto,
     Min(usize),
@@ -33,37 +152,658 @@ impl ColumnWidth {
     }
 }
 
+/// How records are grouped into bands to help keep one's place in tall tables.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Banding {
+    /// Draws a horizontal line after every band.
+    Line,
+    /// Draws every other band in a different color.
+    Stripe,
+}
+
+/// How a view shows that it has focus, to tell it apart from other views in
+/// multi-pane layouts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FocusIndicator {
+    /// Highlights the column titles.
+    Header,
+    /// Draws a marker in the top left corner, on the line under the titles.
+    Corner,
+    /// Draws the line under the titles in bold.
+    Delimiter,
+}
+
+/// How the title of a column is shortened when it does not fit, along with the
+/// sort glyph of the column the records are sorted by.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HeaderTruncation {
+    /// Replaces the middle of the title with an ellipsis, always keeping the
+    /// sort glyph.
+    Middle,
+    /// Replaces the end of the title with an ellipsis, always keeping the sort
+    /// glyph.
+    End,
+    /// Cuts off the end of the title followed by the sort glyph, so the glyph
+    /// goes first.
+    Clip,
+}
+
+/// The order records are sorted in by a column.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    /// Returns `Ascending` if `ascending`, or `Descending` otherwise.
+    pub fn from_ascending(ascending: bool) -> Self {
+        if ascending { Self::Ascending } else { Self::Descending }
+    }
+
+    /// Returns the opposite order.
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::Ascending => Self::Descending,
+            Self::Descending => Self::Ascending,
+        }
+    }
+
+    /// Turns the ordering of two values into the ordering of their records
+    /// sorted in this order.
+    fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            Self::Ascending => ordering,
+            Self::Descending => ordering.reverse(),
+        }
+    }
+}
+
+/// What the user selects and the cursor highlights: single cells, whole
+/// records, or whole columns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    Cell,
+    Row,
+    Column,
+}
+
+/// The definition of a column of a view: its title, how wide it is, and how
+/// its cells are aligned.
 pub struct ColumnDef {
     title: String,
     width: ColumnWidth,
     alignment: HAlign,
     selected: bool,
+    grow_only: bool,
+    style: Style,
+    overflow: Overflow,
+    multiline: bool,
+    hidden: bool,
+    read_only: bool,
+    /// The width the user resized this column to, if any.
+    resized_width: Option<usize>,
+    /// The widest this column has been laid out, for grow-only columns.
+    grown_width: usize,
+    /// The width this column was given by the last layout, if any.
+    laid_out_width: Option<usize>,
+    /// Data attached to the column by the application, which is not shown.
+    data: Option<Rc<dyn Any>>,
+}
+
+impl ColumnDef {
+    /// Creates a left-aligned column with the given title, sized to fit its
+    /// contents.
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            width: ColumnWidth::Auto,
+            alignment: HAlign::Left,
+            selected: false,
+            grow_only: false,
+            style: Style::none(),
+            overflow: Overflow::Clip,
+            multiline: false,
+            hidden: false,
+            read_only: false,
+            resized_width: None,
+            grown_width: 0,
+            laid_out_width: None,
+            data: None,
+        }
+    }
+
+    /// Sets the bounds of the width of this column.
+    pub fn width(mut self, width: ColumnWidth) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets how the cells of this column are aligned.
+    pub fn align(mut self, alignment: HAlign) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Sets whether this column is marked as selected.
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    /// Sets how the values too wide for this column are shown.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets whether the line breaks in the values of this column are kept,
+    /// e.g. for a column of notes. Each line of a value is then drawn on a
+    /// line of its own, making its record as tall as it needs to be, and is
+    /// cut off or wrapped as set by `overflow`. Off by default, which shows
+    /// line breaks as `␊`.
+    pub fn multiline(mut self, multiline: bool) -> Self {
+        self.multiline = multiline;
+        self
+    }
+
+    /// Sets whether this column is hidden, which leaves it out of the layout
+    /// while keeping its values and its definition.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Sets whether the cells of this column cannot be edited, even in views
+    /// that can be. Such cells are drawn dimmed.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Returns the title of this column.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the bounds of the width of this column.
+    pub fn get_width(&self) -> ColumnWidth {
+        self.width
+    }
+
+    /// Returns how the cells of this column are aligned.
+    pub fn get_alignment(&self) -> &HAlign {
+        &self.alignment
+    }
+
+    /// Returns `true` if this column is marked as selected.
+    pub fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    /// Returns how the values too wide for this column are shown.
+    pub fn get_overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// Returns `true` if the line breaks in the values of this column are
+    /// kept.
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    /// Returns `true` if this column is hidden.
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Returns `true` if the cells of this column cannot be edited.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Sets the style of every cell in this column, e.g. to dim a column of
+    /// identifiers. Row styles and the cell style callback of the view are
+    /// drawn over it.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Returns the style of every cell in this column.
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// Attaches data to this column that is not shown, such as the units of
+    /// its values or the name of the field backing it. Replaces any data
+    /// attached before.
+    pub fn set_data<T: Any>(&mut self, data: T) {
+        self.data = Some(Rc::new(data));
+    }
+
+    /// Returns the data attached to this column, if there is any and it has
+    /// the given type.
+    pub fn data<T: Any>(&self) -> Option<&T> {
+        self.data.as_ref()?.downcast_ref()
+    }
+}
+
+impl Clone for ColumnDef {
+    fn clone(&self) -> Self {
+        // `HAlign` cannot be cloned itself.
+        #[allow(clippy::needless_match)]
+        let alignment = match self.alignment {
+            HAlign::Left => HAlign::Left,
+            HAlign::Center => HAlign::Center,
+            HAlign::Right => HAlign::Right,
+        };
+
+        Self {
+            title: self.title.clone(),
+            width: self.width,
+            alignment,
+            selected: self.selected,
+            grow_only: self.grow_only,
+            style: self.style,
+            overflow: self.overflow,
+            multiline: self.multiline,
+            hidden: self.hidden,
+            read_only: self.read_only,
+            resized_width: self.resized_width,
+            grown_width: self.grown_width,
+            laid_out_width: self.laid_out_width,
+            data: self.data.clone(),
+        }
+    }
 }
 
 pub type Record<D> = HashMap<String, D>;
 
-/// Callback for when a column is sorted. Takes the column and ordering as input.
+/// A cell whose value was rejected by the validator of its column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The index of the record containing the cell.
+    pub row: usize,
+    /// The key of the column containing the cell.
+    pub key: String,
+    /// The message returned by the validator.
+    pub message: String,
+}
+
+/// A change made to a cell through one of its editors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellChange<D> {
+    /// The value of the cell before the change, if it had one.
+    pub old: Option<D>,
+    /// The value of the cell after the change.
+    pub new: D,
+    /// When the change was made.
+    pub time: SystemTime,
+}
+
+/// A change to the records of a view, as reported to the data-changed callback.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChangeEvent {
+    /// `count` records were inserted, the first of them at index `start`.
+    RowsInserted { start: usize, count: usize },
+    /// `count` records were removed, the first of them from index `start`.
+    RowsRemoved { start: usize, count: usize },
+    /// The value of a cell was changed, or the cell was emptied.
+    CellChanged { row: usize, key: String },
+    /// The records were sorted by a column.
+    Sorted { key: String, ascending: bool },
+    /// Any number of records may have changed at once, e.g. during a batch
+    /// update, so anything derived from them should be rebuilt.
+    Reset,
+}
+
+/// Cells about to be pasted into a view, which the paste callback may change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Paste {
+    /// The index of the record the first row of cells goes into.
+    pub row: usize,
+    /// The index of the column the first cell of every row goes into.
+    pub column: usize,
+    /// The text of the cells, row by row.
+    pub cells: Vec<Vec<String>>,
+    /// Whether rows going past the last record are added as new records,
+    /// rather than dropped. Off by default.
+    pub append_rows: bool,
+    /// Whether cells going past the last column are put into new columns,
+    /// keyed and titled by their letter, rather than dropped. Off by default.
+    pub append_columns: bool,
+}
+
+/// Callback for when a column is sorted. Takes the column and ordering as input,
+/// with `Ordering::Less` meaning ascending.
 type OnSortCallback = Rc<dyn Fn(&mut Cursive, &str, Ordering)>;
 
-/// Callback taking as argument the row and the index of an element.
+/// Callback taking as argument the row and the index of an element, such as the
+/// column of a cell.
 type IndexCallback = Rc<dyn Fn(&mut Cursive, usize, usize)>;
 
+/// Callback for when a cell is submitted. Takes the row, the column index, and
+/// the column key of the cell as input.
+type SubmitCallback = Rc<dyn Fn(&mut Cursive, usize, usize, &str)>;
+
+/// Callback taking as argument the key of a column.
+type ColumnCallback = Rc<dyn Fn(&mut Cursive, &str)>;
+
+/// Callback for when a cell is edited. Takes the row, the column key, the old
+/// value of the cell (if any) and the new value as input.
+type OnEditCallback<D> = Rc<dyn Fn(&mut Cursive, usize, &str, Option<&D>, &D)>;
+
+/// Callback for when editing a cell begins or is abandoned. Takes the row and
+/// the column key of the cell as input.
+type EditCellCallback = Rc<dyn Fn(&mut Cursive, usize, &str)>;
+
+/// Converts the text typed into an editor back into a cell value, or returns
+/// a message describing why the text is invalid.
+type ParseCallback<D> = Rc<dyn Fn(&str) -> Result<D, String>>;
+
+/// Callback for auditing a change to a cell. Takes the row, the column key, and
+/// the change as input.
+type AuditCallback<D> = Rc<dyn Fn(&mut Cursive, usize, &str, &CellChange<D>)>;
+
+/// Checks a cell value, returning a message describing the problem if the value
+/// is invalid.
+type ValidateCallback<D> = Rc<dyn Fn(&D) -> Result<(), String>>;
+
+/// Compares two cell values of a column, in place of their `Ord`.
+type CompareCallback<D> = Rc<dyn Fn(&D, &D) -> Ordering>;
+
+/// Turns a cell value of a column into the text shown, in place of its
+/// `Display`.
+type FormatCallback<D> = Rc<dyn Fn(&D) -> String>;
+
+/// Labels the record at the given index in the gutter.
+type RowLabelCallback = Rc<dyn Fn(usize) -> String>;
+
+/// Decides whether a row style applies to a record.
+type RowPredicate<D> = Rc<dyn Fn(&Record<D>) -> bool>;
+
+/// Opens the text of a cell in an external program and returns the edited text.
+type ExternalEditCallback = Rc<dyn Fn(&str) -> io::Result<String>>;
+
+/// Callback for when cells are about to be pasted into the view. Returns
+/// `false` to cancel the paste.
+type PasteCallback = Rc<dyn Fn(&mut Paste) -> bool>;
+
+/// Callback for when many cells were written at once, e.g. by a paste. Takes
+/// the range of the cells as input.
+type BatchEditCallback = Rc<dyn Fn(&mut Cursive, &CellRange)>;
+
+/// Callback for when the user turns editing on or off. Takes whether the view
+/// is now read-only as input.
+type ReadOnlyCallback = Rc<dyn Fn(&mut Cursive, bool)>;
+
+/// Callback for when the user changes the selection. Takes the number of
+/// selected cells as input.
+type SelectionCallback = Rc<dyn Fn(&mut Cursive, usize)>;
+
+/// Receives a plain-text description of a change in the state of the view.
+type AnnounceCallback = Rc<dyn Fn(&str)>;
+
+/// Callback for when the records of the view change.
+type DataChangedCallback = Rc<dyn Fn(ChangeEvent)>;
+
+/// Callback for when the user changes the records of the view.
+type ChangeCallback = Rc<dyn Fn(&mut Cursive, &ChangeEvent)>;
+
+/// Callback for when the view is laid out at a new size.
+type LayoutCallback = Rc<dyn Fn(Vec2)>;
+
+/// Callback for when a column is laid out at a new width. Takes the column key
+/// and its new width as input.
+type ColumnResizeCallback = Rc<dyn Fn(&str, usize)>;
+
+/// Callback for styling a cell by its value. Takes the row index, the column
+/// key, and the value of the cell as input.
+type CellStyleCallback<D> = Rc<dyn Fn(usize, &str, &D) -> Option<Style>>;
+
+/// Bookkeeping kept alongside every record, following it as it moves.
+#[derive(Clone, Debug)]
+struct RowMeta {
+    /// The position of the record in the order records were added in.
+    seq: u64,
+    /// When the record was added or last refreshed, for expiring records.
+    time: SystemTime,
+    /// Data attached to the record by the application, which is not shown.
+    data: Option<Rc<dyn Any>>,
+}
+
+/// A formula entered into a cell, along with its parsed form.
+struct Formula {
+    source: String,
+    expr: Expr,
+}
+
+/// Formulas being evaluated against the cells of a view, keeping track of the
+/// cells on the way to catch formulas that refer back to themselves.
+struct Evaluation<'a, D: Display + Ord> {
+    view: &'a SpreadsheetView<D>,
+    visiting: RefCell<Vec<(usize, usize)>>,
+}
+
+impl<'a, D: Display + Ord> Sheet for Evaluation<'a, D> {
+    fn value(&self, (x, y): (usize, usize)) -> FormulaValue {
+        let (key, record) = match (self.view.columns.get_index(x), self.view.records.get(y)) {
+            (Some((key, _)), Some(record)) => (key, record),
+            _ => return FormulaError::Ref.into(),
+        };
+
+        if let Some(&reader) = self.visiting.borrow().last() {
+            self.view.formula_cache.borrow_mut().add_dependency((x, y), reader);
+        }
+
+        match self.view.formula_at(y, key) {
+            Some(formula) => {
+                if let Some(value) = self.view.formula_cache.borrow().get((x, y)) {
+                    return value.clone();
+                }
+
+                if self.visiting.borrow().contains(&(x, y)) {
+                    return FormulaError::Cycle.into();
+                }
+
+                self.visiting.borrow_mut().push((x, y));
+                let value = formula::evaluate(&formula.expr, self, &self.view.functions, (x, y));
+                self.visiting.borrow_mut().pop();
+
+                self.view.formula_cache.borrow_mut().insert((x, y), value.clone());
+                value
+            },
+            None => record.get(key).map_or(FormulaValue::Empty, |d| FormulaValue::from_text(&d.to_string())),
+        }
+    }
+
+    fn named_range(&self, name: &str) -> Option<CellRange> {
+        self.view.named_range(name)
+    }
+
+    fn column_index(&self, key: &str) -> Option<usize> {
+        self.view.columns.get_index_of(key)
+    }
+}
+
+/// Compares two cells, with the given comparator if any. Empty cells go
+/// first.
+fn compare_values<D: Ord>(a: Option<&D>, b: Option<&D>, comparator: Option<&CompareCallback<D>>) -> Ordering {
+    match (a, b, comparator) {
+        (Some(a), Some(b), Some(comparator)) => comparator(a, b),
+        (a, b, _) => a.cmp(&b),
+    }
+}
+
+/// Compares two records by their values in the columns of a sort, the most
+/// significant first, with empty cells first in ascending columns.
+fn compare_records<D: Ord>(
+    a: &Record<D>,
+    b: &Record<D>,
+    spec: &[(String, SortOrder)],
+    comparators: &HashMap<String, CompareCallback<D>>,
+) -> Ordering {
+    spec.iter()
+        .map(|(key, order)| order.apply(compare_values(a.get(key), b.get(key), comparators.get(key))))
+        .find(|&o| o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
 pub struct SpreadsheetView<D: Display + Ord> {
     columns: IndexMap<String, ColumnDef>,
     records: Vec<Record<D>>,
+    row_meta: Vec<RowMeta>,
+    next_seq: u64,
+    max_records: Option<usize>,
+    row_ttl: Option<Duration>,
 
     enabled: bool,
     scroll_base: ScrollBase,
     last_size: Vec2,
+    /// Whether this view had focus when it was last drawn.
+    last_focused: Cell<bool>,
+    row_height: usize,
+    /// The number of lines taken up by the records shown before each position
+    /// as of the last layout, if any column wraps or is multi-line.
+    record_lines: Vec<usize>,
+    max_row_height: Option<usize>,
+    frozen_columns: usize,
+    status_bar: bool,
+    status_aggregates: Vec<Aggregate>,
+    /// Labels the records in the gutter left of the columns, if shown.
+    row_labels: Option<RowLabelCallback>,
+    gutter_style: Option<Style>,
+    /// The width of the labels in the gutter, as of the last layout.
+    gutter_width: usize,
+    /// The index of the first column drawn after the frozen ones.
+    first_column: usize,
+    horizontal_scrollbar: bool,
+    column_fill: ColumnFill,
+    measure_visible_rows: bool,
+    /// Whether the horizontal scrollbar is drawn, as of the last layout.
+    horizontal_scrollbar_shown: bool,
+    banding: Option<(usize, Banding)>,
+    monochrome: bool,
+    style: SpreadsheetStyle,
+    grid_mode: GridMode,
+    ascii_grid: bool,
+    row_styles: Vec<(RowPredicate<D>, Style)>,
+    cell_style: Option<CellStyleCallback<D>>,
+    filter: Option<RowPredicate<D>>,
+    /// The indices of the records the filter holds for, in ascending order,
+    /// if there is a filter.
+    shown_rows: Option<Vec<usize>>,
+    grouping: Option<Grouping>,
+    /// The groups of the records as of the last time the records shown were
+    /// worked out, in the order they are shown in.
+    groups: Vec<Group>,
+    /// The shown positions of the first records of the expanded groups, which
+    /// have a header line above them.
+    group_header_positions: Vec<usize>,
+    group_aggregates: HashMap<String, Aggregate>,
+    /// The shown positions just past the last records of the expanded groups,
+    /// which have a line of aggregates under them if any column has a group
+    /// aggregate.
+    group_footer_ends: Vec<usize>,
+    focus_indicator: Option<FocusIndicator>,
+    header_truncation: HeaderTruncation,
     read_only: bool,
+    read_only_indicator: Option<String>,
+    /// The cells that cannot be edited, by record index and column key.
+    read_only_cells: HashSet<(usize, String)>,
+    edit_in_place: bool,
+
+    /// The keys of the columns the records are sorted by, most significant
+    /// first.
+    sort_spec: Vec<(String, SortOrder)>,
+    key_column: Option<String>,
+
+    /// How many batch updates are in progress, the sort from before the
+    /// outermost one began, and whether the records changed since.
+    batch_depth: usize,
+    batch_sort_spec: Vec<(String, SortOrder)>,
+    batch_changed: Cell<bool>,
 
     cursor_pos: Option<(usize, usize)>,
     selected_cells: HashSet<(usize, usize)>,
+    /// The corner of the rectangular selection opposite the cursor, while the
+    /// user is extending it.
+    selection_anchor: Option<(usize, usize)>,
+    selection_mode: SelectionMode,
+    /// Whether the user is picking a column rather than moving the cursor.
     column_select: bool,
+    /// The index of the column highlighted in column-select mode.
+    highlighted_column: usize,
+
+    column_widths: Vec<usize>,
+    parser: Option<ParseCallback<D>>,
+    validators: HashMap<String, ValidateCallback<D>>,
+    comparators: HashMap<String, CompareCallback<D>>,
+    column_aggregates: HashMap<String, Aggregate>,
+    formatters: HashMap<String, FormatCallback<D>>,
+    renderers: HashMap<String, Rc<dyn CellRenderer<D>>>,
+    spinners: HashMap<String, Spinner>,
+    #[cfg(feature = "chrono")]
+    date_pickers: HashMap<String, DatePicker>,
+    active_edit: Option<ActiveEdit>,
+    search: Option<Search>,
+    search_prompt: Option<SearchPrompt>,
+    search_ignore_case: bool,
+    #[cfg(feature = "regex")]
+    search_regex: bool,
+    external_editor: ExternalEditCallback,
+    bindings: Bindings,
+    track_history: bool,
+    history: HashMap<(usize, String), Vec<CellChange<D>>>,
+    undo_history: Option<UndoHistory<D>>,
+    clipboard: Option<Clipboard<D>>,
+    /// Where the records come from, in place of `records`, if anywhere.
+    source: Option<Box<dyn SpreadsheetSource<D>>>,
+    /// Receives the records sent by loaders, while they are loading.
+    loader: Option<Receiver<Vec<Record<D>>>>,
+    /// The column being resized by dragging the separator to its right, and
+    /// the offset of its left edge from the left edge of this view.
+    resizing_column: Option<(usize, usize)>,
+    /// When the last click on a cell was, and the `(column, row)` of the cell.
+    last_click: Option<(Instant, (usize, usize))>,
+
+    formulas_enabled: bool,
+    formulas: HashMap<(usize, String), Formula>,
+    names: IndexMap<String, CellRange>,
+    functions: HashMap<String, FormulaFunction>,
+    formula_cache: RefCell<FormulaCache>,
+    formula_bar: Option<Rc<RefCell<FormulaBarState>>>,
+    /// The text of the cells of the records last drawn, by record index and
+    /// then column index, so that redrawing them does not look them up and
+    /// format them again. Only the cells drawn are formatted.
+    visible_cells: RefCell<HashMap<usize, Vec<Option<String>>>>,
 
     on_sort: Option<OnSortCallback>,
-    on_submit: Option<IndexCallback>,
+    on_submit: Option<SubmitCallback>,
+    on_column_select: Option<ColumnCallback>,
+    on_column_submit: Option<ColumnCallback>,
     on_select: Option<IndexCallback>,
+    on_edit: Option<OnEditCallback<D>>,
+    on_edit_begin: Option<EditCellCallback>,
+    on_edit_cancel: Option<EditCellCallback>,
+    on_locked_edit_attempt: Option<EditCellCallback>,
+    on_audit: Option<AuditCallback<D>>,
+    on_selection_changed: Option<SelectionCallback>,
+    on_paste: Option<PasteCallback>,
+    on_batch_edit: Option<BatchEditCallback>,
+    on_read_only_toggle: Option<ReadOnlyCallback>,
+    on_announce: Option<AnnounceCallback>,
+    on_data_changed: Option<DataChangedCallback>,
+    on_change: Option<ChangeCallback>,
+    /// The changes made while handling the current event, for the change
+    /// callback.
+    pending_changes: RefCell<Vec<ChangeEvent>>,
+    /// Whether the records changed since the view was last marked clean.
+    dirty: Cell<bool>,
+    on_layout: Option<LayoutCallback>,
+    on_column_resize: Option<ColumnResizeCallback>,
 }
 
 impl<D: Display + Ord> Default for SpreadsheetView<D> {
@@ -79,19 +819,120 @@ impl<D: Display + Ord> SpreadsheetView<D> {
         Self {
             columns: IndexMap::new(),
             records: Vec::new(),
+            row_meta: Vec::new(),
+            next_seq: 0,
+            max_records: None,
+            row_ttl: None,
 
             enabled: true,
             scroll_base: ScrollBase::new(),
             last_size: Vec2::new(0, 0),
+            last_focused: Cell::new(true),
+            row_height: 1,
+            record_lines: Vec::new(),
+            max_row_height: None,
+            frozen_columns: 0,
+            status_bar: false,
+            status_aggregates: vec![Aggregate::Sum, Aggregate::Average],
+            row_labels: None,
+            gutter_style: None,
+            gutter_width: 0,
+            first_column: 0,
+            horizontal_scrollbar: false,
+            column_fill: ColumnFill::None,
+            measure_visible_rows: false,
+            horizontal_scrollbar_shown: false,
+            banding: None,
+            monochrome: false,
+            style: SpreadsheetStyle::default(),
+            grid_mode: GridMode::default(),
+            ascii_grid: false,
+            row_styles: Vec::new(),
+            cell_style: None,
+            filter: None,
+            shown_rows: None,
+            grouping: None,
+            groups: Vec::new(),
+            group_header_positions: Vec::new(),
+            group_aggregates: HashMap::new(),
+            group_footer_ends: Vec::new(),
+            focus_indicator: None,
+            header_truncation: HeaderTruncation::Middle,
             read_only: true,
+            read_only_indicator: None,
+            read_only_cells: HashSet::new(),
+            edit_in_place: false,
+
+            sort_spec: Vec::new(),
+            key_column: None,
+
+            batch_depth: 0,
+            batch_sort_spec: Vec::new(),
+            batch_changed: Cell::new(false),
 
             cursor_pos: None,
             selected_cells: HashSet::new(),
+            selection_anchor: None,
+            selection_mode: SelectionMode::Cell,
             column_select: false,
+            highlighted_column: 0,
+
+            column_widths: Vec::new(),
+            parser: None,
+            validators: HashMap::new(),
+            comparators: HashMap::new(),
+            column_aggregates: HashMap::new(),
+            formatters: HashMap::new(),
+            renderers: HashMap::new(),
+            spinners: HashMap::new(),
+            #[cfg(feature = "chrono")]
+            date_pickers: HashMap::new(),
+            active_edit: None,
+            search: None,
+            search_prompt: None,
+            search_ignore_case: false,
+            #[cfg(feature = "regex")]
+            search_regex: false,
+            external_editor: Rc::new(external::edit_in_external_editor),
+            bindings: Bindings::default(),
+            track_history: false,
+            history: HashMap::new(),
+            undo_history: None,
+            clipboard: None,
+            source: None,
+            loader: None,
+            resizing_column: None,
+            last_click: None,
+
+            formulas_enabled: false,
+            formulas: HashMap::new(),
+            names: IndexMap::new(),
+            functions: formula::builtin_functions(),
+            formula_cache: RefCell::new(FormulaCache::default()),
+            formula_bar: None,
+            visible_cells: RefCell::new(HashMap::new()),
 
             on_sort: None,
             on_submit: None,
+            on_column_select: None,
+            on_column_submit: None,
             on_select: None,
+            on_edit: None,
+            on_edit_begin: None,
+            on_edit_cancel: None,
+            on_locked_edit_attempt: None,
+            on_audit: None,
+            on_selection_changed: None,
+            on_paste: None,
+            on_batch_edit: None,
+            on_read_only_toggle: None,
+            on_announce: None,
+            on_data_changed: None,
+            on_change: None,
+            pending_changes: RefCell::new(Vec::new()),
+            dirty: Cell::new(false),
+            on_layout: None,
+            on_column_resize: None,
         }
     }
 
@@ -108,16 +949,216 @@ impl<D: Display + Ord> SpreadsheetView<D> {
         self
     }
 
+    /// Builder version of `push_column`, for making a view in one expression.
+    pub fn column(mut self, key: String, column_def: ColumnDef) -> Self {
+        self.push_column(key, column_def);
+        self
+    }
+
+    /// Appends a column with a typed key to this view.
+    pub fn push_key_column<K: ColumnKey>(&mut self, key: K, column_def: ColumnDef) {
+        self.push_column(key.key().to_string(), column_def);
+    }
+
+    /// Returns the typed key of the column under the cursor, if there is a
+    /// cursor and its column has a key of the given type.
+    pub fn cursor_key<K: ColumnKey>(&self) -> Option<K> {
+        self.cursor_column().and_then(K::from_key)
+    }
+
+    /// Attaches data to the column with the given key that is not shown, e.g.
+    /// for renderers or comparators to look up. Returns `false` if there is no
+    /// such column.
+    pub fn set_column_data<T: Any>(&mut self, key: &str, data: T) -> bool {
+        match self.columns.get_mut(key) {
+            Some(def) => {
+                def.set_data(data);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns the data attached to the column with the given key, if there
+    /// is any and it has the given type.
+    pub fn column_data<T: Any>(&self, key: &str) -> Option<&T> {
+        self.columns.get(key)?.data()
+    }
+
     /// Removes and returns the column with the specified key from this view,
     /// or `None` if there is no such column.
     pub fn remove_column(&mut self, key: &str) -> Option<ColumnDef> {
-        self.columns.shift_remove(key)
+        let old_keys = self.column_keys();
+        let (index, key, column_def) = self.columns.shift_remove_full(key)?;
+        self.reconcile_columns(&old_keys);
+        self.record_change(|_| Change::ColumnRemoved { index, key, def: column_def.clone() });
+        Some(column_def)
     }
 
     /// Removes and returns the last column from this view, or `None` if there
     /// are no columns.
     pub fn pop_column(&mut self) -> Option<ColumnDef> {
-        self.columns.pop().map(|(_, v)| v)
+        let old_keys = self.column_keys();
+        let (key, column_def) = self.columns.pop()?;
+        self.reconcile_columns(&old_keys);
+        let index = self.columns.len();
+        self.record_change(|_| Change::ColumnRemoved { index, key, def: column_def.clone() });
+        Some(column_def)
+    }
+
+    /// Moves the column with the specified key to the given index, shifting
+    /// the columns in between over. The cursor and the selection follow the
+    /// columns to their new positions. Returns `false` if there is no such
+    /// column.
+    pub fn move_column(&mut self, key: &str, index: usize) -> bool {
+        let from = match self.columns.get_full(key) {
+            Some((from, _, _)) => from,
+            None => return false,
+        };
+
+        let to = index.min(self.columns.len() - 1);
+        if from != to {
+            let old_keys = self.column_keys();
+            self.columns.move_index(from, to);
+            self.reconcile_columns(&old_keys);
+        }
+        true
+    }
+
+    /// Moves the column of the cursor, or the highlighted column in
+    /// column-select mode, one place to the left or right, on behalf of the
+    /// user.
+    fn move_cursor_column(&mut self, right: bool) -> EventResult {
+        let (old_x, x) = match self.current_column() {
+            Some(x) if right && x + 1 < self.columns.len() => (x, x + 1),
+            Some(x) if !right && x > 0 => (x, x - 1),
+            _ => return EventResult::Ignored,
+        };
+
+        let key = self.columns.get_index(old_x).map(|(key, _)| key.clone()).unwrap_or_default();
+        self.move_column(&key, x);
+        if self.column_select {
+            self.highlighted_column = x;
+        }
+        self.scroll_to_column_index(x);
+        EventResult::Consumed(None)
+    }
+
+    /// Removes all columns from this view. The records keep their values.
+    pub fn clear_columns(&mut self) {
+        self.set_columns(IndexMap::new());
+    }
+
+    /// Replaces the columns of this view. The cursor and the selection follow
+    /// the columns that are kept to their new positions, and any sort or edit
+    /// of a column that is dropped is forgotten.
+    pub fn set_columns<I>(&mut self, columns: I)
+    where
+        I: IntoIterator<Item = (String, ColumnDef)>,
+    {
+        let old_keys = self.column_keys();
+        self.columns = columns.into_iter().collect();
+        self.reconcile_columns(&old_keys);
+    }
+
+    fn column_keys(&self) -> Vec<String> {
+        self.columns.keys().cloned().collect()
+    }
+
+    /// Brings the state that refers to columns by position or by key in line
+    /// with the current columns, given the keys the columns had before.
+    fn reconcile_columns(&mut self, old_keys: &[String]) {
+        let columns = &self.columns;
+        let new_index = |x: usize| old_keys.get(x).and_then(|key| columns.get_full(key)).map(|(i, _, _)| i);
+
+        // A cursor on a dropped column stays in its row, as close as possible.
+        self.cursor_pos = self.cursor_pos.and_then(|(x, y)| {
+            let last = columns.len().checked_sub(1)?;
+            Some((new_index(x).unwrap_or_else(|| x.min(last)), y))
+        });
+
+        self.selected_cells = self.selected_cells.iter()
+            .filter_map(|&(x, y)| new_index(x).map(|x| (x, y)))
+            .collect();
+        self.selection_anchor = None;
+
+        if self.active_edit.as_ref().is_some_and(|edit| !columns.contains_key(&edit.key)) {
+            self.active_edit = None;
+        }
+
+        // Grouping by a dropped column leaves the records ungrouped.
+        if self.grouping.as_ref().is_some_and(|grouping| !self.columns.contains_key(&grouping.key)) {
+            self.group_aggregates.clear();
+            self.clear_grouping();
+        }
+        let columns = &self.columns;
+        self.group_aggregates.retain(|key, _| columns.contains_key(key));
+
+        self.keep_sort_by_columns();
+        self.clear_formula_cache();
+    }
+
+    /// Sets the bounds of the width of the column with the specified key,
+    /// forgetting any width the user resized it to. The column is resized the
+    /// next time the view is laid out.
+    pub fn set_column_width(&mut self, key: &str, width: ColumnWidth) {
+        if let Some(def) = self.columns.get_mut(key) {
+            def.width = width;
+            def.resized_width = None;
+            def.grown_width = 0;
+        }
+    }
+
+    /// Returns the bounds of the width of the column with the specified key.
+    pub fn column_width(&self, key: &str) -> Option<ColumnWidth> {
+        self.columns.get(key).map(|def| def.width)
+    }
+
+    /// Resizes the column with the specified key to the given width, as the
+    /// user would, within the bounds of its width and at least one wide. The
+    /// column keeps this width whatever its contents, until its bounds are
+    /// set again. Returns `false` if there is no such column.
+    pub fn resize_column(&mut self, key: &str, width: usize) -> bool {
+        match self.columns.get_mut(key) {
+            Some(def) => {
+                let (min_width, max_width) = def.width.bounds();
+                let width = width.max(min_width).max(1);
+                def.resized_width = Some(max_width.map_or(width, |max_width| width.min(max_width)));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Widens or narrows the column of the cursor, or the highlighted column
+    /// in column-select mode, by one, on behalf of the user.
+    fn resize_cursor_column(&mut self, wider: bool) -> EventResult {
+        let (x, key) = match self.current_column().and_then(|x| Some((x, self.columns.get_index(x)?.0.clone()))) {
+            Some(found) => found,
+            None => return EventResult::Ignored,
+        };
+
+        let old_width = self.columns[x].resized_width
+            .or_else(|| self.column_widths.get(x).copied())
+            .unwrap_or_default();
+        let width = if wider { old_width + 1 } else { old_width.saturating_sub(1) };
+        self.resize_column(&key, width);
+        if self.columns[x].resized_width == Some(old_width) {
+            return EventResult::Ignored;
+        }
+
+        self.scroll_to_column_index(x);
+        EventResult::Consumed(None)
+    }
+
+    /// Sets whether the column with the specified key may only ever get wider.
+    /// This keeps live-updating tables from "breathing" as short values come
+    /// and go. Turning this off lets the column shrink back to its contents.
+    pub fn set_column_grow_only(&mut self, key: &str, grow_only: bool) {
+        if let Some(def) = self.columns.get_mut(key) {
+            def.grow_only = grow_only;
+            def.grown_width = 0;
+        }
     }
 
     /// Returns the number of columns in this view.
@@ -129,7 +1170,12 @@ impl<D: Display + Ord> SpreadsheetView<D> {
 
     /// Appends a record to the end of this view.
     pub fn push_record(&mut self, record: Record<D>) {
-        self.records.push(record)
+        self.records.push(record);
+        self.sort_spec.clear();
+        self.remap_rows(Some);
+        self.record_insertion(self.records.len() - 1..self.records.len());
+        self.notify(|| ChangeEvent::RowsInserted { start: self.records.len() - 1, count: 1 });
+        self.evict_records();
     }
 
     /// Chainable version of `push_record`.
@@ -143,7 +1189,17 @@ impl<D: Display + Ord> SpreadsheetView<D> {
     where
         I: IntoIterator<Item = Record<D>>,
     {
+        let start = self.records.len();
         self.records.extend(iter);
+        self.sort_spec.clear();
+
+        let count = self.records.len() - start;
+        if count > 0 {
+            self.remap_rows(Some);
+            self.record_insertion(start..self.records.len());
+            self.notify(|| ChangeEvent::RowsInserted { start, count });
+            self.evict_records();
+        }
     }
 
     /// Chainable version of `extend_records`.
@@ -155,82 +1211,5782 @@ impl<D: Display + Ord> SpreadsheetView<D> {
         self
     }
 
+    /// Builder version of `extend_records`.
+    pub fn records<I>(mut self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = Record<D>>,
+    {
+        self.extend_records(iter);
+        self
+    }
+
+    /// Inserts a record at the given index, shifting the records after it
+    /// down.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of records.
+    pub fn insert_record(&mut self, index: usize, record: Record<D>) {
+        self.insert_records(index, Some(record));
+    }
+
+    /// Inserts the records of an iterator at the given index, in order,
+    /// shifting the records after them down at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of records.
+    pub fn insert_records<I>(&mut self, index: usize, iter: I)
+    where
+        I: IntoIterator<Item = Record<D>>,
+    {
+        let len = self.records.len();
+        self.records.splice(index..index, iter);
+
+        let count = self.records.len() - len;
+        if count > 0 {
+            self.sort_spec.clear();
+            self.remap_rows(|row| Some(if row >= index { row + count } else { row }));
+            self.record_insertion(index..index + count);
+            self.notify(|| ChangeEvent::RowsInserted { start: index, count });
+            self.evict_records();
+        }
+    }
+
+    /// Replaces the record at the given index, returning the old record, or
+    /// `None` if the index is out of bounds. The record keeps its place, its
+    /// age, and its edit history, but loses any formulas.
+    pub fn replace_record(&mut self, index: usize, record: Record<D>) -> Option<Record<D>> {
+        let old = mem::replace(self.records.get_mut(index)?, record);
+        let mut old_formulas = HashMap::new();
+        self.formulas.retain(|(row, key), formula| {
+            if *row == index {
+                old_formulas.insert(key.clone(), formula.source.clone());
+            }
+            *row != index
+        });
+        self.sort_spec.clear();
+        self.record_replacement(index, &old, old_formulas);
+        self.record_replaced(index, &old);
+        Some(old)
+    }
+
     /// Removes and returns the last record from this view, or `None` if there
     /// are no records.
     pub fn pop_record(&mut self) -> Option<Record<D>> {
-        self.records.pop()
+        let record = self.records.pop()?;
+        let len = self.records.len();
+        let meta = self.meta_for_undo(len);
+        self.remap_rows(|row| if row < len { Some(row) } else { None });
+        self.record_removal(len, &record, meta);
+        self.notify(|| ChangeEvent::RowsRemoved { start: len, count: 1 });
+        Some(record)
     }
 
     /// Removes and returns the record at a specified index from this view, or
     /// `None` if the index is out of bounds.
     pub fn remove_record(&mut self, index: usize) -> Option<Record<D>> {
-        if index < self.records.len() { Some(self.records.remove(index)) }
+        if index < self.records.len() {
+            let record = self.records.remove(index);
+            let meta = self.meta_for_undo(index);
+            self.remap_rows(|row| match row.cmp(&index) {
+                Ordering::Less => Some(row),
+                Ordering::Equal => None,
+                Ordering::Greater => Some(row - 1),
+            });
+            self.record_removal(index, &record, meta);
+            self.notify(|| ChangeEvent::RowsRemoved { start: index, count: 1 });
+            Some(record)
+        }
         else { None }
     }
 
+    /// Removes the records in the given range from this view and returns
+    /// them, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn drain_records<R>(&mut self, range: R) -> Vec<Record<D>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let records: Vec<_> = self.records.drain(range).collect();
+        let count = records.len();
+        if count == 0 {
+            return records;
+        }
+
+        let metas = self.undo_history.as_ref().map(|_| self.row_meta[start..start + count].to_vec());
+        self.remap_rows(|row| {
+            if row < start { Some(row) }
+            else if row < start + count { None }
+            else { Some(row - count) }
+        });
+        self.record_removals(start, &records, metas);
+        if let Some((x, y)) = self.cursor_pos {
+            self.set_cursor_pos(x, y);
+        }
+        self.notify(|| ChangeEvent::RowsRemoved { start, count });
+        records
+    }
+
     /// Clears all records from this view.
     pub fn clear_records(&mut self) {
-        self.records.clear();
+        self.take_records();
     }
 
-    /// Returns the number of records in this view.
-    pub fn len_records(&self) -> usize {
-        self.records.len()
-    }
+    /// Removes all records from this view and returns them, without cloning.
+    pub fn take_records(&mut self) -> Vec<Record<D>> {
+        let records = mem::take(&mut self.records);
+        let metas = self.undo_history.as_ref().map(|_| self.row_meta.clone());
+        self.remap_rows(|_| None);
 
-    /// Sorts the records in this view by the specified column.
-    /// This sort is stable, so multiple calls of this method with different
-    /// columns will co-sort as expected.
-    pub fn sort_records(&mut self, key: &str, ascending: bool) {
-        // If the key is not in the column list, just no-op.
-        if self.columns.contains_key(key) {
-            self.records.sort_by(|ra, rb| {
-                let o = ra.get(key).cmp(&rb.get(key));
-                if ascending { o } else { o.reverse() }
-            })
+        if !records.is_empty() {
+            self.record_removals(0, &records, metas);
+            self.notify(|| ChangeEvent::RowsRemoved { start: 0, count: records.len() });
         }
-    }
 
-    // CURSOR ------------------------------------------------------------------
-
-    /// Set the position of the cursor, snapping to the bounds of the view.
-    /// If there are no columns or records, sets the cursor to `None`,
-    /// regardless of the inputs.
-    pub fn set_cursor_pos(&mut self, x: usize, y: usize) {
-        let num_cols = self.len_columns();
-        let num_recs = self.len_records();
+        records
+    }
 
-        self.cursor_pos = match (num_cols, num_recs) {
-            // No way to place a cursor, set to `None`.
-            (0, _) | (_, 0) => None,
+    /// Consumes this view and returns its records, e.g. to carry on
+    /// processing the edited data once the user interface is torn down.
+    pub fn into_records(self) -> Vec<Record<D>> {
+        self.records
+    }
 
-            // Bound the new target position to the edges of the view.
-            (lx, ly) => Some((x.min(lx - 1), y.min(ly - 1))),
-        };
+    /// Consumes this view and returns its columns and its records.
+    pub fn into_parts(self) -> (IndexMap<String, ColumnDef>, Vec<Record<D>>) {
+        (self.columns, self.records)
     }
 
-    // CURSIVE-RELATED ---------------------------------------------------------
+    /// Sets a callback to be used whenever records are added, removed, edited,
+    /// or sorted, so that external state such as caches, counters, or
+    /// persistence can follow the view without polling it.
+    pub fn set_on_data_changed<F>(&mut self, cb: F)
+    where
+        F: Fn(ChangeEvent) + 'static,
+    {
+        self.on_data_changed = Some(Rc::new(cb));
+    }
+
+    /// Sets a callback to be used after the user changes the records, with
+    /// a description of every change, e.g. to save the changes back to a
+    /// file or a database. Unlike the data-changed callback, it runs with
+    /// access to the Cursive root, once the event causing the changes has
+    /// been handled. Changes made by the application itself are not reported
+    /// to it.
+    pub fn set_on_change<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, &ChangeEvent) + 'static,
+    {
+        self.on_change = Some(Rc::new(cb));
+    }
+
+    /// Returns `true` if the records were added, removed, edited, or sorted
+    /// since this view was created or last marked clean.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.get()
+    }
+
+    /// Marks the records as unchanged, e.g. once they have been saved.
+    pub fn mark_clean(&mut self) {
+        self.dirty.set(false);
+    }
+
+    /// Passes the event made by `event` to the data-changed callback, if any,
+    /// and keeps it for the change callback. During a batch update, the event
+    /// is dropped in favor of a single `ChangeEvent::Reset` once the batch
+    /// ends.
+    fn notify<F>(&self, event: F)
+    where
+        F: FnOnce() -> ChangeEvent,
+    {
+        let event = event();
+        self.forget_drawn_cells(&event);
+        self.dirty.set(true);
+
+        if self.batch_depth > 0 {
+            self.batch_changed.set(true);
+        }
+        else if self.on_data_changed.is_some() || self.on_change.is_some() {
+            if self.on_change.is_some() {
+                self.pending_changes.borrow_mut().push(event.clone());
+            }
+            if let Some(cb) = self.on_data_changed.as_ref() {
+                cb(event);
+            }
+        }
+    }
+
+    /// Adds the change callback, run for every change made while handling an
+    /// event, to the result of handling it.
+    fn report_changes(&mut self, result: EventResult) -> EventResult {
+        let changes = mem::take(self.pending_changes.get_mut());
+        match self.on_change.clone() {
+            Some(cb) if !changes.is_empty() => result.and(EventResult::with_cb(move |s| {
+                for change in &changes {
+                    cb(s, change);
+                }
+            })),
+            _ => result,
+        }
+    }
+
+    /// Starts a batch update. Until the matching `end_batch`, changes to the
+    /// records are not announced or reported to the data-changed callback,
+    /// and adding records does not cost the view its sort. Batches may nest.
+    pub fn begin_batch(&mut self) {
+        if self.batch_depth == 0 {
+            self.batch_sort_spec = self.sort_spec.clone();
+            self.batch_changed.set(false);
+        }
+        self.batch_depth += 1;
+    }
+
+    /// Ends a batch update. Once the outermost batch ends, records are sorted
+    /// again by the column they were sorted by when it began, if they have
+    /// fallen out of order, and a single `ChangeEvent::Reset` is reported if
+    /// any records changed.
+    pub fn end_batch(&mut self) {
+        if self.batch_depth != 1 {
+            self.batch_depth = self.batch_depth.saturating_sub(1);
+            return;
+        }
+
+        let spec = mem::take(&mut self.batch_sort_spec);
+        if !spec.is_empty() && self.sort_spec.is_empty() {
+            self.sort_by_spec(&spec);
+        }
+
+        self.batch_depth = 0;
+        if self.batch_changed.replace(false) {
+            self.notify(|| ChangeEvent::Reset);
+        }
+    }
+
+    /// Runs `f` on this view as a single batch update, see `begin_batch`.
+    pub fn update<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        self.begin_batch();
+        let result = f(self);
+        self.end_batch();
+        result
+    }
+
+    /// Replaces all records of this view at once, e.g. to refresh it from a
+    /// backend. The view stays sorted the way it was, and stays scrolled to
+    /// where it was.
+    ///
+    /// If a key column is set, the cursor follows the record it was on to its
+    /// new position, along with the edit history of the records; otherwise,
+    /// the cursor stays where it is on screen and the history is forgotten.
+    /// Either way, the changes recorded to be undone are forgotten.
+    pub fn set_records(&mut self, mut records: Vec<Record<D>>) {
+        if !self.sort_spec.is_empty() {
+            records.sort_by(|a, b| compare_records(a, b, &self.sort_spec, &self.comparators));
+        }
+
+        let old_records = mem::replace(&mut self.records, records);
+
+        // Maps the index of every old record to that of the new record with
+        // the same key, if any.
+        let new_index: Vec<Option<usize>> = match self.key_column.as_ref() {
+            Some(key) => {
+                let mut by_key = BTreeMap::new();
+                for (i, record) in self.records.iter().enumerate() {
+                    if let Some(value) = record.get(key) {
+                        by_key.entry(value).or_insert(i);
+                    }
+                }
+
+                old_records.iter()
+                    .map(|record| record.get(key).and_then(|value| by_key.get(value)).copied())
+                    .collect()
+            },
+            None => Vec::new(),
+        };
+
+        self.remap_rows(|row| new_index.get(row).copied().flatten());
+        self.forget_undo_history();
+
+        let now = SystemTime::now();
+        for meta in &mut self.row_meta {
+            meta.time = now;
+        }
+
+        if let Some((x, y)) = self.cursor_pos {
+            let screen_line = self.row_to_line(y).saturating_sub(self.scroll_base.start_line);
+
+            match new_index.get(y).copied().flatten() {
+                Some(new_y) => {
+                    self.set_cursor_pos(x, new_y);
+                    self.scroll_base.start_line = self.row_to_line(new_y).saturating_sub(screen_line);
+                },
+                None => self.set_cursor_pos(x, y),
+            }
+        }
+
+        self.notify(|| ChangeEvent::Reset);
+        self.evict_records();
+    }
+
+    /// Replaces all records of this view at once like `set_records`, matching
+    /// the old and new records by their values in the column with the given
+    /// key rather than by the key column of the view, e.g. for views refreshed
+    /// periodically from a live source. The cursor, the selection, and the
+    /// edit history follow the records they were on, and the view stays
+    /// sorted the way it was.
+    pub fn update_records(&mut self, records: Vec<Record<D>>, key_column: &str) {
+        let key_column = self.key_column.replace(key_column.to_string());
+        self.set_records(records);
+        self.key_column = key_column;
+    }
+
+    /// Replaces all records of this view at once, forgetting the sort, the
+    /// edit history, and the changes recorded to be undone, and moving the
+    /// cursor and the scroll position back to the first record.
+    pub fn reset_records(&mut self, records: Vec<Record<D>>) {
+        self.records = records;
+        self.sort_spec.clear();
+        self.remap_rows(|_| None);
+        self.forget_undo_history();
+        self.scroll_base.start_line = 0;
+
+        if let Some((x, _)) = self.cursor_pos {
+            self.set_cursor_pos(x, 0);
+        }
+
+        self.notify(|| ChangeEvent::Reset);
+        self.evict_records();
+    }
+
+    /// Caps the number of records in this view. Whenever records are added
+    /// beyond the cap, the records that were added the longest ago are
+    /// removed, keeping memory bounded for views that are fed rows forever.
+    /// Passing `None` lifts the cap.
+    pub fn set_max_records(&mut self, max_records: Option<usize>) {
+        self.max_records = max_records;
+        self.evict_records();
+    }
+
+    /// Returns the maximum number of records in this view, if capped.
+    pub fn max_records(&self) -> Option<usize> {
+        self.max_records
+    }
+
+    /// Removes the oldest records beyond the cap on the number of records.
+    fn evict_records(&mut self) {
+        let len = self.records.len();
+        let excess = match self.max_records {
+            Some(max) if len > max => len - max,
+            _ => return,
+        };
+
+        let mut by_age: Vec<usize> = (0..len).collect();
+        let row_meta = &self.row_meta;
+        by_age.select_nth_unstable_by_key(excess - 1, |&i| row_meta[i].seq);
+
+        let mut evicted = vec![false; len];
+        for &i in &by_age[..excess] {
+            evicted[i] = true;
+        }
+
+        self.remove_marked_records(&evicted);
+    }
+
+    /// Sets how long records are kept after being added, or last refreshed by
+    /// `upsert_record` or `set_records`. Expired records are removed every
+    /// time the view is laid out, and by `prune_expired_records`. This suits
+    /// views of e.g. currently active connections. Passing `None`, the
+    /// default, keeps records forever.
+    pub fn set_row_ttl(&mut self, ttl: Option<Duration>) {
+        self.row_ttl = ttl;
+    }
+
+    /// Returns how long records are kept after being added, if not forever.
+    pub fn row_ttl(&self) -> Option<Duration> {
+        self.row_ttl
+    }
+
+    /// Sets the time the record at the given index counts as added at, e.g.
+    /// the time the data in it was last seen by its source.
+    pub fn set_record_timestamp(&mut self, index: usize, time: SystemTime) {
+        if let Some(meta) = self.row_meta.get_mut(index) {
+            meta.time = time;
+        }
+    }
+
+    /// Returns the time the record at the given index was added or last
+    /// refreshed, or `None` if the index is out of bounds.
+    pub fn record_timestamp(&self, index: usize) -> Option<SystemTime> {
+        self.row_meta.get(index).map(|meta| meta.time)
+    }
+
+    /// Attaches data to the record at the given index that is not shown, but
+    /// follows the record as it is sorted or moved around, e.g. the ID of the
+    /// record in a database. Replaces any data attached before.
+    pub fn set_record_data<T: Any>(&mut self, index: usize, data: T) {
+        if let Some(meta) = self.row_meta.get_mut(index) {
+            meta.data = Some(Rc::new(data));
+        }
+    }
+
+    /// Returns the data attached to the record at the given index, if there is
+    /// any and it has the given type.
+    pub fn record_data<T: Any>(&self, index: usize) -> Option<&T> {
+        self.row_meta.get(index)?.data.as_ref()?.downcast_ref()
+    }
+
+    /// Detaches the data attached to the record at the given index, returning
+    /// it.
+    pub fn take_record_data(&mut self, index: usize) -> Option<Rc<dyn Any>> {
+        self.row_meta.get_mut(index)?.data.take()
+    }
+
+    /// Removes the records older than the row time-to-live, returning how many
+    /// were removed.
+    pub fn prune_expired_records(&mut self) -> usize {
+        let expires_before = match self.row_ttl.and_then(|ttl| SystemTime::now().checked_sub(ttl)) {
+            Some(time) => time,
+            None => return 0,
+        };
+
+        let expired: Vec<bool> = self.row_meta.iter().map(|meta| meta.time < expires_before).collect();
+        let count = expired.iter().filter(|&&e| e).count();
+        if count > 0 {
+            self.remove_marked_records(&expired);
+        }
+        count
+    }
+
+    /// Removes the records whose index is marked, reporting each run of
+    /// removed records.
+    fn remove_marked_records(&mut self, marked: &[bool]) {
+        let len = self.records.len();
+        let mut kept = 0;
+        let new_index: Vec<Option<usize>> = marked.iter()
+            .map(|&e| if e { None } else { kept += 1; Some(kept - 1) })
+            .collect();
+
+        let mut marked_iter = marked.iter();
+        self.records.retain(|_| !marked_iter.next().copied().unwrap_or(false));
+        self.remap_rows(|row| new_index.get(row).copied().flatten());
+
+        if let Some((x, y)) = self.cursor_pos {
+            self.set_cursor_pos(x, y);
+        }
+
+        // Report runs of removed records from the last to the first, so that
+        // the indices of the earlier runs are still valid when applied in turn.
+        let mut end = len;
+        while end > 0 {
+            match marked[..end].iter().rposition(|&e| e) {
+                Some(last) => {
+                    let start = marked[..last].iter().rposition(|&e| !e).map_or(0, |i| i + 1);
+                    self.notify(|| ChangeEvent::RowsRemoved { start, count: last + 1 - start });
+                    end = start;
+                },
+                None => break,
+            }
+        }
+    }
+
+    /// Brings the state derived from the record at `index` in line with it
+    /// having replaced `old`, and reports the cells that changed.
+    fn record_replaced(&mut self, index: usize, old: &Record<D>) {
+        for key in self.columns.keys() {
+            self.invalidate_cell(index, key);
+        }
+        self.refilter();
+
+        if self.on_data_changed.is_some() {
+            let new = &self.records[index];
+            let mut keys: Vec<&String> = old.keys().chain(new.keys())
+                .filter(|&key| old.get(key) != new.get(key))
+                .collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                self.notify(|| ChangeEvent::CellChanged { row: index, key: key.clone() });
+            }
+        }
+    }
+
+    /// Designates the column whose values identify records, as used by
+    /// `upsert_record`. Pass `None` to remove the designation.
+    pub fn set_key_column(&mut self, key: Option<&str>) {
+        self.key_column = key.map(str::to_string);
+    }
+
+    /// Returns the key of the column whose values identify records, if any.
+    pub fn key_column(&self) -> Option<&str> {
+        self.key_column.as_deref()
+    }
+
+    /// Replaces the record with the same value in the key column as the given
+    /// record, returning the old record, or adds the given record if there is
+    /// no such record. This suits views refreshed by repeatedly polling an
+    /// external source.
+    ///
+    /// If the records are sorted by the key column, new records are inserted
+    /// in order; otherwise, they are appended. Records without a value in the
+    /// key column, or any record if no key column is set, are always appended.
+    /// Replacing a record forgets any sort by the columns whose values change.
+    pub fn upsert_record(&mut self, record: Record<D>) -> Option<Record<D>> {
+        let found = self.key_column.as_ref()
+            .and_then(|key| record.get(key).map(|value| (key, value)))
+            .map(|(key, value)| {
+                match self.binary_search_by_key(key, value) {
+                    Some(result) => result,
+                    None => self.find_record(|r| r.get(key) == Some(value)).ok_or(self.records.len()),
+                }
+            });
+
+        match found {
+            Some(Ok(index)) => {
+                let old = mem::replace(&mut self.records[index], record);
+                self.row_meta[index].time = SystemTime::now();
+                let new = &self.records[index];
+                let changed: Vec<String> = old.keys().chain(new.keys())
+                    .filter(|&key| old.get(key) != new.get(key))
+                    .cloned()
+                    .collect();
+                for key in &changed {
+                    self.forget_sort_by(key);
+                }
+                self.record_replacement(index, &old, HashMap::new());
+                self.record_replaced(index, &old);
+                Some(old)
+            },
+            Some(Err(index)) if index < self.records.len() => {
+                self.records.insert(index, record);
+                self.remap_rows(|row| Some(if row >= index { row + 1 } else { row }));
+                self.record_insertion(index..index + 1);
+                self.notify(|| ChangeEvent::RowsInserted { start: index, count: 1 });
+                self.evict_records();
+                None
+            },
+            _ => {
+                // A record found to belong after all others keeps the sort.
+                let keeps_sort = found.is_some()
+                    && self.sort_spec.len() == 1
+                    && self.sorted_by().map(|(key, _)| key) == self.key_column();
+                let sort_spec = mem::take(&mut self.sort_spec);
+
+                self.push_record(record);
+                if keeps_sort {
+                    self.sort_spec = sort_spec;
+                }
+                None
+            },
+        }
+    }
+
+    /// Returns the number of records in this view.
+    pub fn len_records(&self) -> usize {
+        self.num_records()
+    }
+
+    /// Sorts the records in this view by the specified column alone.
+    pub fn sort_records(&mut self, key: &str, ascending: bool) {
+        self.sort_by_spec(&[(key, SortOrder::from_ascending(ascending))]);
+    }
+
+    /// Sorts the records in this view by several columns at once: by the
+    /// first column of the spec, then records equal in it by the second, and
+    /// so on. Columns not in the view are skipped. Sources only sort by the
+    /// first column.
+    pub fn sort_by_spec<K: AsRef<str>>(&mut self, spec: &[(K, SortOrder)]) {
+        let spec: Vec<(String, SortOrder)> = spec.iter()
+            .filter(|(key, _)| self.columns.contains_key(key.as_ref()))
+            .map(|(key, order)| (key.as_ref().to_string(), *order))
+            .collect();
+        let mut spec = spec;
+        let grouped = self.grouping.as_ref().map(|grouping| &grouping.key).filter(|key| self.columns.contains_key(*key));
+        if let Some(grouped) = grouped {
+            // Records are only sorted within their groups.
+            if spec.first().is_some_and(|(key, _)| key != grouped) {
+                spec.retain(|(key, _)| key != grouped);
+                spec.insert(0, (grouped.clone(), SortOrder::Ascending));
+            }
+        }
+        let (key, ascending) = match spec.first() {
+            Some((key, order)) => (key.clone(), *order == SortOrder::Ascending),
+            None => return,
+        };
+
+        if self.source.is_some() {
+            if self.sort_source(&key, ascending) {
+                self.notify(|| ChangeEvent::Sorted { key: key.clone(), ascending });
+            }
+            return;
+        }
+
+        let records = &self.records;
+        let mut order: Vec<usize> = (0..records.len()).collect();
+        let comparators = &self.comparators;
+        order.sort_by(|&a, &b| compare_records(&records[a], &records[b], &spec, comparators));
+
+        self.reorder_records(&order);
+        let description = spec.iter()
+            .map(|(key, order)| {
+                let direction = if *order == SortOrder::Ascending { "ascending" } else { "descending" };
+                let title = self.columns.get(key).map_or(key.as_str(), |def| def.title.as_str());
+                format!("{}, {}", display_text(title), direction)
+            })
+            .collect::<Vec<_>>()
+            .join(", then by column ");
+        self.sort_spec = spec;
+        self.notify(|| ChangeEvent::Sorted { key: key.clone(), ascending });
+        self.announce(&format!("sorted by column {}", description));
+    }
+
+    /// Returns the keys of the columns the records are currently sorted by,
+    /// with the order of each, most significant first. Empty if they are not
+    /// sorted.
+    pub fn sort_spec(&self) -> &[(String, SortOrder)] {
+        &self.sort_spec
+    }
+
+    /// Forgets the sort if it is by the column with the given key, e.g. once
+    /// a cell of the column changes.
+    pub(crate) fn forget_sort_by(&mut self, key: &str) {
+        if self.sort_spec.iter().any(|(sorted_key, _)| sorted_key == key) {
+            self.sort_spec.clear();
+        }
+    }
+
+    /// Drops the columns that are gone from the sort, along with the ones
+    /// after them, which the records are no longer sorted by either.
+    fn keep_sort_by_columns(&mut self) {
+        let columns = &self.columns;
+        let kept = self.sort_spec.iter().take_while(|(key, _)| columns.contains_key(key)).count();
+        self.sort_spec.truncate(kept);
+    }
+
+    /// Sets a callback to be used when the user sorts the records, by key or
+    /// by clicking a column header. Takes the key of the column and the order
+    /// of the sort, `Ordering::Less` for ascending and `Ordering::Greater` for
+    /// descending, as input.
+    pub fn set_on_sort<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, &str, Ordering) + 'static,
+    {
+        self.on_sort = Some(Rc::new(cb));
+    }
+
+    /// Builder version of `set_on_sort`.
+    pub fn on_sort<F>(mut self, cb: F) -> Self
+    where
+        F: Fn(&mut Cursive, &str, Ordering) + 'static,
+    {
+        self.set_on_sort(cb);
+        self
+    }
+
+    /// Sorts the records by the column at the given index on behalf of the
+    /// user, notifying the sort callback.
+    fn sort_by_column(&mut self, x: usize, ascending: bool) -> EventResult {
+        let key = match self.columns.get_index(x) {
+            Some((key, _)) => key.clone(),
+            None => return EventResult::Ignored,
+        };
+
+        self.sort_records(&key, ascending);
+        let order = if ascending { Ordering::Less } else { Ordering::Greater };
+        match self.on_sort.clone() {
+            Some(cb) => EventResult::with_cb(move |s| cb(s, &key, order)),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Sorts the records by the column at the given index on behalf of the
+    /// user, in ascending order unless they are sorted by that column in
+    /// ascending order already.
+    fn toggle_sort(&mut self, x: usize) -> EventResult {
+        let ascending = match (self.sorted_by(), self.columns.get_index(x)) {
+            (Some((sorted, ascending)), Some((key, _))) if sorted == key => !ascending,
+            _ => true,
+        };
+        self.sort_by_column(x, ascending)
+    }
+
+    /// Adds the column at the given index to the sort on behalf of the user,
+    /// after the columns the records are sorted by already, or reverses its
+    /// order if it is one of them. Notifies the sort callback.
+    fn add_sort_key(&mut self, x: usize) -> EventResult {
+        let key = match self.columns.get_index(x) {
+            Some((key, _)) => key.clone(),
+            None => return EventResult::Ignored,
+        };
+
+        let mut spec = self.sort_spec.clone();
+        let order = match spec.iter_mut().find(|(sorted, _)| *sorted == key) {
+            Some((_, order)) => {
+                *order = order.reverse();
+                *order
+            },
+            None => {
+                spec.push((key.clone(), SortOrder::Ascending));
+                SortOrder::Ascending
+            },
+        };
+
+        self.sort_by_spec(&spec);
+        let order = if order == SortOrder::Ascending { Ordering::Less } else { Ordering::Greater };
+        match self.on_sort.clone() {
+            Some(cb) => EventResult::with_cb(move |s| cb(s, &key, order)),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Returns the key of the column the records are currently sorted by
+    /// first (see `sort_spec`), and whether the sort is ascending. Adding
+    /// records or editing a sorted column forgets the sort, as the records
+    /// might no longer be in order.
+    pub fn sorted_by(&self) -> Option<(&str, bool)> {
+        self.sort_spec.first().map(|(key, order)| (key.as_str(), *order == SortOrder::Ascending))
+    }
+
+    /// Returns the typed key of the column the records are currently sorted
+    /// by, and whether the sort is ascending, if that column has a key of the
+    /// given type.
+    pub fn sorted_by_key<K: ColumnKey>(&self) -> Option<(K, bool)> {
+        self.sorted_by().and_then(|(key, ascending)| Some((K::from_key(key)?, ascending)))
+    }
+
+    // CELLS -------------------------------------------------------------------
+
+    /// Returns the value of the cell of the record at the given index in the
+    /// column with the given key, if it has one. This is the value stored in
+    /// the record; see `cell_value` for the result of a formula.
+    pub fn get_cell(&self, row: usize, key: &str) -> Option<&D> {
+        self.records.get(row)?.get(key)
+    }
+
+    /// Returns the value of a cell like `get_cell`, to be changed in place.
+    /// The cell is drawn again and any sort by its column is forgotten, but
+    /// the change is not reported to the data-changed callback, and the
+    /// filter is not applied to the record again.
+    pub fn get_cell_mut(&mut self, row: usize, key: &str) -> Option<&mut D> {
+        if self.records.get(row)?.contains_key(key) {
+            self.invalidate_cell(row, key);
+            self.forget_sort_by(key);
+        }
+        self.records.get_mut(row)?.get_mut(key)
+    }
+
+    /// Sets the value of a cell, removing any formula it has, and returns the
+    /// value it had before. Does nothing if there is no record at that index.
+    pub fn set_cell(&mut self, row: usize, key: &str, value: D) -> Option<D> {
+        let old = self.records.get_mut(row)?.insert(key.to_string(), value);
+        self.cell_changed(row, key);
+        old
+    }
+
+    /// Empties a cell, removing any formula it has, and returns the value it
+    /// had.
+    pub fn take_cell(&mut self, row: usize, key: &str) -> Option<D> {
+        let old = self.records.get_mut(row)?.remove(key);
+        self.cell_changed(row, key);
+        old
+    }
+
+    /// Brings the state derived from a cell in line with a change to it made
+    /// by the application, and reports the change.
+    fn cell_changed(&mut self, row: usize, key: &str) {
+        self.formulas.remove(&(row, key.to_string()));
+        self.invalidate_cell(row, key);
+        self.refilter();
+        self.forget_sort_by(key);
+        self.notify(|| ChangeEvent::CellChanged { row, key: key.to_string() });
+    }
+
+    /// Returns an iterator over the records, in the order they are shown
+    /// when no filter is set.
+    pub fn iter_rows(&self) -> impl Iterator<Item = &Record<D>> {
+        self.records.iter()
+    }
+
+    /// Returns an iterator over the cells of the column with the given key,
+    /// one per record, with `None` for the records without a value in it.
+    pub fn iter_column<'a>(&'a self, key: &'a str) -> impl Iterator<Item = Option<&'a D>> + 'a {
+        self.records.iter().map(move |record| record.get(key))
+    }
+
+    /// Returns the index of the first record matching the given predicate, or
+    /// `None` if there is no such record.
+    pub fn find_record<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: FnMut(&Record<D>) -> bool,
+    {
+        self.records.iter().position(predicate)
+    }
+
+    /// Binary searches the records for one whose cell in the column with the
+    /// specified key equals `value`, in the manner of `slice::binary_search`:
+    /// `Ok` holds the index of a matching record, and `Err` the index where a
+    /// record with that value could be inserted while keeping the sort order.
+    ///
+    /// Returns `None` if the records are not currently sorted by that column
+    /// (see `sorted_by`), since the search would be meaningless.
+    pub fn binary_search_by_key(&self, key: &str, value: &D) -> Option<Result<usize, usize>> {
+        let ascending = match self.sorted_by() {
+            Some((sorted_key, ascending)) if sorted_key == key => ascending,
+            _ => return None,
+        };
+
+        let comparator = self.comparators.get(key);
+        Some(self.records.binary_search_by(|record| {
+            let o = compare_values(record.get(key), Some(value), comparator);
+            if ascending { o } else { o.reverse() }
+        }))
+    }
+
+    /// Rearranges the records so that the record at index `order[i]` ends up
+    /// at index `i`, carrying along any state tied to the records.
+    fn reorder_records(&mut self, order: &[usize]) {
+        let mut old_records: Vec<_> = mem::take(&mut self.records).into_iter().map(Some).collect();
+        self.records = order.iter().filter_map(|&i| old_records[i].take()).collect();
+
+        let mut new_index = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+
+        // Named ranges cover positions rather than records, so they stay put.
+        self.move_row_state(|row| new_index.get(row).copied());
+    }
+
+    /// Updates state tied to record indices after records have been inserted
+    /// or removed. `f` maps an old index to its new index, or to `None` if the
+    /// record is gone.
+    ///
+    /// Named ranges shrink or grow to the records left inside them. Ranges
+    /// without any records left are kept as they were, so that refilling a
+    /// view brings them back.
+    fn remap_rows<F>(&mut self, f: F)
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        for range in self.names.values_mut() {
+            if let Some(remapped) = range.remap_rows(&f) {
+                *range = remapped;
+            }
+        }
+
+        self.move_row_state(f);
+    }
+
+    /// Updates the state that follows records around after they have moved.
+    /// `f` maps an old index to its new index, or to `None` if the record is
+    /// gone. Records without any record mapped to their index are taken to be
+    /// new.
+    fn move_row_state<F>(&mut self, f: F)
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        let mut row_meta = vec![None; self.records.len()];
+        for (row, meta) in mem::take(&mut self.row_meta).into_iter().enumerate() {
+            if let Some(slot) = f(row).and_then(|row| row_meta.get_mut(row)) {
+                *slot = Some(meta);
+            }
+        }
+
+        let next_seq = &mut self.next_seq;
+        let now = SystemTime::now();
+        self.row_meta = row_meta.into_iter()
+            .map(|meta| meta.unwrap_or_else(|| {
+                *next_seq += 1;
+                RowMeta { seq: *next_seq - 1, time: now, data: None }
+            }))
+            .collect();
+
+        self.history = mem::take(&mut self.history).into_iter()
+            .filter_map(|((row, key), changes)| f(row).map(|row| ((row, key), changes)))
+            .collect();
+
+        self.formulas = mem::take(&mut self.formulas).into_iter()
+            .filter_map(|((row, key), formula)| f(row).map(|row| ((row, key), formula)))
+            .collect();
+
+        self.read_only_cells = mem::take(&mut self.read_only_cells).into_iter()
+            .filter_map(|(row, key)| f(row).map(|row| (row, key)))
+            .collect();
+
+        // Formulas refer to cells by position, so any of them may have changed.
+        self.formula_cache.get_mut().clear();
+        let visible_cells = mem::take(self.visible_cells.get_mut());
+        *self.visible_cells.get_mut() = visible_cells.into_iter()
+            .filter_map(|(row, cells)| f(row).map(|row| (row, cells)))
+            .collect();
+        self.forget_drawn_formula_rows();
+
+        self.selected_cells = self.selected_cells.iter()
+            .filter_map(|&(x, row)| f(row).map(|row| (x, row)))
+            .collect();
+        self.selection_anchor = None;
+
+        self.refilter();
+    }
+
+    // FILTERING ---------------------------------------------------------------
+
+    /// Hides the records `filter` does not hold for, e.g. to show only the
+    /// records whose `status` is `error`, replacing any filter set before.
+    /// Hidden records stay in the view, keep their indices, and are still
+    /// returned by `records` and friends. The filter is applied again whenever
+    /// the records change, and the cursor moves off records that get hidden.
+    pub fn set_filter<F>(&mut self, filter: F)
+    where
+        F: Fn(&Record<D>) -> bool + 'static,
+    {
+        self.filter = Some(Rc::new(filter));
+        self.refilter();
+    }
+
+    /// Removes the filter, showing every record again.
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+        self.refilter();
+    }
+
+    /// Returns `true` if a filter is set.
+    pub fn has_filter(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    /// Returns the number of records not hidden by the filter.
+    pub fn len_shown_records(&self) -> usize {
+        self.shown_rows.as_ref().map_or(self.num_records(), Vec::len)
+    }
+
+    /// Returns `true` if the record at the given index exists and is not
+    /// hidden by the filter.
+    pub fn is_record_shown(&self, row: usize) -> bool {
+        match self.shown_rows.as_ref() {
+            Some(shown) => shown.binary_search(&row).is_ok(),
+            None => row < self.num_records(),
+        }
+    }
+
+    /// Returns the index of the record shown at the given position, counting
+    /// only the records not hidden by the filter.
+    fn shown_row(&self, position: usize) -> Option<usize> {
+        match self.shown_rows.as_ref() {
+            Some(shown) => shown.get(position).copied(),
+            None => Some(position).filter(|&row| row < self.num_records()),
+        }
+    }
+
+    /// Returns the position at which the record at the given index is shown,
+    /// or, if it is hidden, where the next record shown after it is.
+    fn shown_position(&self, row: usize) -> usize {
+        match self.shown_rows.as_ref() {
+            Some(shown) => shown.partition_point(|&shown_row| shown_row < row),
+            None => row,
+        }
+    }
+
+    /// Applies the filter to the records again, dropping hidden cells from
+    /// the selection and moving the cursor off a hidden record.
+    fn refilter(&mut self) {
+        let mut shown_rows: Option<Vec<usize>> = self.filter.as_ref().filter(|_| self.source.is_none()).map(|filter| {
+            self.records.iter().enumerate()
+                .filter(|(_, record)| filter(record))
+                .map(|(row, _)| row)
+                .collect()
+        });
+        if self.grouping.is_some() && self.source.is_none() {
+            let rows = shown_rows.unwrap_or_else(|| (0..self.records.len()).collect());
+            shown_rows = Some(self.group_rows(rows));
+        }
+        else {
+            self.groups.clear();
+            self.group_header_positions.clear();
+            self.group_footer_ends.clear();
+        }
+        let unchanged = shown_rows == self.shown_rows;
+        self.shown_rows = shown_rows;
+        if unchanged {
+            return;
+        }
+
+        if let Some(shown) = self.shown_rows.as_ref() {
+            self.selected_cells.retain(|(_, y)| shown.binary_search(y).is_ok());
+        }
+        self.selection_anchor = None;
+
+        if let Some((x, y)) = self.cursor_pos {
+            self.set_cursor_pos(x, y);
+        }
+    }
+
+    // CURSOR ------------------------------------------------------------------
+
+    /// Set the position of the cursor, snapping to the bounds of the view.
+    /// A cursor on a record hidden by the filter snaps to the next record
+    /// shown, or to the last one. If there are no columns or records shown,
+    /// sets the cursor to `None`, regardless of the inputs.
+    pub fn set_cursor_pos(&mut self, x: usize, y: usize) {
+        let num_cols = self.len_columns();
+        let num_recs = self.len_shown_records();
+
+        let cursor_pos = match (num_cols, num_recs) {
+            // No way to place a cursor, set to `None`.
+            (0, _) | (_, 0) => None,
+
+            // Bound the new target position to the edges of the view.
+            (lx, ly) => {
+                let position = self.shown_position(y).min(ly - 1);
+                let x = self.shown_column_near(x.min(lx - 1), true);
+                self.shown_row(position).zip(x).map(|(y, x)| (x, y))
+            },
+        };
+
+        if cursor_pos != self.cursor_pos {
+            self.cursor_pos = cursor_pos;
+            if let Some((x, y)) = cursor_pos {
+                self.announce_cell(y, x);
+            }
+        }
+    }
+
+    /// Returns the position of the cursor as `(column, row)`, or `None` if
+    /// there is no cursor.
+    pub fn cursor_pos(&self) -> Option<(usize, usize)> {
+        self.cursor_pos
+    }
+
+    /// Sets a callback to be used when the user moves the cursor. Takes the
+    /// row and the column index of the cell now under the cursor as input.
+    pub fn set_on_select<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize, usize) + 'static,
+    {
+        self.on_select = Some(Rc::new(cb));
+    }
+
+    /// Builder version of `set_on_select`.
+    pub fn on_select<F>(mut self, cb: F) -> Self
+    where
+        F: Fn(&mut Cursive, usize, usize) + 'static,
+    {
+        self.set_on_select(cb);
+        self
+    }
+
+    /// Sets a callback to be used when the user submits the cell under the
+    /// cursor, by pressing the keys bound to editing (Enter) on a view whose
+    /// cells cannot be edited, or by double-clicking the cell. Takes the row,
+    /// the column index, and the column key of the cell as input. On read-only
+    /// views, it is used in place of the locked edit callback.
+    pub fn set_on_submit<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize, usize, &str) + 'static,
+    {
+        self.on_submit = Some(Rc::new(cb));
+    }
+
+    /// Builder version of `set_on_submit`.
+    pub fn on_submit<F>(mut self, cb: F) -> Self
+    where
+        F: Fn(&mut Cursive, usize, usize, &str) + 'static,
+    {
+        self.set_on_submit(cb);
+        self
+    }
+
+    // COLUMN SELECTION --------------------------------------------------------
+
+    /// Turns column-select mode on or off. In column-select mode, the title
+    /// of a column is highlighted instead of a cell, the keys bound to moving
+    /// left and right (Left and Right by default) move the highlight, and the
+    /// keys bound to editing (Enter) sort by the highlighted column or submit
+    /// it to the column submit callback. The highlight starts at the column of the
+    /// cursor, and the cursor moves to the highlighted column on leaving.
+    pub fn set_column_select(&mut self, column_select: bool) {
+        if column_select == self.column_select {
+            return;
+        }
+
+        if column_select {
+            self.highlighted_column = self.cursor_pos.map_or(0, |(x, _)| x);
+        }
+        else if let Some((_, y)) = self.cursor_pos {
+            self.set_cursor_pos(self.highlighted_column, y);
+        }
+        self.column_select = column_select;
+    }
+
+    /// Returns `true` if the view is in column-select mode.
+    pub fn is_column_select(&self) -> bool {
+        self.column_select
+    }
+
+    /// Returns the key of the column highlighted in column-select mode, or
+    /// `None` outside of it.
+    pub fn highlighted_column(&self) -> Option<&str> {
+        self.columns.get_index(self.highlighted_column)
+            .filter(|_| self.column_select)
+            .map(|(key, _)| key.as_str())
+    }
+
+    /// Sets a callback to be used when the user moves the highlight in
+    /// column-select mode. Takes the key of the column now highlighted as
+    /// input.
+    pub fn set_on_column_select<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, &str) + 'static,
+    {
+        self.on_column_select = Some(Rc::new(cb));
+    }
+
+    /// Builder version of `set_on_column_select`.
+    pub fn on_column_select<F>(mut self, cb: F) -> Self
+    where
+        F: Fn(&mut Cursive, &str) + 'static,
+    {
+        self.set_on_column_select(cb);
+        self
+    }
+
+    /// Sets a callback to be used when the user submits the highlighted
+    /// column in column-select mode, in place of sorting by it. Takes the key
+    /// of the column as input.
+    pub fn set_on_column_submit<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, &str) + 'static,
+    {
+        self.on_column_submit = Some(Rc::new(cb));
+    }
+
+    /// Builder version of `set_on_column_submit`.
+    pub fn on_column_submit<F>(mut self, cb: F) -> Self
+    where
+        F: Fn(&mut Cursive, &str) + 'static,
+    {
+        self.set_on_column_submit(cb);
+        self
+    }
+
+    /// Returns the index of the column the user acts on: the highlighted one
+    /// in column-select mode, or the column of the cursor otherwise.
+    fn current_column(&self) -> Option<usize> {
+        if self.column_select { Some(self.highlighted_column).filter(|&x| x < self.columns.len()) }
+        else { self.cursor_pos.map(|(x, _)| x) }
+    }
+
+    /// Turns column-select mode on or off on behalf of the user.
+    fn toggle_column_select(&mut self) -> EventResult {
+        if self.columns.is_empty() {
+            return EventResult::Ignored;
+        }
+
+        self.set_column_select(!self.column_select);
+        EventResult::Consumed(None)
+    }
+
+    /// Handles the keys bound to the actions that act differently in
+    /// column-select mode, and Esc, or returns `None` for the others.
+    fn on_column_select_event(&mut self, event: &Event) -> Option<EventResult> {
+        let x = self.highlighted_column;
+        let result = match (self.bindings.action(event), event) {
+            (Some(SpreadsheetAction::MoveLeft), _) => self.highlight_column(x.checked_sub(1)),
+            (Some(SpreadsheetAction::MoveRight), _) => self.highlight_column(Some(x + 1)),
+            (Some(SpreadsheetAction::MoveUp), _) | (Some(SpreadsheetAction::MoveDown), _) => EventResult::Ignored,
+            (_, Event::Key(Key::Esc)) => self.toggle_column_select(),
+            (Some(SpreadsheetAction::EditCell), _) => match (self.on_column_submit.clone(), self.columns.get_index(x)) {
+                (Some(cb), Some((key, _))) => {
+                    let key = key.clone();
+                    EventResult::with_cb(move |s| cb(s, &key))
+                },
+                (None, Some(_)) => self.toggle_sort(x),
+                (_, None) => EventResult::Ignored,
+            },
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Moves the highlight of column-select mode to the column at the given
+    /// index, if there is one.
+    fn highlight_column(&mut self, x: Option<usize>) -> EventResult {
+        let x = match x.filter(|&x| x < self.columns.len()) {
+            Some(x) => x,
+            None => return EventResult::Ignored,
+        };
+
+        self.highlighted_column = x;
+        self.scroll_to_column_index(x);
+        match self.on_column_select.clone() {
+            Some(cb) => {
+                let key = self.columns.get_index(x).map(|(key, _)| key.clone()).unwrap_or_default();
+                EventResult::with_cb(move |s| cb(s, &key))
+            },
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Moves the cursor on behalf of the user, to a position computed from the
+    /// current one, or from the first cell if there is no cursor. Scrolls the
+    /// cursor into view. Ignores the move if the cursor stays put, e.g. at the
+    /// edges of the view, so that focus can move on to neighboring views.
+    ///
+    /// Rows are counted as shown, skipping the records hidden by the filter.
+    fn move_cursor<F>(&mut self, f: F) -> EventResult
+    where
+        F: FnOnce((usize, usize)) -> (usize, usize),
+    {
+        let (x, position) = match self.cursor_pos {
+            Some((x, y)) => {
+                // Hidden columns are skipped in the direction of the move.
+                let (new_x, position) = f((x, self.shown_position(y)));
+                let new_x = self.shown_column_near(new_x.min(self.len_columns().saturating_sub(1)), new_x >= x);
+                (new_x.unwrap_or(x), position)
+            },
+            None => (0, 0),
+        };
+
+        let last = self.len_shown_records().saturating_sub(1);
+        let y = self.shown_row(position.min(last)).unwrap_or(0);
+        self.move_cursor_to(x, y)
+    }
+
+    /// Moves the cursor on behalf of the user to the given position, like
+    /// `move_cursor`.
+    fn move_cursor_to(&mut self, x: usize, y: usize) -> EventResult {
+        self.selection_anchor = None;
+        let old = self.cursor_pos;
+        self.set_cursor_pos(x, y);
+        let (x, y) = match self.cursor_pos {
+            Some(pos) if self.cursor_pos != old => pos,
+            _ => return EventResult::Ignored,
+        };
+
+        self.scroll_to_row(y);
+        self.scroll_to_column_index(x);
+        match self.on_select.clone() {
+            Some(cb) => EventResult::with_cb(move |s| cb(s, y, x)),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Returns the number of records moved over by paging up or down, which
+    /// is as many as fit in the body next to the one under the cursor.
+    fn page_rows(&self, up: bool) -> usize {
+        if self.record_lines.is_empty() {
+            return (self.scroll_base.view_height / self.row_height).max(1);
+        }
+
+        let cursor = self.cursor_pos.map_or(0, |(_, y)| self.shown_position(y));
+        let lines_between = |rows: usize| {
+            if up { self.lines_before(cursor) - self.lines_before(cursor - rows) }
+            else { self.lines_before(cursor + rows) - self.lines_before(cursor) }
+        };
+        let available = if up { cursor } else { self.len_shown_records().saturating_sub(cursor + 1) };
+
+        let mut rows = 0;
+        while rows < available && lines_between(rows + 1) <= self.scroll_base.view_height {
+            rows += 1;
+        }
+        rows.max(1)
+    }
+
+    /// Returns the record under the cursor, if there is a cursor.
+    pub fn cursor_record(&self) -> Option<&Record<D>> {
+        self.cursor_pos.and_then(|(_, y)| self.records.get(y))
+    }
+
+    /// Returns the key of the column under the cursor, if there is a cursor.
+    pub fn cursor_column(&self) -> Option<&str> {
+        self.cursor_pos.and_then(|(x, _)| self.columns.get_index(x)).map(|(key, _)| key.as_str())
+    }
+
+    /// Sets whether the user selects single cells, whole records, or whole
+    /// columns, clearing the selection. In row and column mode the whole
+    /// record or column under the cursor is highlighted. Cell mode by
+    /// default.
+    pub fn set_selection_mode(&mut self, mode: SelectionMode) {
+        self.selection_mode = mode;
+        self.selected_cells.clear();
+        self.selection_anchor = None;
+    }
+
+    /// Returns what the user selects.
+    pub fn selection_mode(&self) -> SelectionMode {
+        self.selection_mode
+    }
+
+    /// Switches to the next selection mode on behalf of the user.
+    fn cycle_selection_mode(&mut self) -> EventResult {
+        let mode = match self.selection_mode {
+            SelectionMode::Cell => SelectionMode::Row,
+            SelectionMode::Row => SelectionMode::Column,
+            SelectionMode::Column => SelectionMode::Cell,
+        };
+        let had_selection = self.has_selection();
+        self.set_selection_mode(mode);
+        if had_selection { self.selection_changed() } else { EventResult::Consumed(None) }
+    }
+
+    /// Returns the indices of the records with selected cells, in ascending
+    /// order. In row mode, these are the selected records.
+    pub fn selected_rows(&self) -> Vec<usize> {
+        let rows: BTreeSet<usize> = self.selected_cells.iter().map(|&(_, y)| y).collect();
+        rows.into_iter().collect()
+    }
+
+    /// Returns the indices of the columns with selected cells, in ascending
+    /// order. In column mode, these are the selected columns.
+    pub fn selected_columns(&self) -> Vec<usize> {
+        let columns: BTreeSet<usize> = self.selected_cells.iter().map(|&(x, _)| x).collect();
+        columns.into_iter().collect()
+    }
+
+    /// Returns the cells of the given range, widened to whole records or
+    /// columns as the selection mode asks, leaving out hidden records.
+    fn cells_for_mode(&self, range: CellRange) -> HashSet<(usize, usize)> {
+        let (start, end) = (range.start(), range.end());
+        let (xs, ys) = match self.selection_mode {
+            SelectionMode::Cell => (start.0..end.0 + 1, start.1..end.1 + 1),
+            SelectionMode::Row => (0..self.columns.len(), start.1..end.1 + 1),
+            SelectionMode::Column => (start.0..end.0 + 1, 0..self.num_records()),
+        };
+
+        ys.filter(|&y| self.is_record_shown(y))
+            .flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .collect()
+    }
+
+    /// Returns `true` if the cell at the given position is highlighted as
+    /// being under the cursor, which takes in its whole record or column in
+    /// row and column mode.
+    fn is_cursor_cell(&self, x: usize, y: usize) -> bool {
+        match (self.cursor_pos, self.selection_mode) {
+            (Some(pos), SelectionMode::Cell) => pos == (x, y),
+            (Some((_, cursor_y)), SelectionMode::Row) => cursor_y == y,
+            (Some((cursor_x, _)), SelectionMode::Column) => cursor_x == x,
+            (None, _) => false,
+        }
+    }
+
+    /// Returns the cells in the selection, as `(column, row)` positions.
+    pub fn selected_cells(&self) -> &HashSet<(usize, usize)> {
+        &self.selected_cells
+    }
+
+    /// Returns `true` if any cells are selected.
+    pub fn has_selection(&self) -> bool {
+        !self.selected_cells.is_empty()
+    }
+
+    /// Returns the number of selected cells.
+    pub fn selection_len(&self) -> usize {
+        self.selected_cells.len()
+    }
+
+    /// Returns the smallest range containing every selected cell, which is the
+    /// whole selection if it was made with Shift and the arrow keys.
+    pub fn selection(&self) -> Option<CellRange> {
+        let mut cells = self.selected_cells.iter().copied();
+        let first = cells.next()?;
+        let (start, end) = cells.fold((first, first), |(start, end), (x, y)| {
+            ((start.0.min(x), start.1.min(y)), (end.0.max(x), end.1.max(y)))
+        });
+        Some(CellRange::new(start, end))
+    }
+
+    /// Selects the cells of the given range, limited to the bounds of the
+    /// view, in place of the current selection.
+    pub fn set_selection(&mut self, range: CellRange) {
+        let num_cols = self.columns.len();
+        self.selected_cells = range.cells().filter(|&(x, y)| x < num_cols && self.is_record_shown(y)).collect();
+        self.selection_anchor = None;
+    }
+
+    /// Moves the cursor like `move_cursor`, growing or shrinking the selection
+    /// to the rectangle between the cursor and where the cursor was when the
+    /// user started extending the selection.
+    fn extend_selection<F>(&mut self, f: F) -> EventResult
+    where
+        F: FnOnce((usize, usize)) -> (usize, usize),
+    {
+        let anchor = match self.selection_anchor.or(self.cursor_pos) {
+            Some(anchor) => anchor,
+            None => return EventResult::Ignored,
+        };
+
+        let result = self.move_cursor(f);
+        let cursor = match self.cursor_pos {
+            Some(cursor) if result.is_consumed() => cursor,
+            _ => return result,
+        };
+
+        self.selection_anchor = Some(anchor);
+        self.selected_cells = self.cells_for_mode(CellRange::new(anchor, cursor));
+        result.and(self.selection_changed())
+    }
+
+    /// Adds the cell under the cursor to the selection, or removes it if it
+    /// was selected already. In row and column mode, the whole record or
+    /// column under the cursor is added or removed. Returns `false` if there
+    /// is no cursor.
+    pub fn toggle_cursor_selection(&mut self) -> bool {
+        let pos = match self.cursor_pos {
+            Some(pos) => pos,
+            None => return false,
+        };
+
+        let cells = self.cells_for_mode(CellRange::new(pos, pos));
+        if self.selected_cells.contains(&pos) {
+            self.selected_cells.retain(|cell| !cells.contains(cell));
+        }
+        else {
+            self.selected_cells.extend(cells);
+        }
+        true
+    }
+
+    /// Sets a callback to be used when the user adds cells to the selection
+    /// or removes them from it, e.g. to enable actions on the marked rows.
+    pub fn set_on_selection_changed<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize) + 'static,
+    {
+        self.on_selection_changed = Some(Rc::new(cb));
+    }
+
+    /// Notifies the selection callback of a change made by the user.
+    fn selection_changed(&self) -> EventResult {
+        let len = self.selected_cells.len();
+        match self.on_selection_changed.clone() {
+            Some(cb) => EventResult::with_cb(move |s| cb(s, len)),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Scrolls horizontally just enough for the column with the given key to
+    /// be visible, as far as the frozen columns leave room for it. Returns
+    /// `false` if there is no such column. The view must have been laid out.
+    pub fn scroll_to_column(&mut self, key: &str) -> bool {
+        match self.columns.get_index_of(key) {
+            Some(x) => {
+                self.scroll_to_column_index(x);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Scrolls just enough for the cell under the cursor to be visible, if
+    /// there is a cursor.
+    pub fn scroll_to_cursor(&mut self) {
+        if let Some((x, y)) = self.cursor_pos {
+            self.scroll_to_row(y);
+            self.scroll_to_column_index(x);
+        }
+    }
+
+    /// Returns the indices of the records and of the columns in view as of
+    /// the last layout. Records hidden by the filter may be among the indices
+    /// of the records. The columns are those after the frozen ones that fit
+    /// entirely; the frozen columns are always in view.
+    pub fn visible_range(&self) -> (Range<usize>, Range<usize>) {
+        let lines = self.scroll_base.start_line..self.scroll_base.start_line + self.scroll_base.view_height;
+        let mut rows = lines.filter_map(|line| self.line_to_row(line))
+            .map(|(row, _)| row)
+            .filter(|&row| row < self.num_records());
+        let rows = match rows.next() {
+            Some(first) => first..rows.next_back().unwrap_or(first) + 1,
+            None => 0..0,
+        };
+
+        let start = self.first_column.max(self.frozen_columns).min(self.columns.len());
+        let columns = if self.column_widths.len() == self.columns.len() {
+            start..(start + self.visible_scrolled_columns()).min(self.columns.len())
+        }
+        else { start..start };
+        (rows, columns)
+    }
+
+    /// Returns how far the body of this view is scrolled, in columns and
+    /// lines.
+    pub fn scroll_offset(&self) -> Vec2 {
+        let frozen = self.frozen_columns.min(self.columns.len());
+        Vec2::new(self.first_column.saturating_sub(frozen), self.scroll_base.start_line)
+    }
+
+    // ANNOUNCEMENTS -----------------------------------------------------------
+
+    /// Sets a callback that receives a plain-text description of every cursor
+    /// move, edit, and sort, such as `"row 12, column Price, value 4.99"`.
+    /// This lets accessibility layers or status bars voice the state of the
+    /// view.
+    pub fn set_on_announce<F>(&mut self, cb: F)
+    where
+        F: Fn(&str) + 'static,
+    {
+        self.on_announce = Some(Rc::new(cb));
+    }
+
+    fn announce(&self, message: &str) {
+        if self.batch_depth > 0 {
+            return;
+        }
+
+        if let Some(cb) = self.on_announce.as_ref() {
+            cb(message);
+        }
+    }
+
+    /// Announces the position and value of the cell at the given row and
+    /// column index.
+    fn announce_cell(&self, row: usize, x: usize) {
+        if self.on_announce.is_none() {
+            return;
+        }
+
+        let (key, def) = match self.columns.get_index(x) {
+            Some(column) => column,
+            None => return,
+        };
+
+        let value = match self.records.get(row).and_then(|record| record.get(key)) {
+            Some(value) => format!("value {}", self.format_value(key, value)),
+            None => "empty".to_string(),
+        };
+
+        self.announce(&format!("row {}, column {}, {}", row + 1, display_text(&def.title), value));
+    }
+
+    // COMPARING AND FORMATTING ------------------------------------------------
+
+    /// Sets a function that orders the values of the column with the specified
+    /// key when sorting by it, in place of their `Ord`, e.g. to sort names
+    /// without regard to case. Forgets the sort if it is by that column.
+    pub fn set_comparator<F>(&mut self, key: &str, comparator: F)
+    where
+        F: Fn(&D, &D) -> Ordering + 'static,
+    {
+        self.comparators.insert(key.to_string(), Rc::new(comparator));
+        self.forget_sort_by(key);
+    }
+
+    /// Removes the comparator of the column with the specified key, if any.
+    pub fn clear_comparator(&mut self, key: &str) {
+        if self.comparators.remove(key).is_some() {
+            self.forget_sort_by(key);
+        }
+    }
+
+    /// Sets a function that turns the values of the column with the specified
+    /// key into the text shown, in place of their `Display`, e.g. to show a
+    /// number of bytes as "1.2 GB" while still sorting by the number. Editors
+    /// still start from the `Display` of the value, which the parser reads.
+    pub fn set_formatter<F>(&mut self, key: &str, formatter: F)
+    where
+        F: Fn(&D) -> String + 'static,
+    {
+        self.formatters.insert(key.to_string(), Rc::new(formatter));
+        self.visible_cells.borrow_mut().clear();
+    }
+
+    /// Removes the formatter of the column with the specified key, if any.
+    pub fn clear_formatter(&mut self, key: &str) {
+        if self.formatters.remove(key).is_some() {
+            self.visible_cells.borrow_mut().clear();
+        }
+    }
+
+    /// Returns the text shown for a value in the column with the given key.
+    fn format_value(&self, key: &str, value: &D) -> String {
+        match self.formatters.get(key) {
+            Some(formatter) => self.escape_cell_text(key, &formatter(value)),
+            None if self.is_multiline(key) => escape_lines(&value.to_string()),
+            None => display_text(value),
+        }
+    }
+
+    /// Returns `true` if the column with the given key keeps line breaks.
+    fn is_multiline(&self, key: &str) -> bool {
+        self.columns.get(key).is_some_and(|def| def.multiline)
+    }
+
+    /// Escapes the text of a cell in the column with the given key, keeping
+    /// its line breaks if the column is multi-line.
+    fn escape_cell_text(&self, key: &str, text: &str) -> String {
+        if self.is_multiline(key) { escape_lines(text) } else { escape_controls(text) }
+    }
+
+    // VALIDATION --------------------------------------------------------------
+
+    /// Sets a function that checks the values of the column with the specified
+    /// key. Edits, pastes, and text written with `set_cell_text` whose values
+    /// it rejects are refused, keeping the editor open with its message, which
+    /// inline editors show just below the cell. Cells it rejects that got in
+    /// otherwise, e.g. through `set_cell`, are drawn with a marker, and are
+    /// listed by `validation_errors`.
+    pub fn set_validator<F>(&mut self, key: &str, validator: F)
+    where
+        F: Fn(&D) -> Result<(), String> + 'static,
+    {
+        self.validators.insert(key.to_string(), Rc::new(validator));
+    }
+
+    /// Removes the validator of the column with the specified key, if any.
+    pub fn clear_validator(&mut self, key: &str) {
+        self.validators.remove(key);
+    }
+
+    /// Runs the validator of the given column on the cell at the given row,
+    /// returning its message if the value is rejected. Empty cells are never
+    /// rejected.
+    fn validate_cell(&self, row: usize, key: &str) -> Option<String> {
+        let value = self.records.get(row)?.get(key)?;
+        self.validate_value(key, value).err()
+    }
+
+    /// Runs the validator of the given column on a value about to be written
+    /// into one of its cells, if the column has one.
+    fn validate_value(&self, key: &str, value: &D) -> Result<(), String> {
+        match self.validators.get(key) {
+            Some(validator) => validator(value),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns every cell currently rejected by the validator of its column,
+    /// ordered by row and then by column.
+    pub fn validation_errors(&self) -> Vec<ValidationError> {
+        if self.validators.is_empty() {
+            return Vec::new();
+        }
+
+        let mut errors = Vec::new();
+        for row in 0..self.records.len() {
+            for key in self.columns.keys() {
+                if let Some(message) = self.validate_cell(row, key) {
+                    errors.push(ValidationError { row, key: key.clone(), message });
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Moves the cursor to the next cell rejected by its validator, in row and
+    /// then column order, wrapping around after the last cell and skipping
+    /// records hidden by the filter. Returns `false` if there are no invalid
+    /// cells shown.
+    pub fn jump_to_next_error(&mut self) -> bool {
+        let num_cols = self.columns.len();
+        let num_cells = num_cols * self.records.len();
+        if self.validators.is_empty() || num_cells == 0 {
+            return false;
+        }
+
+        let start = self.cursor_pos.map_or(num_cells - 1, |(x, y)| y * num_cols + x);
+
+        for step in 1..=num_cells {
+            let cell = (start + step) % num_cells;
+            let (x, y) = (cell % num_cols, cell / num_cols);
+            let key = self.columns.get_index(x).map(|(k, _)| k.as_str()).unwrap_or_default();
+
+            if self.is_record_shown(y) && self.validate_cell(y, key).is_some() {
+                self.set_cursor_pos(x, y);
+                self.scroll_to_row(y);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // EDITING -----------------------------------------------------------------
+
+    /// Sets whether the cells of this view can be edited by the user.
+    /// Views are read-only by default.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Returns `true` if the cells of this view cannot be edited by the user.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Sets the marker drawn at the right end of the line under the titles
+    /// while this view is read-only, such as `"RO"`. Passing `None`, the
+    /// default, draws no marker.
+    pub fn set_read_only_indicator(&mut self, indicator: Option<&str>) {
+        self.read_only_indicator = indicator.map(str::to_string);
+    }
+
+    /// Sets a callback to be used when the user turns editing on or off
+    /// through `SpreadsheetAction::ToggleReadOnly`.
+    pub fn set_on_read_only_toggle<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, bool) + 'static,
+    {
+        self.on_read_only_toggle = Some(Rc::new(cb));
+    }
+
+    /// Turns editing on or off, on behalf of the user.
+    fn toggle_read_only(&mut self) -> EventResult {
+        self.read_only = !self.read_only;
+        self.announce(if self.read_only { "editing off" } else { "editing on" });
+
+        let read_only = self.read_only;
+        match self.on_read_only_toggle.clone() {
+            Some(cb) => EventResult::with_cb(move |s| cb(s, read_only)),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Sets the function used to turn the text typed into an editor back into
+    /// a cell value. Editing is only possible once a parser has been set.
+    pub fn set_parser<F>(&mut self, parser: F)
+    where
+        F: Fn(&str) -> Result<D, String> + 'static,
+    {
+        self.parser = Some(Rc::new(parser));
+    }
+
+    /// Uses the `FromStr` implementation of the cell type as the parser.
+    pub fn set_from_str_parser(&mut self)
+    where
+        D: FromStr,
+        D::Err: Display,
+    {
+        self.set_parser(|s| s.parse::<D>().map_err(|e| e.to_string()));
+    }
+
+    /// Uses the text of an edit as the new value as it is. This suits cells of
+    /// type `String`, as well as `Rc<str>` or `Cow<'static, str>`, which share
+    /// their text when records are sorted, copied, or exported.
+    pub fn set_from_text_parser(&mut self)
+    where
+        D: From<String>,
+    {
+        self.set_parser(|s| Ok(D::from(s.to_string())));
+    }
+
+    /// Edits the cells of the column with the specified key using a spinner:
+    /// Up/Down (or +/-) step the value by a fixed amount within fixed bounds,
+    /// and Enter writes it back into the cell.
+    pub fn set_spinner(&mut self, key: &str, spinner: Spinner) {
+        self.spinners.insert(key.to_string(), spinner);
+    }
+
+    /// Edits the cells of the column with the specified key as free text
+    /// again, undoing `set_spinner`.
+    pub fn clear_spinner(&mut self, key: &str) {
+        self.spinners.remove(key);
+    }
+
+    /// Edits the cells of the column with the specified key using a date
+    /// picker, which steps the year, month, and day of the date separately and
+    /// writes the result back in the format of the picker.
+    #[cfg(feature = "chrono")]
+    pub fn set_date_picker(&mut self, key: &str, picker: DatePicker) {
+        self.date_pickers.insert(key.to_string(), picker);
+    }
+
+    /// Edits the cells of the column with the specified key as free text
+    /// again, undoing `set_date_picker`.
+    #[cfg(feature = "chrono")]
+    pub fn clear_date_picker(&mut self, key: &str) {
+        self.date_pickers.remove(key);
+    }
+
+    /// Sets the function used to edit a cell in an external program. By
+    /// default this opens the user's `$EDITOR` on a temporary file.
+    ///
+    /// The function runs on the UI thread while cursive keeps the terminal, so
+    /// applications that manage their backend themselves may want to release
+    /// the terminal around the call.
+    pub fn set_external_editor<F>(&mut self, editor: F)
+    where
+        F: Fn(&str) -> io::Result<String> + 'static,
+    {
+        self.external_editor = Rc::new(editor);
+    }
+
+    /// Sets whether Enter edits single-line cells in place, rather than in a
+    /// modal dialog. Typing a character on an editable cell always edits it in
+    /// place, replacing its contents. Off by default.
+    pub fn set_edit_in_place(&mut self, edit_in_place: bool) {
+        self.edit_in_place = edit_in_place;
+    }
+
+    /// Sets a callback to be used when a cell is edited. Takes the row, the
+    /// column key, and the old and new values of the cell as input.
+    pub fn set_on_edit<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize, &str, Option<&D>, &D) + 'static,
+    {
+        self.on_edit = Some(Rc::new(cb));
+    }
+
+    /// Sets a callback to be used when an editor is opened on a cell, e.g. to
+    /// lock the underlying row or show contextual help.
+    ///
+    /// Edits done in an external program complete immediately and only
+    /// notify the edit callback.
+    pub fn set_on_edit_begin<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize, &str) + 'static,
+    {
+        self.on_edit_begin = Some(Rc::new(cb));
+    }
+
+    /// Sets a callback to be used when the user abandons an edit without
+    /// changing the cell.
+    pub fn set_on_edit_cancel<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize, &str) + 'static,
+    {
+        self.on_edit_cancel = Some(Rc::new(cb));
+    }
+
+    /// Sets a callback to be used when the user tries to edit a cell of a
+    /// read-only view, instead of ignoring the attempt. The callback may allow
+    /// the edit after all, e.g. after asking for confirmation, by calling
+    /// `edit_locked_cell` on this view.
+    pub fn set_on_locked_edit_attempt<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize, &str) + 'static,
+    {
+        self.on_locked_edit_attempt = Some(Rc::new(cb));
+    }
+
+    /// Moves the cursor to the cell at the given row and column, and opens its
+    /// editor even if this view is read-only.
+    pub fn edit_locked_cell(&mut self, row: usize, key: &str) -> EventResult {
+        let x = match self.columns.get_full(key) {
+            Some((x, _, _)) if row < self.records.len() => x,
+            _ => return EventResult::Ignored,
+        };
+
+        self.set_cursor_pos(x, row);
+        let read_only = mem::replace(&mut self.read_only, false);
+        let result = self.open_editor();
+        self.read_only = read_only;
+        result
+    }
+
+    /// Calls the submit callback with the cell under the cursor, if both are
+    /// set.
+    fn submit_cursor_cell(&self) -> EventResult {
+        match (self.on_submit.clone(), self.cursor_pos, self.cursor_text()) {
+            (Some(cb), Some((x, y)), Some((key, _))) => {
+                let key = key.to_string();
+                EventResult::with_cb(move |s| cb(s, y, x, &key))
+            },
+            _ => EventResult::Ignored,
+        }
+    }
+
+    /// Notifies the locked-edit callback of an attempt to edit the cell under
+    /// the cursor of a read-only view.
+    fn locked_edit_attempt(&self) -> EventResult {
+        if !self.read_only || self.parser.is_none() {
+            return EventResult::Ignored;
+        }
+
+        match (self.on_locked_edit_attempt.clone(), self.cursor_pos, self.cursor_text()) {
+            (Some(cb), Some((_, y)), Some((key, _))) => {
+                let key = key.to_string();
+                EventResult::with_cb(move |s| cb(s, y, &key))
+            },
+            _ => EventResult::Ignored,
+        }
+    }
+
+    /// Sets whether the changes made to cells through their editors are kept,
+    /// so that they can be looked up with `cell_history`. Off by default.
+    pub fn set_track_history(&mut self, track_history: bool) {
+        self.track_history = track_history;
+        if !track_history {
+            self.history.clear();
+        }
+    }
+
+    /// Returns the changes made to the cell at the given row and column while
+    /// history tracking was on, oldest first.
+    pub fn cell_history(&self, row: usize, key: &str) -> &[CellChange<D>] {
+        self.history.get(&(row, key.to_string())).map_or(&[], |changes| changes.as_slice())
+    }
+
+    /// Forgets the recorded changes of all cells.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Sets a callback to be used for every change made to a cell through its
+    /// editors, e.g. to log who changed what during a session.
+    pub fn set_on_audit<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize, &str, &CellChange<D>) + 'static,
+    {
+        self.on_audit = Some(Rc::new(cb));
+    }
+
+    /// Returns `true` if an editor is currently open on a cell.
+    pub fn is_editing(&self) -> bool {
+        self.active_edit.is_some()
+    }
+
+    /// Returns `true` if the cell at the cursor can be edited.
+    fn can_edit(&self) -> bool {
+        !self.read_only && self.parser.is_some() && self.cursor_pos.is_some() && self.source.is_none()
+            && !self.cursor_on_collapsed_group() && !self.cursor_cell_read_only()
+    }
+
+    /// Returns the key of the column at the cursor, along with the text of the
+    /// cell at the cursor.
+    fn cursor_text(&self) -> Option<(&str, String)> {
+        let (x, y) = self.cursor_pos?;
+        let key = self.columns.get_index(x)?.0;
+        let text = match self.formula(y, key) {
+            Some(source) => source.to_string(),
+            None => self.records.get(y)?.get(key).map(|d| d.to_string()).unwrap_or_default(),
+        };
+        Some((key, text))
+    }
+
+    /// Returns the inline editor configured for the column of the cell under
+    /// the cursor, if any, and if it can handle the contents of the cell.
+    fn inline_editor(&self) -> Option<(String, Editor)> {
+        let (key, text) = self.cursor_text()?;
+
+        if let Some(&spinner) = self.spinners.get(key) {
+            let editor = SpinnerEditor::new(spinner, &text)?;
+            return Some((key.to_string(), Editor::Spinner(editor)));
+        }
+
+        #[cfg(feature = "chrono")]
+        {
+            if let Some(picker) = self.date_pickers.get(key) {
+                let editor = DateEditor::new(picker.clone(), &text)?;
+                return Some((key.to_string(), Editor::Date(editor)));
+            }
+        }
+
+        if self.edit_in_place && !text.contains('\n') {
+            let editor = self.text_editor(key, text);
+            return Some((key.to_string(), editor));
+        }
+
+        None
+    }
+
+    /// Creates an inline text editor for a cell of the given column, starting
+    /// out with the given text.
+    fn text_editor(&self, key: &str, text: String) -> Editor {
+        let width = self.columns.get_full(key).map_or(0, |(x, _, _)| self.column_widths[x]);
+        Editor::Text(TextEditor::new(text, width))
+    }
+
+    /// Opens the editor suited to the cell under the cursor: a spinner or date
+    /// picker for columns that have one and whose cell holds a valid value,
+    /// an inline text editor for single-line cells if editing in place, and a
+    /// modal dialog otherwise. Does nothing if the cell cannot be edited.
+    pub fn open_editor(&mut self) -> EventResult {
+        if !self.can_edit() {
+            return EventResult::Ignored;
+        }
+
+        match (self.inline_editor(), self.cursor_pos) {
+            (Some((key, editor)), Some((_, y))) => self.begin_edit(ActiveEdit { row: y, key, editor, error: None }),
+            _ => self.open_edit_dialog(),
+        }
+    }
+
+    /// Opens an inline text editor on the cell under the cursor, replacing its
+    /// contents with the given character as if the user had typed it over the
+    /// cell. Does nothing if the cell cannot be edited.
+    fn type_over_cell(&mut self, c: char) -> EventResult {
+        if !self.can_edit() {
+            return EventResult::Ignored;
+        }
+
+        match (self.cursor_pos, self.cursor_text()) {
+            (Some((_, y)), Some((key, _))) => {
+                let key = key.to_string();
+                let editor = self.text_editor(&key, c.to_string());
+                self.begin_edit(ActiveEdit { row: y, key, editor, error: None })
+            },
+            _ => EventResult::Ignored,
+        }
+    }
+
+    /// Opens a modal dialog over this view, containing the full value of the
+    /// cell under the cursor in a multi-line editor. This is useful for very
+    /// long values, or when the view is too narrow to edit a cell in place.
+    /// Does nothing if the cell cannot be edited.
+    pub fn open_edit_dialog(&mut self) -> EventResult {
+        if !self.can_edit() {
+            return EventResult::Ignored;
+        }
+
+        match (self.cursor_pos, self.cursor_text()) {
+            (Some((_, y)), Some((key, content))) => {
+                let key = key.to_string();
+                let editor = self.dialog_editor(y, &key, content);
+                self.begin_edit(ActiveEdit { row: y, key, editor, error: None })
+            },
+            _ => EventResult::Ignored,
+        }
+    }
+
+    /// Creates a dialog editor for the cell at the given row and column,
+    /// starting out with the given text.
+    fn dialog_editor(&self, row: usize, key: &str, content: String) -> Editor {
+        let title = format!("{} (row {})", display_text(&self.columns[key].title), row + 1);
+        let available = self.last_size.saturating_sub((0, HEADER_HEIGHT));
+        let mut editor = Editor::Dialog(DialogEditor::new(&title, content, available));
+        editor.layout(available);
+        editor
+    }
+
+    /// Makes `edit` the open editor, notifying the begin-edit callback.
+    fn begin_edit(&mut self, edit: ActiveEdit) -> EventResult {
+        let (row, key) = (edit.row, edit.key.clone());
+        self.active_edit = Some(edit);
+
+        match self.on_edit_begin.clone() {
+            Some(cb) => EventResult::with_cb(move |s| cb(s, row, &key)),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Closes the open editor, if any, discarding its contents.
+    pub fn cancel_edit(&mut self) -> EventResult {
+        match (self.active_edit.take(), self.on_edit_cancel.clone()) {
+            (Some(edit), Some(cb)) => EventResult::with_cb(move |s| cb(s, edit.row, &edit.key)),
+            (Some(_), None) => EventResult::Consumed(None),
+            (None, _) => EventResult::Ignored,
+        }
+    }
+
+    // LAYOUT ------------------------------------------------------------------
+
+    /// Sets the number of terminal lines taken up by every record, with the
+    /// values centered vertically inside. Heights below 1 are treated as 1.
+    pub fn set_row_height(&mut self, row_height: usize) {
+        self.row_height = row_height.max(1);
+    }
+
+    /// Returns the number of terminal lines taken up by every record.
+    pub fn row_height(&self) -> usize {
+        self.row_height
+    }
+
+    /// Sets the number of leading columns that stay pinned on the left while
+    /// the others scroll horizontally, e.g. to keep the column identifying
+    /// each record in sight. Like the header, which never scrolls vertically,
+    /// they are set apart by a double line. None by default.
+    pub fn set_frozen_columns(&mut self, frozen_columns: usize) {
+        self.frozen_columns = frozen_columns;
+        self.first_column = self.first_column.max(frozen_columns);
+        if let Some((x, _)) = self.cursor_pos {
+            self.scroll_to_column_index(x);
+        }
+    }
+
+    /// Returns the number of leading columns pinned on the left.
+    pub fn frozen_columns(&self) -> usize {
+        self.frozen_columns
+    }
+
+    /// Sets whether a horizontal scrollbar is drawn at the bottom of the
+    /// body when the columns are too wide to fit. Off by default.
+    pub fn set_horizontal_scrollbar(&mut self, horizontal_scrollbar: bool) {
+        self.horizontal_scrollbar = horizontal_scrollbar;
+    }
+
+    /// Returns the total width of the gutter, the frozen columns, and the
+    /// separators to their right, none of which scroll horizontally.
+    fn frozen_width(&self) -> usize {
+        let frozen = self.frozen_columns.min(self.column_widths.len());
+        let columns: usize = (0..frozen)
+            .filter(|&x| self.is_column_shown(x))
+            .map(|x| self.column_widths[x] + COLUMN_SEPARATOR.width())
+            .sum();
+        self.gutter_offset() + columns
+    }
+
+    /// Returns the number of columns after the frozen ones that fit entirely
+    /// in the body at the current horizontal scroll offset, and at least one.
+    fn visible_scrolled_columns(&self) -> usize {
+        let available = self.body_width().saturating_sub(self.frozen_width());
+        let start = self.first_column.max(self.frozen_columns);
+        let (mut count, mut x) = (0, 0);
+        for (_, &width) in self.column_widths.iter().enumerate().skip(start).filter(|&(i, _)| self.is_column_shown(i)) {
+            x += width;
+            if x > available {
+                break;
+            }
+            count += 1;
+            x += COLUMN_SEPARATOR.width();
+        }
+        count.max(1)
+    }
+
+    /// Draws the horizontal scrollbar below the columns after the frozen ones,
+    /// with a thumb showing which of them are visible.
+    fn draw_horizontal_scrollbar(&self, printer: &Printer) {
+        let frozen = self.frozen_columns.min(self.column_widths.len());
+        let track_x = self.frozen_width();
+        let track = self.body_width().saturating_sub(track_x);
+        let total = self.shown_columns_width(frozen..self.column_widths.len());
+        if track == 0 || total <= track {
+            return;
+        }
+
+        let offset: usize = (frozen..self.first_column.max(frozen))
+            .filter(|&x| self.is_column_shown(x))
+            .map(|x| self.column_widths[x] + COLUMN_SEPARATOR.width())
+            .sum();
+        let thumb = (track * track / total).max(1);
+        let start = ((track - thumb + 1) * offset / (1 + total - track)).min(track - thumb);
+
+        let color = if printer.focused { ColorStyle::highlight() } else { ColorStyle::highlight_inactive() };
+        printer.print_hline((track_x, 0), track, "-");
+        printer.with_color(color, |p| p.print_hline((track_x + start, 0), thumb, "▒"));
+    }
+
+    /// Returns the width taken up by the columns in the given range that are
+    /// not hidden, along with the separators between them.
+    fn shown_columns_width(&self, columns: Range<usize>) -> usize {
+        let (width, count) = columns.filter(|&x| self.is_column_shown(x))
+            .fold((0, 0_usize), |(width, count), x| (width + self.column_widths[x], count + 1));
+        width + count.saturating_sub(1) * COLUMN_SEPARATOR.width()
+    }
+
+    /// Returns the number of lines taken up under the body, by the row of
+    /// aggregates, the footer shown while records load, and the status bar.
+    fn footer_height(&self) -> usize {
+        usize::from(self.has_column_aggregates()) + usize::from(self.is_loading()) + usize::from(self.status_bar)
+    }
+
+    /// Returns the width of the body as of the last layout, without the
+    /// scrollbar.
+    fn body_width(&self) -> usize {
+        if self.scroll_base.scrollable() { self.last_size.x.saturating_sub(1 + self.scroll_base.right_padding) }
+        else { self.last_size.x }
+    }
+
+    /// Returns the indices of the columns drawn, from left to right: the
+    /// frozen ones, then the others starting at the horizontal scroll offset.
+    /// Hidden columns are left out, and so are columns added since the last
+    /// layout, which have no width yet.
+    fn drawn_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        let len = self.columns.len().min(self.column_widths.len());
+        let frozen = self.frozen_columns.min(len);
+        (0..frozen).chain(self.first_column.max(frozen)..len).filter(move |&x| self.is_column_shown(x))
+    }
+
+    /// Returns the separator drawn to the left of the column at the given
+    /// index, which must not be the first one drawn.
+    fn separator_before(&self, i: usize) -> &'static str {
+        self.vertical_separator(self.frozen_columns > 0 && i == self.first_column.max(self.frozen_columns))
+    }
+
+    /// Scrolls horizontally just enough for the column at the given index to
+    /// be visible, as far as the frozen columns leave room for it.
+    fn scroll_to_column_index(&mut self, x: usize) {
+        let frozen = self.frozen_columns.min(self.columns.len());
+        if x < frozen || x >= self.column_widths.len() {
+            return;
+        }
+        if x < self.first_column {
+            self.first_column = x;
+            return;
+        }
+
+        let available = self.body_width().saturating_sub(self.frozen_width());
+        let mut first = self.first_column.max(frozen);
+        while first < x && self.shown_columns_width(first..x + 1) > available {
+            first += 1;
+        }
+        self.first_column = first;
+    }
+
+    /// Sets a callback to be used when this view is laid out at a different
+    /// size than before, e.g. because the terminal was resized.
+    pub fn set_on_layout<F>(&mut self, cb: F)
+    where
+        F: Fn(Vec2) + 'static,
+    {
+        self.on_layout = Some(Rc::new(cb));
+    }
+
+    /// Sets a callback to be used when a column is laid out at a different
+    /// width than before, e.g. to persist the widths users settle on. Columns
+    /// being laid out for the first time do not trigger the callback.
+    pub fn set_on_column_resize<F>(&mut self, cb: F)
+    where
+        F: Fn(&str, usize) + 'static,
+    {
+        self.on_column_resize = Some(Rc::new(cb));
+    }
+
+    /// Records the width each column was laid out at, notifying the resize
+    /// callback of the columns whose width changed.
+    fn report_column_widths(&mut self) {
+        for ((key, def), &width) in self.columns.iter_mut().zip(&self.column_widths) {
+            let previous = def.laid_out_width.replace(width);
+            if previous.is_some_and(|previous| previous != width) {
+                if let Some(cb) = self.on_column_resize.as_ref() {
+                    cb(key, width);
+                }
+            }
+        }
+    }
+
+    /// Groups records into bands of `every` records, marked as given. Passing
+    /// `None` or a band size of 0 turns banding off.
+    pub fn set_banding(&mut self, banding: Option<(usize, Banding)>) {
+        self.banding = banding.filter(|&(every, _)| every > 0);
+    }
+
+    /// Returns the size of the bands records are grouped in, and how they are
+    /// marked, if banding is on.
+    pub fn banding(&self) -> Option<(usize, Banding)> {
+        self.banding
+    }
+
+    /// Returns `true` if the record at the given index is drawn in a stripe,
+    /// by banding or by the zebra striping of the style.
+    fn is_striped(&self, row: usize) -> bool {
+        match self.banding {
+            Some((every, Banding::Stripe)) => (self.shown_position(row) / every) % 2 == 1,
+            Some((_, Banding::Line)) => false,
+            None => self.style.zebra && self.shown_position(row) % 2 == 1,
+        }
+    }
+
+    /// Returns the number of records per band if bands are separated by lines,
+    /// which is every record if the grid has lines between records.
+    fn band_line_every(&self) -> Option<usize> {
+        match self.banding {
+            _ if self.grid_mode.has_row_lines() => Some(1),
+            Some((every, Banding::Line)) => Some(every),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of lines taken up by all records shown and the
+    /// separators between them.
+    fn content_lines(&self) -> usize {
+        let num_recs = self.len_shown_records();
+        let separators = match self.band_line_every() {
+            Some(every) if num_recs > 0 => (num_recs - 1) / every,
+            _ => 0,
+        };
+
+        self.lines_before(num_recs) + separators + self.group_header_positions.len() + self.group_footer_ends.len()
+    }
+
+    /// Returns the first line taken up by the record at the given index.
+    fn row_to_line(&self, row: usize) -> usize {
+        self.position_to_line(self.shown_position(row))
+    }
+
+    /// Returns the first line taken up by the record shown at the given
+    /// position, below the band separators and group headers and footers
+    /// before it.
+    fn position_to_line(&self, position: usize) -> usize {
+        let separators = self.band_line_every().map_or(0, |every| position / every);
+        let groups = self.group_headers_through(position) + self.group_footers_before(position);
+        self.lines_before(position) + separators + groups
+    }
+
+    /// Returns the last position at which the record shown starts on the
+    /// given line or above it, or `None` if the line is above every record.
+    fn position_at_line(&self, line: usize) -> Option<usize> {
+        // Every record takes up at least one line, so the record on the line
+        // is at most that far along.
+        let (mut low, mut high) = (0, line + 1);
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if self.position_to_line(middle) <= line { low = middle } else { high = middle }
+        }
+        Some(low).filter(|&low| self.position_to_line(low) <= line)
+    }
+
+    /// Returns the number of lines taken up by the record at the given index.
+    fn record_height(&self, row: usize) -> usize {
+        self.record_height_at(self.shown_position(row))
+    }
+
+    /// Returns the record drawn on the given line along with the offset of the
+    /// line inside the record, or `None` if the line is a band separator or a
+    /// group header or footer. Lines past the last record shown map to indices past the
+    /// last record.
+    fn line_to_row(&self, line: usize) -> Option<(usize, usize)> {
+        let position = self.position_at_line(line)?;
+        let offset = line - self.position_to_line(position);
+        if offset >= self.record_height_at(position) {
+            return None;
+        }
+
+        let row = self.shown_row(position)
+            .unwrap_or_else(|| self.num_records() + position - self.len_shown_records());
+        Some((row, offset))
+    }
+
+    /// Scrolls vertically just enough for all lines of the record at the given
+    /// index to be visible, e.g. to jump to the record with a given ID. A
+    /// record hidden by the filter scrolls to where it would be. The view
+    /// must have been laid out.
+    pub fn scroll_to_row(&mut self, row: usize) {
+        let first_line = self.row_to_line(row);
+        self.scroll_base.scroll_to(first_line + self.record_height(row) - 1);
+        self.scroll_base.scroll_to(first_line);
+    }
+
+    // FORMULAS ----------------------------------------------------------------
+
+    /// Sets whether text starting with `=` typed into a cell is taken to be a
+    /// formula, whose result is shown in the cell instead. Formulas address
+    /// cells by column letter and row number, as in `=SUM(B2:B10) / A1`, or
+    /// by column key and row, as in `=[price] * [qty]` for the cells in the
+    /// same record. Off by default.
+    pub fn set_formulas_enabled(&mut self, enabled: bool) {
+        self.formulas_enabled = enabled;
+    }
+
+    /// Returns `true` if cells can hold formulas.
+    pub fn formulas_enabled(&self) -> bool {
+        self.formulas_enabled
+    }
+
+    /// Puts a formula into the cell at the given row and column, replacing its
+    /// value, or returns a message describing why the formula is invalid.
+    pub fn set_formula(&mut self, row: usize, key: &str, source: &str) -> Result<(), String> {
+        let expr = formula::parse(source)?;
+        let record = match self.records.get_mut(row) {
+            Some(record) if self.columns.contains_key(key) => record,
+            _ => return Err(format!("No cell at row {} of column {}", row + 1, key)),
+        };
+
+        record.remove(key);
+        let source = source.trim().to_string();
+        self.formulas.insert((row, key.to_string()), Formula { source, expr });
+        self.invalidate_cell(row, key);
+        self.refilter();
+        self.notify(|| ChangeEvent::CellChanged { row, key: key.to_string() });
+        Ok(())
+    }
+
+    /// Removes the formula from the cell at the given row and column, leaving
+    /// it empty. Returns the formula, if there was one.
+    pub fn clear_formula(&mut self, row: usize, key: &str) -> Option<String> {
+        let formula = self.formulas.remove(&(row, key.to_string()))?;
+        self.invalidate_cell(row, key);
+        self.notify(|| ChangeEvent::CellChanged { row, key: key.to_string() });
+        Some(formula.source)
+    }
+
+    /// Returns the formula in the cell at the given row and column, if any.
+    pub fn formula(&self, row: usize, key: &str) -> Option<&str> {
+        self.formula_at(row, key).map(|formula| formula.source.as_str())
+    }
+
+    /// Discards the computed values of every formula, along with the text of
+    /// the cells last drawn.
+    fn clear_formula_cache(&mut self) {
+        self.formula_cache.get_mut().clear();
+        self.visible_cells.get_mut().clear();
+    }
+
+    /// Discards the computed values of the formulas depending on the cell at
+    /// the given row and column.
+    fn invalidate_cell(&self, row: usize, key: &str) {
+        self.forget_drawn_row(row);
+        if let Some((x, _, _)) = self.columns.get_full(key) {
+            self.formula_cache.borrow_mut().invalidate((x, row));
+        }
+    }
+
+    /// Forgets the text last drawn for the record at the given index, and for
+    /// the records holding formulas, which may depend on it.
+    fn forget_drawn_row(&self, row: usize) {
+        self.visible_cells.borrow_mut().remove(&row);
+        self.forget_drawn_formula_rows();
+    }
+
+    fn forget_drawn_formula_rows(&self) {
+        if !self.formulas.is_empty() {
+            let mut visible_cells = self.visible_cells.borrow_mut();
+            for (row, _) in self.formulas.keys() {
+                visible_cells.remove(row);
+            }
+        }
+    }
+
+    /// Forgets the text last drawn for the records the given change may have
+    /// changed, keeping the rest.
+    fn forget_drawn_cells(&self, event: &ChangeEvent) {
+        match event {
+            ChangeEvent::CellChanged { row, .. } => self.forget_drawn_row(*row),
+            // The records from `start` on may have moved.
+            ChangeEvent::RowsInserted { start, .. } | ChangeEvent::RowsRemoved { start, .. } => {
+                self.visible_cells.borrow_mut().retain(|&row, _| row < *start);
+                self.forget_drawn_formula_rows();
+            },
+            ChangeEvent::Sorted { .. } | ChangeEvent::Reset => self.visible_cells.borrow_mut().clear(),
+        }
+    }
+
+    fn formula_at(&self, row: usize, key: &str) -> Option<&Formula> {
+        if self.formulas.is_empty() {
+            return None;
+        }
+        self.formulas.get(&(row, key.to_string()))
+    }
+
+    /// Returns the value of the cell at the given row and column: the result
+    /// of its formula if it has one, and its value as text or as a number
+    /// otherwise.
+    pub fn cell_value(&self, row: usize, key: &str) -> FormulaValue {
+        match self.columns.get_full(key) {
+            Some((x, _, _)) => self.evaluation().value((x, row)),
+            None => FormulaError::Ref.into(),
+        }
+    }
+
+    /// Returns the values of every cell in a range, by row and then column.
+    pub fn range_values(&self, range: &CellRange) -> Vec<FormulaValue> {
+        let evaluation = self.evaluation();
+        range.cells().map(|cell| evaluation.value(cell)).collect()
+    }
+
+    fn evaluation(&self) -> Evaluation<'_, D> {
+        Evaluation { view: self, visiting: RefCell::new(Vec::new()) }
+    }
+
+    /// Shows the cell under the cursor in the linked formula bar, if any.
+    fn sync_formula_bar(&self) {
+        let mut state = match self.formula_bar.as_ref() {
+            Some(state) => state.borrow_mut(),
+            None => return,
+        };
+
+        match (self.cursor_pos, self.cursor_text()) {
+            (Some((x, y)), Some((key, text))) => {
+                state.cell = Some((y, key.to_string()));
+                state.address = format!("{}{}", column_name(x), y + 1);
+                state.text = text;
+            },
+            _ => *state = FormulaBarState::default(),
+        }
+    }
+
+    /// Returns the text shown in the cell at the given row and column.
+    fn cell_text(&self, row: usize, key: &str) -> String {
+        if let Some(source) = self.source.as_ref() {
+            return source.get(row, key).map(|value| self.format_value(key, &value)).unwrap_or_default();
+        }
+
+        match self.formula_at(row, key) {
+            Some(_) => self.escape_cell_text(key, &self.cell_value(row, key).to_string()),
+            None => self.records.get(row).and_then(|r| r.get(key)).map(|value| self.format_value(key, value)).unwrap_or_default(),
+        }
+    }
+
+    /// Names a range of cells, written like `C2:C100`, so that formulas can
+    /// refer to it as e.g. `=SUM(prices)`. The range grows and shrinks as
+    /// records are inserted into or removed from it.
+    pub fn define_name(&mut self, name: &str, range: &str) -> Result<(), String> {
+        let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+            && CellRange::parse(name).is_none()
+            && !name.eq_ignore_ascii_case("true")
+            && !name.eq_ignore_ascii_case("false");
+
+        if !valid {
+            return Err(format!("Invalid name: {}", name));
+        }
+
+        let range = CellRange::parse(range).ok_or_else(|| format!("Invalid range: {}", range))?;
+        self.names.insert(name.to_string(), range);
+        self.clear_formula_cache();
+        Ok(())
+    }
+
+    /// Removes a named range, returning the range it named.
+    pub fn remove_name(&mut self, name: &str) -> Option<CellRange> {
+        self.clear_formula_cache();
+        self.names.shift_remove(name)
+    }
+
+    /// Returns the range with the given name, if any.
+    pub fn named_range(&self, name: &str) -> Option<CellRange> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns every named range, in the order they were defined.
+    pub fn names(&self) -> impl Iterator<Item = (&str, CellRange)> {
+        self.names.iter().map(|(name, range)| (name.as_str(), *range))
+    }
+
+    /// Selects the cells of the range with the given name. Returns `false` if
+    /// there is no such range.
+    pub fn select_named_range(&mut self, name: &str) -> bool {
+        let range = match self.named_range(name) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        let num_cols = self.columns.len();
+        self.selected_cells = range.cells().filter(|&(x, y)| x < num_cols && self.is_record_shown(y)).collect();
+        true
+    }
+
+    /// Makes a function callable from formulas, replacing any function with
+    /// the same name, including the built-in ones. Names are case-insensitive.
+    /// Ranges passed to the function are flattened into the values of their
+    /// cells.
+    pub fn register_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[FormulaValue]) -> FormulaValue + 'static,
+    {
+        self.functions.insert(name.to_ascii_uppercase(), Rc::new(function));
+        self.clear_formula_cache();
+    }
+
+    /// Makes a function no longer callable from formulas. Returns `false` if
+    /// there was no such function.
+    pub fn unregister_function(&mut self, name: &str) -> bool {
+        self.clear_formula_cache();
+        self.functions.remove(&name.to_ascii_uppercase()).is_some()
+    }
+
+    /// Returns the names of every function callable from formulas, in no
+    /// particular order.
+    pub fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str)
+    }
+
+    // STYLE -------------------------------------------------------------------
+
+    /// Sets whether this view avoids colors, showing the cursor, the
+    /// selection, the header, and invalid cells purely through text effects
+    /// (reverse, bold, underline) and ASCII markers. This suits 2-color
+    /// terminals and colorblind users. Off by default.
+    pub fn set_monochrome(&mut self, monochrome: bool) {
+        self.monochrome = monochrome;
+    }
+
+    /// Returns `true` if this view avoids colors.
+    pub fn is_monochrome(&self) -> bool {
+        self.monochrome
+    }
+
+    /// Sets how this view shows that it has focus. Passing `None`, the default,
+    /// draws focused and unfocused views the same, apart from the cursor.
+    pub fn set_focus_indicator(&mut self, indicator: Option<FocusIndicator>) {
+        self.focus_indicator = indicator;
+    }
+
+    /// Returns how this view shows that it has focus, if at all.
+    pub fn focus_indicator(&self) -> Option<FocusIndicator> {
+        self.focus_indicator
+    }
+
+    /// Adds a rule styling every cell of the records `predicate` holds for,
+    /// e.g. to mark records whose `status` is `error` and whose `retries` are
+    /// over 3. When several rules match a record, their styles are combined in
+    /// the order the rules were added. The cursor and the selection are drawn
+    /// over these styles, and monochrome views only keep their effects.
+    pub fn add_row_style<F>(&mut self, style: Style, predicate: F)
+    where
+        F: Fn(&Record<D>) -> bool + 'static,
+    {
+        self.row_styles.push((Rc::new(predicate), style));
+    }
+
+    /// Removes every row styling rule.
+    pub fn clear_row_styles(&mut self) {
+        self.row_styles.clear();
+    }
+
+    /// Returns the combined style of the row styling rules matching the record
+    /// at the given row.
+    fn row_style(&self, row: usize) -> Style {
+        let record = match self.records.get(row) {
+            Some(record) => record,
+            None => return Style::none(),
+        };
+
+        let style = self.row_styles.iter()
+            .filter(|(predicate, _)| predicate(record))
+            .fold(Style::none(), |style, (_, rule)| style.combine(*rule));
+
+        if self.monochrome { Style { color: None, ..style } }
+        else { style }
+    }
+
+    /// Sets a callback styling cells by their value, e.g. to color negative
+    /// numbers red, replacing any callback set before. It is called for the
+    /// cells holding a value, and the styles it returns are drawn over those of
+    /// the column and the row styles, but under the cursor, the selection, and
+    /// search matches. Monochrome views only keep their effects.
+    pub fn set_cell_style<F>(&mut self, cb: F)
+    where
+        F: Fn(usize, &str, &D) -> Option<Style> + 'static,
+    {
+        self.cell_style = Some(Rc::new(cb));
+    }
+
+    /// Removes the callback styling cells by their value.
+    pub fn clear_cell_style(&mut self) {
+        self.cell_style = None;
+    }
+
+    /// Returns the style of the contents of a cell: the style of its column,
+    /// with the style of its row and then the style given by the cell style
+    /// callback drawn over it. `row_style` is the style of its row.
+    fn content_style(&self, row: usize, key: &str, def: &ColumnDef, row_style: Style) -> Style {
+        let mut style = def.style.combine(row_style);
+        if !self.read_only && self.is_cell_read_only(row, key) {
+            style = style.combine(self.style.read_only);
+        }
+        let value = self.records.get(row).and_then(|record| record.get(key));
+        if let (Some(cb), Some(value)) = (self.cell_style.as_ref(), value) {
+            if let Some(cell_style) = cb(row, key, value) {
+                style = style.combine(cell_style);
+            }
+        }
+
+        if self.monochrome { Style { color: None, ..style } }
+        else { style }
+    }
+
+    /// Sets how column titles are shortened when they do not fit, along with
+    /// the sort glyph. Defaults to `HeaderTruncation::Middle`.
+    pub fn set_header_truncation(&mut self, truncation: HeaderTruncation) {
+        self.header_truncation = truncation;
+    }
+
+    /// Returns how column titles are shortened when they do not fit.
+    pub fn header_truncation(&self) -> HeaderTruncation {
+        self.header_truncation
+    }
+
+    /// Returns the glyph showing the direction of the sort if the records are
+    /// sorted by the given column.
+    fn sort_glyph(&self, key: &str) -> Option<&'static str> {
+        let (_, order) = self.sort_spec.iter().find(|(sorted, _)| sorted == key)?;
+        Some(match (*order, self.monochrome) {
+            (SortOrder::Ascending, false) => SORT_ASCENDING,
+            (SortOrder::Descending, false) => SORT_DESCENDING,
+            (SortOrder::Ascending, true) => SORT_ASCENDING_ASCII,
+            (SortOrder::Descending, true) => SORT_DESCENDING_ASCII,
+        })
+    }
+
+    /// Returns the title of a column, followed by its sort glyph, shortened to
+    /// fit in the given width.
+    fn header_text(&self, key: &str, def: &ColumnDef, width: usize) -> String {
+        let title = display_text(&def.title);
+        let glyph = match self.sort_glyph(key) {
+            Some(glyph) => glyph,
+            None => return title,
+        };
+
+        // Drop the title, and the space before the glyph, before the glyph.
+        let room = width.saturating_sub(glyph.width() + 1);
+        let title = match self.header_truncation {
+            HeaderTruncation::Clip => title,
+            _ if room == 0 => return glyph.to_string(),
+            HeaderTruncation::Middle => ellipsize_middle(&title, room),
+            HeaderTruncation::End => ellipsize_end(&title, room),
+        };
+
+        format!("{} {}", title, glyph)
+    }
+
+    /// Returns the style of a cell of the body, which is at the cursor if
+    /// `cursor`, part of the selection if `selected`, and being edited in
+    /// place if `editing`.
+    fn cell_style(&self, focused: bool, editing: bool, cursor: bool, selected: bool, matched: bool, striped: bool) -> Style {
+        if self.monochrome {
+            // Bold and underline leave room for the reversed fields of the
+            // inline editors.
+            if editing { Style::from(Effect::Bold).combine(Effect::Underline) }
+            else if cursor && focused { Effect::Reverse.into() }
+            else if cursor { Effect::Underline.into() }
+            else if selected { Effect::Bold.into() }
+            else if matched { Effect::Italic.into() }
+            else { Style::none() }
+        }
+        else {
+            if editing || (cursor && focused) { self.style.cursor }
+            else if cursor { self.style.cursor_inactive }
+            else if selected { self.style.selection }
+            else if matched { self.style.search_match }
+            else if striped { self.style.alternate_row }
+            else { Style::none() }
+        }
+    }
+
+    /// Returns the style of the marker drawn at the end of invalid cells.
+    fn error_marker_style(&self) -> Style {
+        if self.monochrome { Effect::Bold.into() }
+        else { ColorStyle::new(Color::Light(BaseColor::Red), PaletteColor::View).into() }
+    }
+
+    // BINDINGS ----------------------------------------------------------------
+
+    /// Replaces the table mapping input events to actions of this view.
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+    }
+
+    /// Returns the table mapping input events to actions of this view.
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    /// Binds an event to an action of this view, replacing any action
+    /// previously bound to the same event.
+    pub fn bind(&mut self, event: Event, action: SpreadsheetAction) {
+        self.bindings.bind(event, action);
+    }
+
+    /// Removes the binding for an event, returning the action it was bound to.
+    pub fn unbind(&mut self, event: &Event) -> Option<SpreadsheetAction> {
+        self.bindings.unbind(event)
+    }
+
+    /// Returns a callback that shows a popup listing every bound action of
+    /// this view and its keys.
+    pub fn help_popup(&self) -> EventResult {
+        let text = self.bindings.help_text();
+        EventResult::with_cb(move |s| {
+            s.add_layer(Dialog::around(TextView::new(text.clone()))
+                .title("Keys")
+                .dismiss_button("Close"));
+        })
+    }
+
+    // CURSIVE-RELATED ---------------------------------------------------------
+
+    /// Disables this view. A disabled view cannot be selected.
+    pub fn disable(&mut self) {
+        self.enabled = false;
+    }
+
+    /// Re-enables this view.
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// Enable or disable this view.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Returns `true` if this view is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Creates a formula bar linked to this view, which must be found under
+    /// the given name in the view tree (see `Nameable::with_name`). The bar
+    /// shows the address and the formula or raw value of the cell under the
+    /// cursor, and writes edits made in it back to this view.
+    ///
+    /// A view links to one formula bar at a time; creating another one
+    /// unlinks the previous one.
+    pub fn formula_bar(&mut self, name: &str) -> FormulaBarView {
+        let state = Rc::new(RefCell::new(FormulaBarState::default()));
+        self.formula_bar = Some(state.clone());
+        self.sync_formula_bar();
+
+        let name = name.to_string();
+        FormulaBarView::new(state, move |s, row, key, text| {
+            let result = s.call_on_name(&name, |view: &mut Self| view.set_cell_text(row, key, text));
+            match result {
+                Some(Ok(result)) => result.process(s),
+                Some(Err(message)) => s.add_layer(Dialog::info(message)),
+                None => {},
+            }
+        })
+    }
+
+    /// Parses `text` and writes it into the cell at the given row and column,
+    /// as if it had been typed into the editor of the cell, returning the
+    /// message of the parser if the text is invalid. The result holds the
+    /// edit callbacks to run.
+    pub fn set_cell_text(&mut self, row: usize, key: &str, text: &str) -> Result<EventResult, String> {
+        if self.read_only || self.parser.is_none() {
+            return Err("This view cannot be edited".to_string());
+        }
+        if self.is_cell_read_only(row, key) {
+            return Err("This cell cannot be edited".to_string());
+        }
+
+        self.write_cell_text(row, key, text)
+    }
+
+    /// Sets a callback to be used before cells are pasted into this view. It
+    /// may change the cells and where they go, let the paste add records, or
+    /// cancel the paste by returning `false`.
+    pub fn set_on_paste<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Paste) -> bool + 'static,
+    {
+        self.on_paste = Some(Rc::new(cb));
+    }
+
+    /// Sets a callback to be used after cells are pasted into this view with
+    /// `paste_text`, with the range of the cells written, e.g. to save them at
+    /// once rather than cell by cell. If the paste re-sorts the records, the
+    /// range spans the rows the pasted records were moved to.
+    pub fn set_on_batch_edit<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, &CellRange) + 'static,
+    {
+        self.on_batch_edit = Some(Rc::new(cb));
+    }
+
+    /// Pastes tab-separated text, one row of cells per line, as copied from
+    /// desktop spreadsheets, into the cells starting at the cursor. Cells going
+    /// past the last column are dropped, unless the paste callback sets
+    /// `append_columns`, and so are cells going into read-only cells.
+    ///
+    /// Either every cell is written, as a single batch update, or none is: if
+    /// any text is invalid, returns the message of the parser and leaves the
+    /// view as it was. The result holds the edit callbacks to run, followed by
+    /// the batch edit callback.
+    pub fn paste_text(&mut self, text: &str) -> Result<EventResult, String> {
+        if self.read_only || self.parser.is_none() {
+            return Err("This view cannot be edited".to_string());
+        }
+
+        let (column, row) = self.cursor_pos.ok_or_else(|| "There is no cursor to paste at".to_string())?;
+        let mut paste = Paste { row, column, cells: split_tsv(text), append_rows: false, append_columns: false };
+        if let Some(cb) = self.on_paste.clone() {
+            if !cb(&mut paste) {
+                return Ok(EventResult::Consumed(None));
+            }
+        }
+
+        let mut keys: Vec<String> = self.columns.keys().skip(paste.column).cloned().collect();
+        let mut new_keys = Vec::new();
+        if paste.append_columns {
+            let width = paste.cells.iter().map(Vec::len).max().unwrap_or(0);
+            while keys.len() < width {
+                let key = self.free_column_key(&column_name(paste.column + keys.len()));
+                new_keys.push(key.clone());
+                keys.push(key);
+            }
+        }
+
+        let mut writes = Vec::new();
+        for (y, cells) in (paste.row..).zip(&paste.cells) {
+            if y >= self.records.len() && !paste.append_rows {
+                break;
+            }
+
+            for (key, text) in keys.iter().zip(cells) {
+                // Read-only cells keep their contents.
+                if self.is_cell_read_only(y, key) {
+                    continue;
+                }
+
+                let title = self.columns.get(key).map_or(key.as_str(), |def| def.title.as_str());
+                self.check_cell_text(key, text)
+                    .map_err(|err| format!("Row {}, column {}: {}", y + 1, display_text(title), err))?;
+                writes.push((y, key.clone(), text));
+            }
+        }
+
+        let rows = writes.last().map_or(0, |&(y, _, _)| y + 1);
+        let columns = writes.iter().map(|(_, key, _)| keys.iter().position(|k| k == key).unwrap_or(0) + 1).max();
+        let (result, seqs) = self.update(|view| {
+            for key in new_keys {
+                let def = ColumnDef::new(&key);
+                view.push_column(key.clone(), def);
+                view.record_change(|_| Change::ColumnInserted { key });
+            }
+            while view.records.len() < rows {
+                view.push_record(Record::new());
+            }
+
+            let result = writes.into_iter().fold(EventResult::Consumed(None), |result, (y, key, text)| {
+                match view.write_cell_text(y, &key, text) {
+                    Ok(written) => result.and(written),
+                    Err(_) => result,
+                }
+            });
+            let seqs: Vec<u64> = view.row_meta.get(paste.row..rows).unwrap_or(&[]).iter().map(|meta| meta.seq).collect();
+            (result, seqs)
+        });
+
+        // The batch re-sorts the records, so the pasted rows are found by
+        // where they ended up rather than where they were pasted.
+        let moved = self.row_meta.iter().enumerate().filter(|(_, meta)| seqs.contains(&meta.seq)).map(|(y, _)| y);
+        let (top, bottom) = moved.fold((usize::MAX, 0), |(top, bottom), y| (top.min(y), bottom.max(y)));
+        match (self.on_batch_edit.clone(), columns) {
+            (Some(cb), Some(columns)) if top <= bottom => {
+                let range = CellRange::new((paste.column, top), (paste.column + columns - 1, bottom));
+                Ok(result.and(EventResult::with_cb(move |s| cb(s, &range))))
+            },
+            _ => Ok(result),
+        }
+    }
+
+    /// Returns `key`, or `key` followed by a number if there already is a
+    /// column with that key.
+    fn free_column_key(&self, key: &str) -> String {
+        let mut candidate = key.to_string();
+        let mut n = 1;
+        while self.columns.contains_key(&candidate) {
+            n += 1;
+            candidate = format!("{}{}", key, n);
+        }
+        candidate
+    }
+
+    /// Checks that `text` can be written into a cell of the column with the
+    /// given key, returning the message of the parser or the validator if not.
+    fn check_cell_text(&self, key: &str, text: &str) -> Result<(), String> {
+        if self.formulas_enabled && text.trim_start().starts_with('=') {
+            return formula::parse(text).map(|_| ());
+        }
+
+        match self.parser.as_ref() {
+            Some(parser) => self.validate_value(key, &parser(text)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns copies of the selected cells as a new set of records, along
+    /// with the keys of the columns they are in, in the order of this view.
+    /// Only rows and columns with a selected cell are included, and each
+    /// record only holds the values of its selected cells. This allows e.g.
+    /// opening just the selection in a new view, or exporting it.
+    pub fn selection_to_records(&self) -> (Vec<String>, Vec<Record<D>>) {
+        let mut xs: Vec<usize> = self.selected_cells.iter().map(|&(x, _)| x).collect();
+        let mut ys: Vec<usize> = self.selected_cells.iter().map(|&(_, y)| y).collect();
+        xs.sort_unstable();
+        xs.dedup();
+        ys.sort_unstable();
+        ys.dedup();
+
+        let keys: Vec<(usize, &String)> = xs.into_iter()
+            .filter_map(|x| self.columns.get_index(x).map(|(key, _)| (x, key)))
+            .collect();
+
+        let records = ys.into_iter()
+            .filter_map(|y| self.records.get(y).map(|record| (y, record)))
+            .map(|(y, record)| {
+                keys.iter()
+                    .filter(|&&(x, _)| self.selected_cells.contains(&(x, y)))
+                    .filter_map(|&(_, key)| record.get(key).map(|value| (key.clone(), value.clone())))
+                    .collect()
+            })
+            .collect();
+
+        (keys.into_iter().map(|(_, key)| key.clone()).collect(), records)
+    }
+
+    /// Performs an action as if the key bound to it had been pressed.
+    pub fn perform(&mut self, action: SpreadsheetAction) -> EventResult {
+        let expands_group = matches!(action, SpreadsheetAction::EditCell | SpreadsheetAction::ToggleSelection);
+        if expands_group && self.cursor_on_collapsed_group() {
+            return self.toggle_cursor_group();
+        }
+
+        match action {
+            SpreadsheetAction::MoveUp => self.move_cursor(|(x, y)| (x, y.saturating_sub(1))),
+            SpreadsheetAction::MoveDown => self.move_cursor(|(x, y)| (x, y + 1)),
+            SpreadsheetAction::MoveLeft => self.move_cursor(|(x, y)| (x.saturating_sub(1), y)),
+            SpreadsheetAction::MoveRight => self.move_cursor(|(x, y)| (x + 1, y)),
+            SpreadsheetAction::PageUp => {
+                let page = self.page_rows(true);
+                self.move_cursor(|(x, y)| (x, y.saturating_sub(page)))
+            },
+            SpreadsheetAction::PageDown => {
+                let page = self.page_rows(false);
+                self.move_cursor(|(x, y)| (x, y.saturating_add(page)))
+            },
+            SpreadsheetAction::PageLeft => {
+                let page = self.visible_scrolled_columns();
+                self.move_cursor(|(x, y)| (x.saturating_sub(page), y))
+            },
+            SpreadsheetAction::PageRight => {
+                let page = self.visible_scrolled_columns();
+                self.move_cursor(|(x, y)| (x.saturating_add(page), y))
+            },
+            SpreadsheetAction::RowStart => self.move_cursor(|(_, y)| (0, y)),
+            SpreadsheetAction::RowEnd => self.move_cursor(|(_, y)| (usize::MAX, y)),
+            SpreadsheetAction::FirstCell => self.move_cursor(|_| (0, 0)),
+            SpreadsheetAction::LastCell => self.move_cursor(|_| (usize::MAX, usize::MAX)),
+            SpreadsheetAction::ExtendUp => self.extend_selection(|(x, y)| (x, y.saturating_sub(1))),
+            SpreadsheetAction::ExtendDown => self.extend_selection(|(x, y)| (x, y + 1)),
+            SpreadsheetAction::ExtendLeft => self.extend_selection(|(x, y)| (x.saturating_sub(1), y)),
+            SpreadsheetAction::ExtendRight => self.extend_selection(|(x, y)| (x + 1, y)),
+            SpreadsheetAction::ExtendToFirstCell => self.extend_selection(|_| (0, 0)),
+            SpreadsheetAction::ExtendToLastCell => self.extend_selection(|_| (usize::MAX, usize::MAX)),
+            SpreadsheetAction::EditCell => {
+                let result = self.open_editor();
+                result
+                    .or_else(|| if self.can_edit() { EventResult::Ignored } else { self.submit_cursor_cell() })
+                    .or_else(|| self.locked_edit_attempt())
+            },
+            SpreadsheetAction::EditCellInDialog => {
+                let result = self.open_edit_dialog();
+                result.or_else(|| self.locked_edit_attempt())
+            },
+            SpreadsheetAction::EditCellExternally => {
+                let result = self.edit_cell_externally();
+                result.or_else(|| self.locked_edit_attempt())
+            },
+            SpreadsheetAction::NextError => {
+                if self.jump_to_next_error() { EventResult::Consumed(None) }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::MoveColumnLeft => self.move_cursor_column(false),
+            SpreadsheetAction::MoveColumnRight => self.move_cursor_column(true),
+            SpreadsheetAction::NarrowColumn => self.resize_cursor_column(false),
+            SpreadsheetAction::WidenColumn => self.resize_cursor_column(true),
+            SpreadsheetAction::Search => self.open_search_prompt(),
+            SpreadsheetAction::SearchNext => {
+                if self.search_next() { EventResult::Consumed(None) }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::SearchPrevious => {
+                if self.search_previous() { EventResult::Consumed(None) }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::Copy => {
+                if self.copy_selection() { EventResult::Consumed(None) }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::Cut => {
+                if self.cut_selection() { EventResult::Consumed(None) }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::Paste => {
+                if self.paste_clipboard() { EventResult::Consumed(None) }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::Undo => {
+                if !self.read_only && self.undo() { EventResult::Consumed(None) }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::Redo => {
+                if !self.read_only && self.redo() { EventResult::Consumed(None) }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::ToggleSelection => {
+                if self.toggle_cursor_selection() { self.selection_changed() }
+                else { EventResult::Ignored }
+            },
+            SpreadsheetAction::CycleSelectionMode => self.cycle_selection_mode(),
+            SpreadsheetAction::ToggleColumnSelect => self.toggle_column_select(),
+            SpreadsheetAction::ToggleReadOnly => self.toggle_read_only(),
+            SpreadsheetAction::SortAscending => self.sort_by_cursor_column(true),
+            SpreadsheetAction::SortDescending => self.sort_by_cursor_column(false),
+            SpreadsheetAction::ToggleSort => match self.cursor_pos {
+                Some((x, _)) => self.toggle_sort(x),
+                None => EventResult::Ignored,
+            },
+            SpreadsheetAction::AddSortKey => match self.cursor_pos {
+                Some((x, _)) => self.add_sort_key(x),
+                None => EventResult::Ignored,
+            },
+            SpreadsheetAction::ToggleGroup => self.toggle_cursor_group(),
+            SpreadsheetAction::ShowHelp => self.help_popup(),
+        }
+    }
+
+    /// Sorts the records by the column of the cursor.
+    fn sort_by_cursor_column(&mut self, ascending: bool) -> EventResult {
+        match self.cursor_pos {
+            Some((x, _)) => self.sort_by_column(x, ascending),
+            None => EventResult::Ignored,
+        }
+    }
+
+    /// Returns the `(column, row)` position of the cell drawn at the given
+    /// position relative to the top left corner of this view, if any.
+    fn cell_at(&self, position: Vec2) -> Option<(usize, usize)> {
+        let y = position.y.checked_sub(HEADER_HEIGHT).filter(|&y| y < self.scroll_base.view_height)?;
+        let line = self.scroll_base.start_line + y;
+        let (row, _) = self.line_to_row(line).filter(|&(row, _)| row < self.num_records())?;
+        Some((self.column_at(position.x)?, row))
+    }
+
+    /// Returns the expanded group whose header is drawn at the given position
+    /// relative to the top left corner of this view, if any.
+    fn group_header_at(&self, position: Vec2) -> Option<&Group> {
+        let y = position.y.checked_sub(HEADER_HEIGHT).filter(|&y| y < self.scroll_base.view_height)?;
+        self.group_header_at_line(self.scroll_base.start_line + y)
+    }
+
+    /// Handles a mouse event at the given position, relative to the top left
+    /// corner of this view. Clicking a cell moves the cursor to it, dragging
+    /// selects the cells between, double-clicking a cell does what the keys
+    /// bound to editing do, clicking a header sorts by its column, clicking
+    /// the header of a group collapses it,
+    /// dragging the separator between two headers resizes the column to its
+    /// left, and the wheel scrolls.
+    fn on_mouse_event(&mut self, event: MouseEvent, position: Vec2) -> EventResult {
+        match event {
+            MouseEvent::Press(MouseButton::Left) if position.y == 0 => match self.column_at(position.x) {
+                Some(x) => self.toggle_sort(x),
+                None => match self.separator_at(position.x) {
+                    Some(resizing) => {
+                        self.resizing_column = Some(resizing);
+                        EventResult::Consumed(None)
+                    },
+                    None => EventResult::Ignored,
+                },
+            },
+            MouseEvent::Hold(MouseButton::Left) if self.resizing_column.is_some() => {
+                if let Some((x, start)) = self.resizing_column {
+                    let key = self.columns.get_index(x).map(|(key, _)| key.clone()).unwrap_or_default();
+                    self.resize_column(&key, position.x.saturating_sub(start));
+                }
+                EventResult::Consumed(None)
+            },
+            MouseEvent::Release(MouseButton::Left) if self.resizing_column.is_some() => {
+                self.resizing_column = None;
+                EventResult::Consumed(None)
+            },
+            MouseEvent::Press(MouseButton::Left) if self.group_header_at(position).is_some() => {
+                let label = self.group_header_at(position).map(|group| group.label.clone()).unwrap_or_default();
+                self.toggle_group(&label)
+            },
+            MouseEvent::Press(MouseButton::Left) => match self.cell_at(position) {
+                Some(cell) => {
+                    let now = Instant::now();
+                    let last_click = self.last_click.replace((now, cell));
+                    if last_click.is_some_and(|(at, last)| last == cell && now - at <= DOUBLE_CLICK_INTERVAL) {
+                        self.last_click = None;
+                        return self.perform(SpreadsheetAction::EditCell).or_else(|| EventResult::Consumed(None));
+                    }
+                    self.move_cursor_to(cell.0, cell.1).or_else(|| EventResult::Consumed(None))
+                },
+                None => EventResult::Ignored,
+            },
+            MouseEvent::Hold(MouseButton::Left) => match self.cell_at(position) {
+                Some(cell) => self.extend_selection(|_| cell).or_else(|| EventResult::Consumed(None)),
+                None => EventResult::Consumed(None),
+            },
+            MouseEvent::WheelUp if self.scroll_base.can_scroll_up() => {
+                self.scroll_base.scroll_up(WHEEL_SCROLL_LINES);
+                EventResult::Consumed(None)
+            },
+            MouseEvent::WheelDown if self.scroll_base.can_scroll_down() => {
+                self.scroll_base.scroll_down(WHEEL_SCROLL_LINES);
+                EventResult::Consumed(None)
+            },
+            _ => EventResult::Ignored,
+        }
+    }
+
+    /// Returns the index of the column drawn at the given offset from the left
+    /// edge of this view, if any. Separators belong to no column.
+    fn column_at(&self, offset: usize) -> Option<usize> {
+        let mut x = self.gutter_offset();
+        for (n, i) in self.drawn_columns().enumerate() {
+            let width = self.column_widths[i];
+            if n > 0 {
+                x += COLUMN_SEPARATOR.width();
+            }
+            if offset < x {
+                return None;
+            }
+            if offset < x + width {
+                return Some(i);
+            }
+            x += width;
+        }
+        None
+    }
+
+    /// Returns the offset from the left edge of this view of the column at the
+    /// given index, if it is drawn.
+    fn column_left(&self, column: usize) -> Option<usize> {
+        let mut x = self.gutter_offset();
+        for (n, i) in self.drawn_columns().enumerate() {
+            if n > 0 {
+                x += COLUMN_SEPARATOR.width();
+            }
+            if i == column {
+                return Some(x);
+            }
+            x += self.column_widths[i];
+        }
+        None
+    }
+
+    /// Draws the message of an inline edit whose text was rejected, on the
+    /// line below the cell being edited, or above it at the bottom of the body.
+    fn draw_edit_error(&self, printer: &Printer, edit: &ActiveEdit) {
+        let message = match edit.error.as_ref() {
+            Some(message) => format!(" {} ", display_text(message)),
+            None => return,
+        };
+        let x = match self.columns.get_index_of(&edit.key).and_then(|x| self.column_left(x)) {
+            Some(x) => x,
+            None => return,
+        };
+
+        let line = self.row_to_line(edit.row) + self.text_line_offset();
+        let y = match line.checked_sub(self.scroll_base.start_line) {
+            Some(y) if y + 1 < printer.size.y => y + 1,
+            Some(y) if y > 0 => y - 1,
+            _ => return,
+        };
+
+        let x = x.min(printer.size.x.saturating_sub(message.width()));
+        printer.with_style(self.error_marker_style().combine(Effect::Reverse), |p| p.print((x, y), &message));
+    }
+
+    /// Returns the index of the column drawn to the left of the separator at
+    /// the given offset from the left edge of this view, and the offset of the
+    /// left edge of that column, if there is a separator there.
+    fn separator_at(&self, offset: usize) -> Option<(usize, usize)> {
+        let mut x = self.gutter_offset();
+        let mut previous = None;
+        for i in self.drawn_columns() {
+            if let Some((previous, start)) = previous {
+                if offset < x + COLUMN_SEPARATOR.width() {
+                    return Some((previous, start)).filter(|_| offset >= x);
+                }
+                x += COLUMN_SEPARATOR.width();
+            }
+            previous = Some((i, x));
+            x += self.column_widths[i];
+        }
+        None
+    }
+
+    /// Lays out and draws this view at the given size into an in-memory
+    /// buffer, and returns the result as plain text, one line per row of the
+    /// screen with trailing whitespace removed. Colors and effects are not
+    /// kept. This allows snapshot testing without a terminal.
+    pub fn render_to_string(&mut self, size: Vec2) -> String {
+        render::render_to_string(self, size)
+    }
+
+    /// Returns what this view currently shows on screen as plain text, with
+    /// the visible records and columns cut off and truncated just as they
+    /// were last drawn, e.g. for logs, bug reports, or "copy screen" commands.
+    /// Unlike `render_to_string`, this does not lay the view out again.
+    pub fn viewport_to_string(&self) -> String {
+        render::draw_to_string(self, self.last_size, self.last_focused.get())
+    }
+
+    /// Sends an event to this view as cursive would, first laying it out at
+    /// its last size so that behaviors depending on the layout (like paging)
+    /// are deterministic. Any callback in the result can be run with
+    /// `EventResult::process` on e.g. `Cursive::dummy()`.
+    ///
+    /// This is meant for testing how the view reacts to input, together with
+    /// `cursor_pos`, `selected_cells`, and `scroll_offset`.
+    pub fn apply_event(&mut self, event: Event) -> EventResult {
+        self.layout(self.last_size);
+        self.on_event(event)
+    }
+}
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Parses `text` and writes it into the cell at the given row and column,
+    /// returning the message of the parser if the text is invalid.
+    ///
+    /// If formulas are enabled, text starting with `=` is put into the cell as
+    /// a formula instead, which is only reported to the data-changed callback.
+    fn write_cell_text(&mut self, row: usize, key: &str, text: &str) -> Result<EventResult, String> {
+        if self.undo_history.is_none() {
+            return self.apply_cell_text(row, key, text);
+        }
+
+        let old = self.cell_contents(row, key);
+        let result = self.apply_cell_text(row, key, text)?;
+        self.record_cell_change(row, key, old);
+        Ok(result)
+    }
+
+    fn apply_cell_text(&mut self, row: usize, key: &str, text: &str) -> Result<EventResult, String> {
+        if self.formulas_enabled && text.trim_start().starts_with('=') {
+            self.set_formula(row, key, text)?;
+            return Ok(EventResult::Consumed(None));
+        }
+
+        let value = match self.parser.as_ref() {
+            Some(parser) => parser(text)?,
+            None => return Ok(EventResult::Consumed(None)),
+        };
+        self.validate_value(key, &value)?;
+
+        let old = match self.records.get_mut(row) {
+            Some(record) => record.insert(key.to_string(), value.clone()),
+            None => return Ok(EventResult::Consumed(None)),
+        };
+        self.formulas.remove(&(row, key.to_string()));
+        self.invalidate_cell(row, key);
+        self.refilter();
+
+        self.forget_sort_by(key);
+
+        if let Some((x, _, _)) = self.columns.get_full(key) {
+            self.announce_cell(row, x);
+        }
+        self.notify(|| ChangeEvent::CellChanged { row, key: key.to_string() });
+
+        let key = key.to_string();
+        let mut result = match self.on_edit.clone() {
+            Some(cb) => {
+                let (key, old, value) = (key.clone(), old.clone(), value.clone());
+                EventResult::with_cb(move |s| cb(s, row, &key, old.as_ref(), &value))
+            },
+            None => EventResult::Consumed(None),
+        };
+
+        if self.track_history || self.on_audit.is_some() {
+            let change = CellChange { old, new: value, time: SystemTime::now() };
+
+            if let Some(cb) = self.on_audit.clone() {
+                let (key, change) = (key.clone(), change.clone());
+                result = result.and(EventResult::with_cb(move |s| cb(s, row, &key, &change)));
+            }
+
+            if self.track_history {
+                self.history.entry((row, key)).or_default().push(change);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Parses `text` and writes it into the cell being edited, closing the
+    /// editor. If parsing or validation fails, the editor stays open and shows
+    /// the error.
+    fn commit_edit(&mut self, text: &str) -> EventResult {
+        let (row, key) = match self.active_edit.as_ref() {
+            Some(edit) => (edit.row, edit.key.clone()),
+            None => return EventResult::Consumed(None),
+        };
+
+        match self.write_cell_text(row, &key, text) {
+            Ok(result) => {
+                self.active_edit = None;
+                result
+            },
+            Err(message) => {
+                if let Some(edit) = self.active_edit.as_mut() {
+                    edit.editor.set_error(&message);
+                    edit.error = Some(message).filter(|_| edit.editor.is_inline());
+                }
+                EventResult::Consumed(None)
+            },
+        }
+    }
+
+    /// Edits the cell under the cursor in an external program (by default the
+    /// user's `$EDITOR`), blocking until it exits, then loads the result back
+    /// into the cell. If the result cannot be parsed, it is opened in the edit
+    /// dialog along with the error so that it is not lost.
+    pub fn edit_cell_externally(&mut self) -> EventResult {
+        if !self.can_edit() {
+            return EventResult::Ignored;
+        }
+
+        let (row, key, text) = match (self.cursor_pos, self.cursor_text()) {
+            (Some((_, y)), Some((key, text))) => (y, key.to_string(), text),
+            _ => return EventResult::Ignored,
+        };
+
+        // The external program has drawn all over the terminal.
+        let redraw = EventResult::with_cb(|s| s.clear());
+
+        let edited = match (self.external_editor)(&text) {
+            Ok(edited) => edited,
+            Err(err) => {
+                let message = format!("Could not run the external editor: {}", err);
+                return redraw.and(EventResult::with_cb(move |s| s.add_layer(Dialog::info(message.clone()))));
+            },
+        };
+
+        match self.write_cell_text(row, &key, &edited) {
+            Ok(result) => redraw.and(result),
+            Err(message) => {
+                let mut editor = self.dialog_editor(row, &key, edited);
+                editor.set_error(&message);
+                redraw.and(self.begin_edit(ActiveEdit { row, key, editor, error: None }))
+            },
+        }
+    }
+
+    fn on_edit_event(&mut self, event: Event) -> EventResult {
+        // Committing with Tab moves on to the next cell, as in other spreadsheets.
+        let tab = event == Event::Key(Key::Tab);
+        let outcome = match self.active_edit.as_mut() {
+            Some(edit) => {
+                edit.error = None;
+                // Dialogs are drawn in the body, below the header.
+                let event = if edit.editor.is_inline() { event } else { event.relativized((0, HEADER_HEIGHT)) };
+                edit.editor.on_event(event)
+            },
+            None => return EventResult::Ignored,
+        };
+
+        match outcome {
+            EditOutcome::Pending(result) => result,
+            EditOutcome::Commit(text) => {
+                let result = self.commit_edit(&text);
+                if tab && !self.is_editing() {
+                    result.and(self.move_cursor(|(x, y)| (x + 1, y)))
+                }
+                else {
+                    result
+                }
+            },
+            EditOutcome::Cancel => self.cancel_edit(),
+        }
+    }
+
+    /// Recalculates the width of each column from its title, its contents,
+    /// and its width bounds.
+    fn compute_column_widths(&mut self) {
+        let measured_rows = self.measured_rows();
+        let is_measured = |row: &usize| measured_rows.as_ref().is_none_or(|rows| rows.contains(row));
+
+        let mut formula_widths: HashMap<String, usize> = HashMap::new();
+        for (row, key) in self.formulas.keys().filter(|(row, _)| is_measured(row)) {
+            let width = widest_line(&self.cell_text(*row, key));
+            let widest = formula_widths.entry(key.clone()).or_default();
+            *widest = width.max(*widest);
+        }
+
+        // The sort glyph and the space before it widen the sorted columns.
+        let glyph_widths: HashMap<String, usize> = self.sort_spec.iter()
+            .filter_map(|(key, _)| Some((key.clone(), self.sort_glyph(key)?.width() + 1)))
+            .collect();
+
+        let records = &self.records;
+        let formatters = &self.formatters;
+        // Only the records of a source in view are measured, as it may have
+        // too many to go through.
+        let source_rows = self.source_rows_in_view();
+        let source = self.source.as_ref();
+
+        self.column_widths = self.columns.iter_mut()
+            .map(|(key, def)| {
+                if def.hidden {
+                    return 0;
+                }
+
+                let (min_width, max_width) = def.width.bounds();
+                let glyph_width = glyph_widths.get(key).copied().unwrap_or(0);
+                let title_width = display_width(&def.title) + glyph_width;
+
+                let formatter = formatters.get(key);
+                let multiline = def.multiline;
+                let value_width = |value: &D| match formatter {
+                    Some(formatter) if multiline => widest_line(&escape_lines(&formatter(value))),
+                    Some(formatter) => escape_controls(&formatter(value)).width(),
+                    None if multiline => widest_line(&escape_lines(&value.to_string())),
+                    None => display_width(value),
+                };
+
+                let content_width = records.iter()
+                    .enumerate()
+                    .filter(|(row, _)| is_measured(row))
+                    .filter_map(|(_, r)| r.get(key))
+                    .map(value_width)
+                    .chain(source.into_iter().flat_map(|source| {
+                        source_rows.clone().filter_map(move |row| source.get(row, key)).map(|value| value_width(&value))
+                    }))
+                    .chain(formula_widths.get(key).copied())
+                    .fold(title_width, usize::max);
+
+                let mut width = content_width.max(min_width);
+                if let Some(resized_width) = def.resized_width {
+                    width = resized_width;
+                }
+                else if def.grow_only {
+                    width = width.max(def.grown_width);
+                    def.grown_width = width;
+                }
+
+                match max_width {
+                    Some(max_width) => width.min(max_width),
+                    None => width,
+                }
+            })
+            .collect();
+
+        self.compute_gutter_width();
+        self.compute_record_heights();
+    }
+
+    /// Returns the total width taken up by the gutter, the columns, and their
+    /// separators.
+    fn content_width(&self) -> usize {
+        let shown = (0..self.column_widths.len()).filter(|&x| self.is_column_shown(x)).count();
+        let separators = shown.saturating_sub(1) * COLUMN_SEPARATOR.width();
+        self.gutter_offset() + self.column_widths.iter().sum::<usize>() + separators
+    }
+
+    fn draw_header(&self, printer: &Printer) {
+        let indicator = self.focus_indicator.filter(|_| printer.focused);
+
+        let mut title_style = if self.monochrome { Style::from(Effect::Bold) } else { self.style.header };
+        if indicator == Some(FocusIndicator::Header) {
+            title_style = title_style.combine(
+                if self.monochrome { Style::from(Effect::Reverse) }
+                else { Style::from(ColorStyle::highlight()) }
+            );
+        }
+
+        self.draw_gutter_header(printer);
+        let mut x = self.gutter_offset();
+        for (n, i) in self.drawn_columns().enumerate() {
+            let ((key, def), width) = (self.columns.get_index(i).unwrap(), self.column_widths[i]);
+            if n > 0 {
+                self.print_grid(printer, x, self.separator_before(i));
+                x += COLUMN_SEPARATOR.width();
+            }
+
+            let title = self.header_text(key, def, width);
+            let style = if self.column_select && i == self.highlighted_column {
+                if self.monochrome { title_style.combine(Effect::Reverse) }
+                else { title_style.combine(ColorStyle::highlight()) }
+            }
+            else { title_style };
+            printer.offset((x, 0)).with_style(style, |p| print_aligned(p, &title, width, &def.alignment));
+            x += width;
+        }
+
+        match indicator {
+            Some(FocusIndicator::Delimiter) => self.draw_grid_rule(printer, 1, true),
+            _ if self.grid_mode.has_header_line() => self.draw_grid_rule(printer, 1, false),
+            _ => {},
+        }
+        if indicator == Some(FocusIndicator::Corner) {
+            printer.print((0, 1), if self.monochrome || self.ascii_grid { FOCUS_MARKER_ASCII } else { FOCUS_MARKER });
+        }
+
+        if let Some(marker) = self.read_only_indicator.as_ref().filter(|_| self.read_only) {
+            let marker = format!(" {} ", display_text(marker));
+            let x = printer.size.x.saturating_sub(marker.width() + 1);
+            let style = if self.monochrome { Style::none() } else { ColorStyle::secondary().into() };
+            printer.with_style(style, |p| p.print((x, 1), &marker));
+        }
+    }
+
+    /// Draws one line of the body. Each record takes up `row_height` lines,
+    /// with its values on the middle one, or more if its values wrap.
+    fn draw_line(&self, printer: &Printer, line: usize) {
+        if let Some(group) = self.pinned_group_footer(line) {
+            self.draw_group_footer(printer, group);
+            return;
+        }
+
+        let (y, offset) = match self.line_to_row(line) {
+            Some(position) => position,
+            None => {
+                if let Some(group) = self.group_footer_at_line(line) {
+                    self.draw_group_footer(printer, group);
+                }
+                else if let Some(group) = self.group_header_at_line(line) {
+                    self.draw_group_header(printer, group, false);
+                }
+                else {
+                    self.draw_grid_rule(printer, 0, false);
+                }
+                return;
+            },
+        };
+
+        if let Some(group) = self.collapsed_group_of_row(y) {
+            if offset == self.text_line_offset() {
+                self.draw_group_header(printer, group, self.cursor_pos.is_some_and(|(_, row)| row == y));
+            }
+            else {
+                printer.print_hline((0, 0), printer.size.x, " ");
+            }
+            return;
+        }
+
+        let is_text_line = offset == self.text_line_offset();
+        let text_line = offset.checked_sub(self.text_line_offset());
+
+        let row_style = self.row_style(y);
+        let striped = self.is_striped(y);
+
+        let mut visible_cells = self.visible_cells.borrow_mut();
+        let cells = visible_cells.entry(y).or_default();
+        if cells.len() != self.columns.len() {
+            *cells = vec![None; self.columns.len()];
+        }
+
+        self.draw_gutter_line(printer, y, is_text_line);
+        let mut x = self.gutter_offset();
+        for (n, i) in self.drawn_columns().enumerate() {
+            let ((key, def), width) = (self.columns.get_index(i).unwrap(), self.column_widths[i]);
+            if n > 0 {
+                self.print_grid(printer, x, self.separator_before(i));
+                x += COLUMN_SEPARATOR.width();
+            }
+
+            let cell_printer = printer.offset((x, 0));
+            x += width;
+            let text = cells[i].get_or_insert_with(|| self.cell_text(y, key)).as_str();
+
+            let inline_edit = self.active_edit.as_ref()
+                .filter(|e| e.editor.is_inline() && e.row == y && &e.key == key);
+
+            // Colors from column, row, and cell styles take precedence over
+            // stripes, while the cursor, the selection, and search matches are
+            // drawn over all of them.
+            let content_style = self.content_style(y, key, def, row_style);
+            let (is_cursor, is_selected) = (self.is_cursor_cell(i, y), self.selected_cells.contains(&(i, y)));
+            let style = content_style.combine(self.cell_style(
+                printer.focused,
+                inline_edit.is_some(),
+                is_cursor,
+                is_selected,
+                self.is_search_match(text),
+                striped && content_style.color.is_none(),
+            ));
+
+            let draw_cell = |p: &Printer| {
+                if let Some(edit) = inline_edit.filter(|_| is_text_line) {
+                    edit.editor.draw(&p.cropped((width, 1)));
+                    return;
+                }
+
+                // Invalid cells give up their last column to the error marker.
+                let invalid = is_text_line && width > 0 && self.validate_cell(y, key).is_some();
+                let text_width = if invalid { width - ERROR_MARKER.width() } else { width };
+                let rendered = text_line == Some(0) && self.draw_rendered_cell(p, y, key, text_width, is_cursor || is_selected);
+                let text = text_line.filter(|_| !rendered).and_then(|line| display_lines(text, text_width, def.overflow).into_iter().nth(line));
+                match text {
+                    Some(text) => print_aligned(p, &text, text_width, &def.alignment),
+                    None if !rendered => p.print_hline((0, 0), width, " "),
+                    None => {},
+                }
+
+                if invalid {
+                    p.with_style(self.error_marker_style(), |p| p.print((text_width, 0), ERROR_MARKER));
+                }
+            };
+
+            cell_printer.with_style(style, draw_cell);
+        }
+    }
+}
+
+impl<D: Display + Ord + Clone + 'static> View for SpreadsheetView<D> {
+    fn draw(&self, printer: &Printer) {
+        self.last_focused.set(printer.focused);
+        self.draw_header(printer);
+
+        let printer = &printer.offset((0, HEADER_HEIGHT)).focused(printer.focused);
+        let mut footer_y = printer.size.y.saturating_sub(self.footer_height());
+        if self.has_column_aggregates() {
+            self.draw_aggregate_row(&printer.offset((0, footer_y)));
+            footer_y += 1;
+        }
+        if self.is_loading() {
+            self.draw_loading_footer(&printer.offset((0, footer_y)));
+            footer_y += 1;
+        }
+        if self.status_bar {
+            self.draw_status_bar(&printer.offset((0, footer_y)));
+        }
+
+        let printer = &printer.cropped((printer.size.x, printer.size.y.saturating_sub(self.footer_height())));
+        if self.horizontal_scrollbar_shown {
+            let view_height = self.scroll_base.view_height;
+            self.scroll_base.draw(&printer.cropped((printer.size.x, view_height)), |p, line| self.draw_line(p, line));
+            self.draw_horizontal_scrollbar(&printer.offset((0, view_height)));
+        }
+        else {
+            self.scroll_base.draw(printer, |p, line| self.draw_line(p, line));
+        }
+
+        // Forget the records scrolled out of view.
+        let lines = self.scroll_base.start_line..self.scroll_base.start_line + printer.size.y;
+        self.visible_cells.borrow_mut().retain(|&row, _| {
+            let line = self.row_to_line(row);
+            line < lines.end && line + self.record_height(row) > lines.start
+        });
+
+        if let Some(edit) = self.active_edit.as_ref().filter(|e| !e.editor.is_inline()) {
+            edit.editor.draw(printer);
+        }
+        if let Some(edit) = self.active_edit.as_ref() {
+            self.draw_edit_error(printer, edit);
+        }
+
+        if let Some(prompt) = self.search_prompt.as_ref() {
+            prompt.draw(printer);
+        }
+    }
+
+    fn layout(&mut self, size: Vec2) {
+        if self.row_ttl.is_some() {
+            self.prune_expired_records();
+        }
+        self.receive_loaded_records();
+
+        if size != self.last_size {
+            self.last_size = size;
+            if let Some(cb) = self.on_layout.clone() {
+                cb(size);
+            }
+        }
+
+        self.compute_column_widths();
+        self.first_column = self.first_column.min(self.columns.len().saturating_sub(1));
+        let body_height = size.y.saturating_sub(HEADER_HEIGHT + self.footer_height());
+        let content_height = self.content_lines();
+        self.scroll_base.set_heights(body_height, content_height);
+        if self.measure_visible_rows {
+            // The records in view are known now.
+            self.compute_column_widths();
+        }
+        self.fill_leftover_width();
+
+        // The horizontal scrollbar takes up the last line of the body.
+        self.horizontal_scrollbar_shown = self.horizontal_scrollbar && self.content_width() > self.body_width();
+        if self.horizontal_scrollbar_shown {
+            self.scroll_base.set_heights(body_height.saturating_sub(1), content_height);
+        }
+
+        self.report_column_widths();
+        self.sync_formula_bar();
+
+        if let Some(edit) = self.active_edit.as_mut() {
+            let available = match self.columns.get_full(&edit.key) {
+                Some((x, _, _)) if edit.editor.is_inline() => Vec2::new(self.column_widths[x], 1),
+                _ => size.saturating_sub((0, HEADER_HEIGHT)),
+            };
+            edit.editor.layout(available);
+        }
+
+        if let Some(prompt) = self.search_prompt.as_mut() {
+            prompt.layout(size);
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        self.compute_column_widths();
+        Vec2::new(self.content_width() + 2, self.content_lines() + HEADER_HEIGHT)
+    }
+
+    fn take_focus(&mut self, _: Direction) -> bool {
+        if self.enabled && self.cursor_pos.is_none() {
+            self.set_cursor_pos(0, 0);
+        }
+
+        self.enabled
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        // Changes made by the application since the last event are its own.
+        self.pending_changes.get_mut().clear();
+        let result = self.handle_event(event);
+        self.report_changes(result)
+    }
+}
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    fn handle_event(&mut self, event: Event) -> EventResult {
+        if !self.enabled {
+            return EventResult::Ignored;
+        }
+
+        if self.active_edit.is_some() {
+            return self.on_edit_event(event);
+        }
+
+        if self.search_prompt.is_some() {
+            return self.on_search_prompt_event(event);
+        }
+
+        if let Event::Mouse { offset, position, event } = event {
+            return match position.checked_sub(offset) {
+                Some(position) => self.on_mouse_event(event, position),
+                None => EventResult::Ignored,
+            };
+        }
+
+        if self.column_select {
+            if let Some(result) = self.on_column_select_event(&event) {
+                return result;
+            }
+        }
+
+        match (self.bindings.action(&event), event) {
+            (Some(action), _) => self.perform(action),
+            (None, Event::Char(c)) => self.type_over_cell(c),
+            (None, _) => EventResult::Ignored,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+
+    use cursive::event::Key;
+    use cursive::view::Nameable;
+
+    fn column(title: &str) -> ColumnDef {
+        ColumnDef::new(title)
+    }
+
+    fn editable_view() -> SpreadsheetView<String> {
+        let mut view = SpreadsheetView::new();
+        view.push_column("name".to_string(), column("Name"));
+        view.push_record(vec![("name".to_string(), "alpha".to_string())].into_iter().collect());
+        view.set_read_only(false);
+        view.set_from_str_parser();
+        view.layout(Vec2::new(80, 24));
+        view.set_cursor_pos(0, 0);
+        view
+    }
+
+    #[test]
+    fn edit_dialog_commits_on_ok() {
+        let mut view = editable_view();
+
+        view.on_event(Event::Key(Key::Enter));
+        assert!(view.is_editing());
+
+        view.on_event(Event::Char('!'));
+        // Move focus from the text area to the "OK" button and press it.
+        view.on_event(Event::Key(Key::Tab));
+        view.on_event(Event::Key(Key::Enter));
+
+        assert!(!view.is_editing());
+        assert_eq!(view.records[0]["name"], "!alpha");
+    }
+
+    #[test]
+    fn edit_dialog_buttons_take_clicks() {
+        let mut view = editable_view();
+        view.on_event(Event::Key(Key::Enter));
+        view.on_event(Event::Char('!'));
+
+        let text = view.render_to_string(Vec2::new(80, 24));
+        let (y, line) = text.lines().enumerate().find(|(_, line)| line.contains("<OK>")).unwrap();
+        let x = line.chars().position(|c| c == 'O').unwrap();
+        for event in [MouseEvent::Press(MouseButton::Left), MouseEvent::Release(MouseButton::Left)] {
+            view.on_event(Event::Mouse { offset: Vec2::zero(), position: Vec2::new(x, y), event });
+        }
+
+        assert!(!view.is_editing());
+        assert_eq!(view.records[0]["name"], "!alpha");
+    }
+
+    #[test]
+    fn user_changes_are_reported_and_tracked() {
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let mut view = editable_view();
+        assert!(view.is_dirty());
+        view.mark_clean();
+        view.set_on_change({
+            let changes = changes.clone();
+            move |_, change| changes.borrow_mut().push(change.clone())
+        });
+
+        let mut siv = Cursive::dummy();
+        view.set_cell(0, "name", "beta".to_string());
+        assert!(view.is_dirty());
+        view.on_event(Event::Char('x'));
+        view.on_event(Event::Key(Key::Enter)).process(&mut siv);
+        view.on_event(Event::AltChar('s')).process(&mut siv);
+        assert_eq!(*changes.borrow(), [
+            ChangeEvent::CellChanged { row: 0, key: "name".to_string() },
+            ChangeEvent::Sorted { key: "name".to_string(), ascending: true },
+        ]);
+
+        view.mark_clean();
+        assert!(!view.is_dirty());
+    }
+
+    #[test]
+    fn typing_edits_in_place() {
+        let edits = Rc::new(RefCell::new(Vec::new()));
+        let mut view = editable_view();
+        view.push_column("note".to_string(), column("Note"));
+        view.set_on_edit({
+            let edits = edits.clone();
+            move |_, row, key, old, new| {
+                edits.borrow_mut().push((row, key.to_string(), old.cloned(), new.clone()))
+            }
+        });
+
+        let mut siv = Cursive::dummy();
+        view.on_event(Event::Char('s'));
+        view.on_event(Event::Char('o'));
+        assert!(view.is_editing());
+        assert!(view.render_to_string(Vec2::new(14, 3)).ends_with("\nso___ │"));
+        view.on_event(Event::Key(Key::Tab)).process(&mut siv);
+        assert_eq!(view.cursor_pos, Some((1, 0)));
+
+        view.on_event(Event::Char('x'));
+        view.on_event(Event::Key(Key::Esc));
+        assert!(!view.is_editing());
+
+        view.set_edit_in_place(true);
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::Key(Key::Enter));
+        view.on_event(Event::Char('!'));
+        view.on_event(Event::Key(Key::Enter)).process(&mut siv);
+
+        assert_eq!(*edits.borrow(), [
+            (0, "name".to_string(), Some("alpha".to_string()), "so".to_string()),
+            (0, "name".to_string(), Some("so".to_string()), "so!".to_string()),
+        ]);
+        assert!(!view.records[0].contains_key("note"));
+    }
+
+    #[test]
+    fn edit_dialog_cancels_on_esc() {
+        let mut view = editable_view();
+
+        view.open_edit_dialog();
+        view.on_event(Event::Char('!'));
+        view.on_event(Event::Key(Key::Esc));
+
+        assert!(!view.is_editing());
+        assert_eq!(view.records[0]["name"], "alpha");
+    }
+
+    #[test]
+    fn spinner_column_edits_in_place() {
+        let mut view = editable_view();
+        view.push_column("qty".to_string(), column("Qty"));
+        view.records[0].insert("qty".to_string(), "4".to_string());
+        view.set_spinner("qty", Spinner::new(2.0).max(7.0));
+        view.set_cursor_pos(1, 0);
+
+        view.on_event(Event::Key(Key::Enter));
+        view.on_event(Event::Key(Key::Up));
+        view.on_event(Event::Key(Key::Up));
+        view.on_event(Event::Key(Key::Enter));
+
+        assert!(!view.is_editing());
+        assert_eq!(view.records[0]["qty"], "7");
+    }
+
+    #[test]
+    fn external_editor_loads_result() {
+        let mut view = editable_view();
+        view.set_external_editor(|text| Ok(format!("{} (edited)", text)));
+
+        assert!(view.on_event(Event::CtrlChar('e')).has_callback());
+        assert_eq!(view.records[0]["name"], "alpha (edited)");
+    }
+
+    #[test]
+    fn external_editor_failure_keeps_value() {
+        let mut view = editable_view();
+        view.set_external_editor(|_| Err(io::Error::new(io::ErrorKind::NotFound, "no editor")));
+        view.edit_cell_externally();
+        assert_eq!(view.records[0]["name"], "alpha");
+
+        // Text that does not parse ends up in the edit dialog instead.
+        view.set_parser(|_| Err("nope".to_string()));
+        view.set_external_editor(|_| Ok("bad".to_string()));
+        view.edit_cell_externally();
+        assert!(view.is_editing());
+        assert_eq!(view.records[0]["name"], "alpha");
+    }
+
+    #[test]
+    fn edit_lifecycle_callbacks() {
+        let mut view = editable_view();
+        view.set_on_edit_begin(|_, _, _| {});
+
+        assert!(view.open_editor().has_callback());
+        assert!(!view.cancel_edit().has_callback());
+        assert!(!view.cancel_edit().is_consumed());
+
+        view.set_on_edit_cancel(|_, _, _| {});
+        view.open_editor();
+        assert!(view.on_event(Event::Key(Key::Esc)).has_callback());
+        assert!(!view.is_editing());
+    }
+
+    #[test]
+    fn edit_dialog_needs_editable_view() {
+        let mut view = editable_view();
+        view.set_read_only(true);
+
+        view.open_edit_dialog();
+        assert!(!view.is_editing());
+    }
+
+    #[test]
+    fn validation_errors_and_jumping() {
+        let mut view = editable_view();
+        view.push_column("qty".to_string(), column("Qty"));
+        for qty in &["1", "x", "3", "y"] {
+            view.push_record(vec![("qty".to_string(), qty.to_string())].into_iter().collect());
+        }
+
+        view.set_validator("qty", |v: &String| {
+            v.parse::<u32>().map(|_| ()).map_err(|_| "not a number".to_string())
+        });
+
+        let errors = view.validation_errors();
+        assert_eq!(errors.iter().map(|e| e.row).collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(errors[0].key, "qty");
+        assert_eq!(errors[0].message, "not a number");
+
+        view.set_cursor_pos(0, 0);
+        assert!(view.jump_to_next_error());
+        assert_eq!(view.cursor_pos, Some((1, 2)));
+        view.on_event(Event::Key(Key::F8));
+        assert_eq!(view.cursor_pos, Some((1, 4)));
+        view.on_event(Event::Key(Key::F8));
+        assert_eq!(view.cursor_pos, Some((1, 2)));
+
+        view.clear_validator("qty");
+        assert!(view.validation_errors().is_empty());
+        assert!(!view.jump_to_next_error());
+    }
+
+    #[test]
+    fn validators_refuse_edits() {
+        let mut view = editable_view();
+        view.set_validator("name", |v: &String| {
+            if v.is_empty() { Err("no name".to_string()) } else { Ok(()) }
+        });
+
+        // Typing over the cell edits it in place.
+        view.on_event(Event::Char('x'));
+        view.on_event(Event::Key(Key::Backspace));
+        view.on_event(Event::Key(Key::Enter));
+        assert!(view.is_editing());
+        assert_eq!(view.get_cell(0, "name").map(String::as_str), Some("alpha"));
+        assert_eq!(view.render_to_string(Vec2::new(20, 6)).lines().nth(3), Some(" no name"));
+
+        view.on_event(Event::Char('b'));
+        assert!(!view.render_to_string(Vec2::new(20, 6)).contains("no name"));
+        view.on_event(Event::Key(Key::Enter));
+        assert!(!view.is_editing());
+        assert_eq!(view.get_cell(0, "name").map(String::as_str), Some("b"));
+
+        assert_eq!(view.set_cell_text(0, "name", "").err().as_deref(), Some("no name"));
+    }
+
+    #[test]
+    fn history_follows_records() {
+        let mut view = editable_view();
+        view.push_record(vec![("name".to_string(), "beta".to_string())].into_iter().collect());
+        view.set_track_history(true);
+
+        view.set_cursor_pos(0, 1);
+        view.set_external_editor(|_| Ok("aardvark".to_string()));
+        view.edit_cell_externally();
+        view.set_external_editor(|_| Ok("zebra".to_string()));
+        view.edit_cell_externally();
+
+        let changes = view.cell_history(1, "name");
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].old.as_deref(), Some("beta"));
+        assert_eq!(changes[1].new, "zebra");
+        assert!(view.cell_history(0, "name").is_empty());
+
+        view.sort_records("name", false);
+        assert_eq!(view.records[0]["name"], "zebra");
+        assert_eq!(view.cell_history(0, "name").len(), 2);
+
+        view.remove_record(1);
+        assert_eq!(view.cell_history(0, "name").len(), 2);
+        view.remove_record(0);
+        assert!(view.cell_history(0, "name").is_empty());
+    }
+
+    #[test]
+    fn find_and_binary_search_records() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("n".to_string(), column("N"));
+        view.extend_records([5, 1, 9, 3].iter().map(|&n| vec![("n".to_string(), n)].into_iter().collect()));
+
+        assert_eq!(view.find_record(|r| r["n"] == 9), Some(2));
+        assert_eq!(view.find_record(|r| r["n"] == 4), None);
+        assert_eq!(view.binary_search_by_key("n", &9), None);
+
+        view.sort_records("n", true);
+        assert_eq!(view.sorted_by(), Some(("n", true)));
+        assert_eq!(view.binary_search_by_key("n", &5), Some(Ok(2)));
+        assert_eq!(view.binary_search_by_key("n", &4), Some(Err(2)));
+
+        view.sort_records("n", false);
+        assert_eq!(view.binary_search_by_key("n", &9), Some(Ok(0)));
+        assert_eq!(view.binary_search_by_key("n", &0), Some(Err(4)));
+
+        view.push_record(vec![("n".to_string(), 7)].into_iter().collect());
+        assert_eq!(view.sorted_by(), None);
+    }
+
+    fn keyed_record(id: u32, value: u32) -> Record<u32> {
+        vec![("id".to_string(), id), ("value".to_string(), value)].into_iter().collect()
+    }
+
+    #[test]
+    fn upsert_replaces_or_adds() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("id".to_string(), column("ID"));
+        view.push_column("value".to_string(), column("Value"));
+        view.extend_records(vec![keyed_record(3, 30), keyed_record(1, 10)]);
+
+        // Without a key column, everything is appended.
+        assert!(view.upsert_record(keyed_record(3, 31)).is_none());
+        assert_eq!(view.len_records(), 3);
+        view.pop_record();
+
+        view.set_key_column(Some("id"));
+        assert_eq!(view.key_column(), Some("id"));
+        assert_eq!(view.upsert_record(keyed_record(3, 33)).unwrap()["value"], 30);
+        assert_eq!(view.records[0]["value"], 33);
+
+        view.sort_records("id", true);
+        assert!(view.upsert_record(keyed_record(2, 20)).is_none());
+        assert!(view.upsert_record(keyed_record(4, 40)).is_none());
+        let ids: Vec<_> = view.records.iter().map(|r| r["id"]).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(view.sorted_by(), Some(("id", true)));
+
+        // Replacing the value of a sorted column forgets the sort.
+        view.sort_records("value", true);
+        view.upsert_record(keyed_record(2, 20));
+        assert_eq!(view.sorted_by(), Some(("value", true)));
+        view.upsert_record(keyed_record(1, 99));
+        assert_eq!(view.sorted_by(), None);
+    }
+
+    #[test]
+    fn grow_only_columns_never_shrink() {
+        let mut view = editable_view();
+        view.push_column("notes".to_string(), column("Notes"));
+        view.records[0].insert("notes".to_string(), "a long note".to_string());
+        view.set_column_grow_only("notes", true);
+
+        view.layout(Vec2::new(80, 24));
+        assert_eq!(view.column_widths[1], 11);
+
+        view.records[0].insert("notes".to_string(), "short".to_string());
+        view.layout(Vec2::new(80, 24));
+        assert_eq!(view.column_widths[1], 11);
+
+        view.set_column_grow_only("notes", false);
+        view.layout(Vec2::new(80, 24));
+        assert_eq!(view.column_widths[1], 5);
+    }
+
+    #[test]
+    fn row_height_scales_scrolling() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("n".to_string(), column("N"));
+        view.extend_records((0..10).map(|n| vec![("n".to_string(), n)].into_iter().collect()));
+        view.set_row_height(3);
+
+        view.layout(Vec2::new(20, HEADER_HEIGHT + 6));
+        assert_eq!(view.scroll_base.content_height, 30);
+        assert_eq!(view.required_size(Vec2::new(20, 100)).y, HEADER_HEIGHT + 30);
+
+        view.scroll_to_row(4);
+        assert_eq!(view.scroll_base.start_line, 9);
+        assert_eq!(view.visible_range(), (3..5, 0..1));
+
+        view.set_row_height(0);
+        assert_eq!(view.row_height(), 1);
+    }
+
+    #[test]
+    fn announcements_describe_changes() {
+        let announced = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(3);
+        view.set_on_announce({
+            let announced = announced.clone();
+            move |message| announced.borrow_mut().push(message.to_string())
+        });
+
+        view.set_cursor_pos(1, 2);
+        view.set_cursor_pos(1, 2);
+        view.sort_records("n", false);
+        view.set_from_str_parser();
+        view.write_cell_text(0, "sq", "10").unwrap();
+
+        assert_eq!(*announced.borrow(), [
+            "row 3, column Square, value 9",
+            "sorted by column N, descending",
+            "row 1, column Square, value 10",
+        ]);
+    }
+
+    #[test]
+    fn data_changes_are_reported() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut view = SpreadsheetView::new();
+        view.push_column("id".to_string(), column("ID"));
+        view.push_column("value".to_string(), column("Value"));
+        view.set_key_column(Some("id"));
+        view.set_on_data_changed({
+            let events = events.clone();
+            move |event| events.borrow_mut().push(event)
+        });
+
+        view.extend_records(vec![keyed_record(1, 10), keyed_record(3, 30)]);
+        view.sort_records("id", true);
+        view.upsert_record(keyed_record(2, 20));
+        view.upsert_record(keyed_record(3, 31));
+        view.remove_record(0);
+        view.clear_records();
+
+        assert_eq!(*events.borrow(), [
+            ChangeEvent::RowsInserted { start: 0, count: 2 },
+            ChangeEvent::Sorted { key: "id".to_string(), ascending: true },
+            ChangeEvent::RowsInserted { start: 1, count: 1 },
+            ChangeEvent::CellChanged { row: 2, key: "value".to_string() },
+            ChangeEvent::RowsRemoved { start: 0, count: 1 },
+            ChangeEvent::RowsRemoved { start: 0, count: 2 },
+        ]);
+    }
+
+    #[test]
+    fn batches_report_once_and_keep_sort() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(2);
+        view.sort_records("n", false);
+        view.set_on_data_changed({
+            let events = events.clone();
+            move |event| events.borrow_mut().push(event)
+        });
+
+        view.update(|view| {
+            view.extend_records((3..=5).map(|n| vec![("n".to_string(), n)].into_iter().collect()));
+            view.update(|view| view.remove_record(0));
+            view.push_record(vec![("n".to_string(), 9)].into_iter().collect());
+        });
+
+        let values: Vec<_> = view.records.iter().map(|r| r["n"]).collect();
+        assert_eq!(values, [9, 5, 4, 3, 1]);
+        assert_eq!(view.sorted_by(), Some(("n", false)));
+        assert_eq!(*events.borrow(), [ChangeEvent::Reset]);
+
+        view.update(|_| ());
+        assert_eq!(events.borrow().len(), 1);
+    }
+
+    #[test]
+    fn replacing_columns_reconciles_state() {
+        let mut view = number_view(3);
+        view.push_column("cube".to_string(), column("Cube"));
+        view.sort_records("sq", true);
+        view.set_cursor_pos(2, 1);
+        view.selected_cells.extend(vec![(0, 0), (1, 2), (2, 2)]);
+
+        view.set_columns(vec![
+            ("cube".to_string(), column("Cube")),
+            ("sq".to_string(), column("Square")),
+        ]);
+
+        assert_eq!(view.cursor_pos(), Some((0, 1)));
+        assert_eq!(view.selected_cells(), &vec![(1, 2), (0, 2)].into_iter().collect());
+        assert_eq!(view.sorted_by(), Some(("sq", true)));
+
+        view.remove_column("sq");
+        assert_eq!(view.cursor_pos(), Some((0, 1)));
+        assert_eq!(view.sorted_by(), None);
+
+        view.clear_columns();
+        assert_eq!(view.cursor_pos(), None);
+        assert!(view.selected_cells().is_empty());
+        assert_eq!(view.len_records(), 3);
+    }
+
+    #[test]
+    fn set_records_follows_keys() {
+        let mut view = SpreadsheetView::new();
+        view.push_column("id".to_string(), column("ID"));
+        view.push_column("value".to_string(), column("Value"));
+        view.set_key_column(Some("id"));
+        view.extend_records((1..=20).map(|id| keyed_record(id, id)));
+        view.sort_records("id", true);
+        view.layout(Vec2::new(20, HEADER_HEIGHT + 5));
+
+        view.set_cursor_pos(1, 9);
+        view.scroll_base.start_line = 7;
+
+        // Drop the first five records, and shuffle the rest.
+        view.set_records((6..=25).rev().map(|id| keyed_record(id, id * 2)).collect());
+
+        assert_eq!(view.sorted_by(), Some(("id", true)));
+        assert_eq!(view.records[0]["id"], 6);
+        assert_eq!(view.cursor_pos(), Some((1, 4)));
+        assert_eq!(view.scroll_offset(), Vec2::new(0, 2));
+
+        view.reset_records(vec![keyed_record(1, 1)]);
+        assert_eq!(view.sorted_by(), None);
+        assert_eq!(view.cursor_pos(), Some((1, 0)));
+        assert_eq!(view.scroll_offset(), Vec2::new(0, 0));
+    }
+
+    #[test]
+    fn update_records_keeps_cursor_and_selection() {
+        let mut view = SpreadsheetView::new();
+        view.push_column("id".to_string(), column("ID"));
+        view.push_column("value".to_string(), column("Value"));
+        view.extend_records((1..=5).map(|id| keyed_record(id, id)));
+        view.sort_records("value", false);
+        view.set_cursor_pos(1, 2);
+        view.set_selection(CellRange::new((0, 1), (1, 2)));
+
+        // Record 4 is gone, and record 3 is now the greatest.
+        view.update_records(vec![keyed_record(1, 1), keyed_record(2, 2), keyed_record(3, 9), keyed_record(5, 5)], "id");
+        assert_eq!(view.key_column(), None);
+        assert_eq!(view.sorted_by(), Some(("value", false)));
+        let ids: Vec<u32> = view.records.iter().map(|record| record["id"]).collect();
+        assert_eq!(ids, [3, 5, 2, 1]);
+        assert_eq!(view.cursor_pos(), Some((1, 0)));
+        let mut selected: Vec<(usize, usize)> = view.selected_cells().iter().copied().collect();
+        selected.sort();
+        assert_eq!(selected, [(0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn capped_views_evict_oldest_records() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(4);
+        view.sort_records("n", false);
+        view.set_on_data_changed({
+            let events = events.clone();
+            move |event| events.borrow_mut().push(event)
+        });
+
+        // The records are 4, 3, 2, 1, so the oldest ones are last.
+        view.set_max_records(Some(3));
+        view.push_record(vec![("n".to_string(), 5)].into_iter().collect());
+
+        let values: Vec<_> = view.records.iter().map(|r| r["n"]).collect();
+        assert_eq!(values, [4, 3, 5]);
+        assert_eq!(*events.borrow(), [
+            ChangeEvent::RowsRemoved { start: 3, count: 1 },
+            ChangeEvent::RowsInserted { start: 3, count: 1 },
+            ChangeEvent::RowsRemoved { start: 2, count: 1 },
+        ]);
+
+        view.extend_records((6..=9).map(|n| vec![("n".to_string(), n)].into_iter().collect()));
+        let values: Vec<_> = view.records.iter().map(|r| r["n"]).collect();
+        assert_eq!(values, [7, 8, 9]);
+    }
+
+    #[test]
+    fn expired_records_are_pruned() {
+        let mut view = number_view(3);
+        view.set_row_ttl(Some(Duration::from_secs(60)));
+        view.set_record_timestamp(1, SystemTime::now() - Duration::from_secs(120));
+        assert_eq!(view.prune_expired_records(), 1);
+        assert_eq!(view.len_records(), 2);
+
+        view.set_record_timestamp(0, SystemTime::UNIX_EPOCH);
+        view.layout(Vec2::new(20, 10));
+        assert_eq!(view.len_records(), 1);
+        assert_eq!(view.records[0]["n"], 3);
+
+        view.set_row_ttl(None);
+        view.set_record_timestamp(0, SystemTime::UNIX_EPOCH);
+        assert_eq!(view.prune_expired_records(), 0);
+    }
+
+    #[test]
+    fn records_can_be_moved_out() {
+        let mut view = number_view(3);
+        let records = view.take_records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(view.len_records(), 0);
+
+        view.extend_records(records);
+        let (columns, records) = view.into_parts();
+        assert_eq!(columns.keys().collect::<Vec<_>>(), ["n", "sq"]);
+        assert_eq!(records[2]["sq"], 9);
+
+        assert_eq!(number_view(2).into_records().len(), 2);
+    }
+
+    #[test]
+    fn selection_becomes_records() {
+        let mut view = number_view(4);
+        view.selected_cells.extend(vec![(1, 3), (1, 1), (0, 1)]);
+
+        let (keys, records) = view.selection_to_records();
+        assert_eq!(keys, ["n", "sq"]);
+        assert_eq!(records, [
+            vec![("n".to_string(), 2), ("sq".to_string(), 4)].into_iter().collect(),
+            vec![("sq".to_string(), 16)].into_iter().collect::<Record<u32>>(),
+        ]);
+    }
+
+    #[test]
+    fn formulas_compute_cells() {
+        let mut view = number_view(3);
+        view.set_formulas_enabled(true);
+        view.set_from_str_parser();
+        view.define_name("squares", "B1:B3").unwrap();
+
+        view.set_formula(0, "n", "=SUM(squares) + A2").unwrap();
+        assert_eq!(view.cell_value(0, "n"), FormulaValue::Number(16.0));
+        assert_eq!(view.formula(0, "n"), Some("=SUM(squares) + A2"));
+
+        // Typing a formula into a cell replaces its value.
+        view.write_cell_text(1, "sq", "=A1 * 2").unwrap();
+        assert_eq!(view.cell_value(0, "n"), FormulaError::Cycle.into());
+        assert_eq!(view.render_to_string(Vec2::new(20, 5)).lines().nth(2), Some("#CYCLE! │       1"));
+
+        view.write_cell_text(1, "sq", "5").unwrap();
+        assert_eq!(view.formula(1, "sq"), None);
+        assert_eq!(view.cell_value(0, "n"), FormulaValue::Number(17.0));
+
+        assert!(view.set_formula(0, "n", "=1 +").is_err());
+        assert!(view.define_name("B2", "A1").is_err());
+    }
+
+    #[test]
+    fn edits_recalculate_dependent_formulas() {
+        let mut view = number_view(3);
+        view.set_formulas_enabled(true);
+        view.set_from_str_parser();
+        view.set_formula(0, "sq", "=A1 * 10").unwrap();
+        view.set_formula(1, "sq", "=B1 + 1").unwrap();
+        view.set_formula(2, "sq", "=A3").unwrap();
+
+        assert_eq!(view.cell_value(1, "sq"), FormulaValue::Number(11.0));
+        assert_eq!(view.cell_value(2, "sq"), FormulaValue::Number(3.0));
+        assert_eq!(view.formula_cache.borrow().len(), 3);
+
+        // Only the formulas depending on A1 are discarded.
+        view.write_cell_text(0, "n", "4").unwrap();
+        assert_eq!(view.formula_cache.borrow().len(), 1);
+        assert_eq!(view.cell_value(1, "sq"), FormulaValue::Number(41.0));
+
+        view.set_formula(0, "n", "=B2").unwrap();
+        assert_eq!(view.cell_value(1, "sq"), FormulaError::Cycle.into());
+        assert_eq!(view.cell_value(2, "sq"), FormulaValue::Number(3.0));
+    }
+
+    #[test]
+    fn formulas_refer_to_columns_by_key() {
+        let mut view = number_view(3);
+        view.set_formulas_enabled(true);
+        view.set_from_str_parser();
+        view.set_formula(1, "sq", "=[n] * 10 + [sq@-1]").unwrap();
+        view.set_formula(2, "sq", "=SUM([n@1]:[n])").unwrap();
+        assert_eq!(view.cell_value(1, "sq"), FormulaValue::Number(21.0));
+        assert_eq!(view.cell_value(2, "sq"), FormulaValue::Number(6.0));
+
+        // References by key follow their column around.
+        view.move_column("n", 1);
+        view.write_cell_text(0, "n", "5").unwrap();
+        assert_eq!(view.cell_value(1, "sq"), FormulaValue::Number(21.0));
+        assert_eq!(view.cell_value(2, "sq"), FormulaValue::Number(10.0));
+    }
 
-    /// Disables this view. A disabled view cannot be selected.
-    pub fn disable(&mut self) {
-        self.enabled = false;
+    #[test]
+    fn custom_formula_functions() {
+        let mut view = number_view(3);
+        view.set_formulas_enabled(true);
+        view.set_formula(0, "n", "=double(A2:A3)").unwrap();
+        assert_eq!(view.cell_value(0, "n"), FormulaError::Name.into());
+
+        view.register_function("Double", |args| {
+            args.iter().map(|arg| arg.as_number()).sum::<Result<f64, _>>()
+                .map_or_else(FormulaValue::Error, |n| FormulaValue::Number(n * 2.0))
+        });
+        assert_eq!(view.cell_value(0, "n"), FormulaValue::Number(10.0));
+
+        assert!(view.unregister_function("DOUBLE"));
+        assert!(!view.function_names().any(|name| name == "DOUBLE"));
+        assert_eq!(view.cell_value(0, "n"), FormulaError::Name.into());
     }
 
-    /// Re-enables this view.
-    pub fn enable(&mut self) {
-        self.enabled = true;
+    #[test]
+    fn formula_bar_edits_linked_view() {
+        let mut view = number_view(3);
+        view.set_read_only(false);
+        view.set_from_str_parser();
+        view.set_formulas_enabled(true);
+        view.set_formula(2, "sq", "=A3 * A3").unwrap();
+        view.set_cursor_pos(1, 2);
+
+        let mut bar = view.formula_bar("sheet");
+        view.layout(Vec2::new(20, 10));
+        assert_eq!(render::render_to_string(&mut bar, Vec2::new(20, 1)), "B3   │ =A3 * A3");
+
+        let mut siv = Cursive::dummy();
+        siv.add_layer(view.with_name("sheet"));
+
+        bar.on_event(Event::Key(Key::Enter));
+        assert!(bar.is_editing());
+        bar.on_event(Event::Char('+'));
+        bar.on_event(Event::Char('1'));
+        bar.on_event(Event::Key(Key::Enter)).process(&mut siv);
+
+        let value = siv.call_on_name("sheet", |view: &mut SpreadsheetView<u32>| view.cell_value(2, "sq"));
+        assert_eq!(value, Some(FormulaValue::Number(10.0)));
     }
 
-    /// Enable or disable this view.
-    pub fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
+    #[test]
+    fn locked_edits_can_be_allowed() {
+        let attempts = Rc::new(RefCell::new(Vec::new()));
+        let mut view = editable_view();
+        view.set_read_only(true);
+        assert!(!view.on_event(Event::Key(Key::Enter)).is_consumed());
+
+        view.set_on_locked_edit_attempt({
+            let attempts = attempts.clone();
+            move |_, row, key| attempts.borrow_mut().push((row, key.to_string()))
+        });
+        view.on_event(Event::Key(Key::Enter)).process(&mut Cursive::dummy());
+        assert_eq!(*attempts.borrow(), [(0, "name".to_string())]);
+        assert!(!view.is_editing());
+
+        view.edit_locked_cell(0, "name");
+        assert!(view.is_editing());
+        view.on_event(Event::Key(Key::Esc));
+
+        view.open_editor();
+        assert!(!view.is_editing());
     }
 
-    /// Returns `true` if this view is enabled.
-    pub fn is_enabled(&self) -> bool {
-        self.enabled
+    #[test]
+    fn named_ranges_follow_records() {
+        let mut view = SpreadsheetView::new();
+        view.push_column("id".to_string(), column("ID"));
+        view.push_column("value".to_string(), column("Value"));
+        view.set_key_column(Some("id"));
+        view.extend_records((1..=6).map(|id| keyed_record(id * 10, id)));
+        view.sort_records("id", true);
+        view.define_name("middle", "B2:B5").unwrap();
+
+        view.remove_record(2);
+        assert_eq!(view.named_range("middle"), CellRange::parse("B2:B4"));
+
+        view.upsert_record(keyed_record(15, 0));
+        assert_eq!(view.named_range("middle"), CellRange::parse("B3:B5"));
+
+        view.upsert_record(keyed_record(25, 0));
+        assert_eq!(view.named_range("middle"), CellRange::parse("B3:B6"));
+
+        view.sort_records("value", true);
+        assert_eq!(view.named_range("middle"), CellRange::parse("B3:B6"));
+
+        assert!(view.select_named_range("middle"));
+        assert_eq!(view.selected_cells().len(), 4);
+
+        view.clear_records();
+        assert_eq!(view.named_range("middle"), CellRange::parse("B3:B6"));
     }
-}
 
-#[cfg(test)]
-mod tests {}
+    #[test]
+    fn layout_callbacks_report_changes() {
+        let sizes = Rc::new(RefCell::new(Vec::new()));
+        let resized = Rc::new(RefCell::new(Vec::new()));
+
+        let mut view = number_view(3);
+        view.set_on_layout({
+            let sizes = sizes.clone();
+            move |size| sizes.borrow_mut().push(size)
+        });
+        view.set_on_column_resize({
+            let resized = resized.clone();
+            move |key, width| resized.borrow_mut().push((key.to_string(), width))
+        });
+
+        view.layout(Vec2::new(20, 10));
+        view.layout(Vec2::new(20, 10));
+        view.push_record(vec![("sq".to_string(), 1_000_000)].into_iter().collect());
+        view.layout(Vec2::new(30, 10));
+
+        assert_eq!(*sizes.borrow(), [Vec2::new(20, 10), Vec2::new(30, 10)]);
+        assert_eq!(*resized.borrow(), [("sq".to_string(), 7)]);
+    }
+
+    #[test]
+    fn focus_indicator_marks_header() {
+        let mut view = number_view(1);
+        view.set_focus_indicator(Some(FocusIndicator::Corner));
+        assert_eq!(view.render_to_string(Vec2::new(10, 3)).lines().nth(1), Some("◆─────────"));
+
+        view.set_focus_indicator(Some(FocusIndicator::Delimiter));
+        view.set_monochrome(true);
+        assert_eq!(view.render_to_string(Vec2::new(10, 3)).lines().nth(1), Some("=========="));
+    }
+
+    #[test]
+    fn shared_string_cells() {
+        let mut view = SpreadsheetView::<Rc<str>>::new();
+        view.push_column("name".to_string(), column("Name"));
+        for name in &["beta", "alpha"] {
+            view.push_record(vec![("name".to_string(), Rc::from(*name))].into_iter().collect());
+        }
+        let beta = view.records[0]["name"].clone();
+
+        view.sort_records("name", true);
+        view.selected_cells.insert((0, 1));
+        let (_, records) = view.selection_to_records();
+        assert!(Rc::ptr_eq(&records[0]["name"], &beta));
+
+        view.set_read_only(false);
+        view.set_from_text_parser();
+        view.write_cell_text(1, "name", "gamma").unwrap();
+        assert_eq!(&*view.records[1]["name"], "gamma");
+    }
+
+    #[test]
+    fn typed_column_keys() {
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        enum Col { Id, Value }
+
+        impl ColumnKey for Col {
+            const ALL: &'static [Self] = &[Col::Id, Col::Value];
+
+            fn key(self) -> &'static str {
+                match self {
+                    Col::Id => "id",
+                    Col::Value => "value",
+                }
+            }
+        }
+
+        let mut view = SpreadsheetView::new();
+        view.push_key_column(Col::Id, column("ID"));
+        view.push_key_column(Col::Value, column("Value"));
+        view.push_record(Col::record(vec![(Col::Id, 2), (Col::Value, 20)]));
+        view.push_record(Col::record(vec![(Col::Id, 1), (Col::Value, 10)]));
+
+        view.sort_records(Col::Id.key(), true);
+        assert_eq!(view.sorted_by_key(), Some((Col::Id, true)));
+        assert_eq!(view.records[0], keyed_record(1, 10));
+
+        view.set_cursor_pos(1, 0);
+        assert_eq!(view.cursor_key(), Some(Col::Value));
+    }
+
+    #[test]
+    fn column_data_is_typed() {
+        let mut view = number_view(1);
+        assert!(view.set_column_data("sq", ("m²", 2u8)));
+        assert!(!view.set_column_data("nope", 0));
+
+        assert_eq!(view.column_data::<(&str, u8)>("sq"), Some(&("m²", 2)));
+        assert_eq!(view.column_data::<u8>("sq"), None);
+        assert_eq!(view.column_data::<(&str, u8)>("n"), None);
+
+        let def = view.remove_column("sq").unwrap();
+        assert_eq!(def.data::<(&str, u8)>().map(|&(unit, _)| unit), Some("m²"));
+    }
+
+    #[test]
+    fn record_data_follows_records() {
+        let mut view = number_view(3);
+        for row in 0..3 {
+            view.set_record_data(row, format!("id-{}", row + 1));
+        }
+        view.set_record_data(7, 0u8);
+
+        view.sort_records("n", false);
+        assert_eq!(view.record_data::<String>(0).map(String::as_str), Some("id-3"));
+        assert_eq!(view.record_data::<u8>(0), None);
+
+        view.remove_record(0);
+        assert!(view.take_record_data(0).is_some());
+        assert_eq!(view.record_data::<String>(0), None);
+        assert_eq!(view.record_data::<String>(1).map(String::as_str), Some("id-1"));
+    }
+
+    #[test]
+    fn cursor_and_selection_getters() {
+        let mut view = number_view(3);
+        assert_eq!(view.cursor_record(), None);
+        assert_eq!(view.cursor_column(), None);
+
+        view.set_cursor_pos(1, 2);
+        assert_eq!(view.cursor_pos(), Some((1, 2)));
+        assert_eq!(view.cursor_record().map(|r| r["sq"]), Some(9));
+        assert_eq!(view.cursor_column(), Some("sq"));
+
+        assert!(!view.has_selection());
+        view.define_name("firsts", "A1:A2").unwrap();
+        view.select_named_range("firsts");
+        assert!(view.has_selection());
+    }
+
+    #[test]
+    fn insert_toggles_selection() {
+        let lens = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(3);
+        view.set_on_selection_changed({
+            let lens = lens.clone();
+            move |_, len| lens.borrow_mut().push(len)
+        });
+        assert!(!view.on_event(Event::Key(Key::Ins)).is_consumed());
+
+        let mut siv = Cursive::dummy();
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::Key(Key::Ins)).process(&mut siv);
+        view.set_cursor_pos(0, 2);
+        view.on_event(Event::Key(Key::Ins)).process(&mut siv);
+        assert_eq!(view.selection_len(), 2);
+
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::Key(Key::Ins)).process(&mut siv);
+        assert_eq!(view.selected_cells(), &vec![(0, 2)].into_iter().collect());
+        assert_eq!(*lens.borrow(), [1, 2, 1]);
+    }
+
+    #[test]
+    fn keys_sort_by_cursor_column() {
+        let mut view = number_view(3);
+        assert!(!view.on_event(Event::AltChar('s')).is_consumed());
+
+        view.set_cursor_pos(1, 0);
+        view.on_event(Event::AltChar('S'));
+        assert_eq!(view.sorted_by(), Some(("sq", false)));
+        assert_eq!(view.records[0]["n"], 3);
+
+        view.on_event(Event::AltChar('s'));
+        assert_eq!(view.sorted_by(), Some(("sq", true)));
+        assert_eq!(view.records[0]["n"], 1);
+    }
+
+    #[test]
+    fn header_clicks_toggle_sort() {
+        let sorts = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(3);
+        view.set_on_sort({
+            let sorts = sorts.clone();
+            move |_, key, order| sorts.borrow_mut().push((key.to_string(), order))
+        });
+        view.layout(Vec2::new(12, 5));
+
+        let click = |x: usize| Event::Mouse {
+            offset: Vec2::new(2, 1),
+            position: Vec2::new(2 + x, 1),
+            event: MouseEvent::Press(MouseButton::Left),
+        };
+        let mut siv = Cursive::dummy();
+        view.on_event(click(5)).process(&mut siv);
+        view.on_event(click(5)).process(&mut siv);
+        // Clicking the separator starts resizing rather than sorting.
+        view.on_event(click(2)).process(&mut siv);
+        view.on_event(Event::Mouse {
+            offset: Vec2::new(2, 1),
+            position: Vec2::new(4, 1),
+            event: MouseEvent::Release(MouseButton::Left),
+        });
+        assert_eq!(view.records[0]["n"], 3);
+        assert!(view.render_to_string(Vec2::new(14, 3)).starts_with("N │ Square ▼\n"));
+
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::AltChar('o')).process(&mut siv);
+        assert_eq!(*sorts.borrow(), [
+            ("sq".to_string(), Ordering::Less),
+            ("sq".to_string(), Ordering::Greater),
+            ("n".to_string(), Ordering::Less),
+        ]);
+    }
+
+    #[test]
+    fn mouse_selects_and_scrolls() {
+        let mut view = number_view(20);
+        view.layout(Vec2::new(14, 7));
+
+        let mouse = |event, x: usize, y: usize| Event::Mouse {
+            offset: Vec2::zero(),
+            position: Vec2::new(x, y),
+            event,
+        };
+        view.on_event(mouse(MouseEvent::Press(MouseButton::Left), 0, 3));
+        assert_eq!(view.cursor_pos, Some((0, 1)));
+        assert!(!view.on_event(mouse(MouseEvent::Press(MouseButton::Left), 2, 3)).is_consumed());
+
+        view.on_event(mouse(MouseEvent::Hold(MouseButton::Left), 5, 4));
+        view.on_event(mouse(MouseEvent::Hold(MouseButton::Left), 5, 5));
+        view.on_event(mouse(MouseEvent::Release(MouseButton::Left), 5, 5));
+        assert_eq!(view.selection(), CellRange::parse("A2:B4"));
+        assert_eq!(view.cursor_pos, Some((1, 3)));
+
+        view.on_event(mouse(MouseEvent::WheelDown, 0, 3));
+        assert_eq!(view.scroll_offset(), Vec2::new(0, 3));
+        view.on_event(mouse(MouseEvent::Press(MouseButton::Left), 0, 2));
+        assert_eq!(view.cursor_pos, Some((0, 3)));
+        view.on_event(mouse(MouseEvent::WheelUp, 0, 3));
+        assert!(!view.on_event(mouse(MouseEvent::WheelUp, 0, 3)).is_consumed());
+    }
+
+    #[test]
+    fn cells_are_submitted_by_enter_and_double_click() {
+        let mut siv = Cursive::dummy();
+        let submitted = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(5);
+        let log = submitted.clone();
+        view.set_on_submit(move |_, row, x, key| log.borrow_mut().push((row, x, key.to_string())));
+        view.layout(Vec2::new(14, 7));
+
+        view.set_cursor_pos(1, 2);
+        view.on_event(Event::Key(Key::Enter)).process(&mut siv);
+        assert_eq!(*submitted.borrow(), [(2, 1, "sq".to_string())]);
+
+        let click = Event::Mouse { offset: Vec2::zero(), position: Vec2::new(0, 2), event: MouseEvent::Press(MouseButton::Left) };
+        view.on_event(click.clone()).process(&mut siv);
+        assert_eq!(view.cursor_pos, Some((0, 0)));
+        assert_eq!(submitted.borrow().len(), 1);
+        view.on_event(click).process(&mut siv);
+        assert_eq!(submitted.borrow()[1], (0, 0, "n".to_string()));
+
+        // Editable views edit the cell instead.
+        let mut view = editable_view();
+        view.set_on_submit(|_, _, _, _| panic!("submitted"));
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::Key(Key::Enter));
+        assert!(view.is_editing());
+    }
+
+    #[test]
+    fn row_styles_see_whole_records() {
+        let mut view = number_view(4);
+        let red = Style::from(ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View));
+        view.add_row_style(red, |record| record["n"] % 2 == 0 && record["sq"] > 1);
+        view.add_row_style(Effect::Bold.into(), |record| record["n"] >= 3);
+
+        assert_eq!(view.row_style(0), Style::none());
+        assert_eq!(view.row_style(1), red);
+        assert_eq!(view.row_style(2), Style::from(Effect::Bold));
+        assert_eq!(view.row_style(3), red.combine(Effect::Bold));
+
+        view.set_monochrome(true);
+        assert_eq!(view.row_style(3), Style::from(Effect::Bold));
+
+        view.clear_row_styles();
+        assert_eq!(view.row_style(3), Style::none());
+    }
+
+    #[test]
+    fn monochrome_styles_use_effects_only() {
+        let mut view = number_view(1);
+        view.set_monochrome(true);
+
+        let styles = [
+            view.cell_style(true, true, true, false, false, false),
+            view.cell_style(true, false, true, false, false, false),
+            view.cell_style(false, false, true, false, false, false),
+            view.cell_style(true, false, false, true, false, true),
+            view.cell_style(true, false, false, false, true, false),
+            view.error_marker_style(),
+        ];
+        assert!(styles.iter().all(|style| style.color.is_none() && !style.effects.is_empty()));
+        assert!(styles[1].effects.contains(Effect::Reverse));
+        assert_eq!(view.cell_style(true, false, false, false, false, true), Style::none());
+
+        view.set_monochrome(false);
+        assert!(view.cell_style(true, false, true, false, false, false).color.is_some());
+    }
+
+    fn number_view(count: u32) -> SpreadsheetView<u32> {
+        let mut view = SpreadsheetView::new();
+        view.push_column("n".to_string(), column("N"));
+        view.push_column("sq".to_string(), ColumnDef { alignment: HAlign::Right, ..column("Square") });
+        view.extend_records((1..=count).map(|n| {
+            vec![("n".to_string(), n), ("sq".to_string(), n * n)].into_iter().collect()
+        }));
+        view
+    }
+
+    #[test]
+    fn keyboard_navigation_moves_and_scrolls() {
+        let mut view = number_view(20);
+        let moves = Rc::new(RefCell::new(Vec::new()));
+        let log = moves.clone();
+        view.set_on_select(move |_, row, col| log.borrow_mut().push((row, col)));
+        view.layout(Vec2::new(12, 7));
+
+        let mut siv = Cursive::dummy();
+        let mut press = |view: &mut SpreadsheetView<u32>, event| match view.on_event(event) {
+            EventResult::Consumed(Some(cb)) => {
+                cb(&mut siv);
+                true
+            },
+            EventResult::Consumed(None) => true,
+            EventResult::Ignored => false,
+        };
+
+        assert!(press(&mut view, Event::Key(Key::Down)));
+        assert_eq!(view.cursor_pos, Some((0, 0)));
+        assert!(press(&mut view, Event::Key(Key::Right)));
+        assert!(!press(&mut view, Event::Key(Key::Right)));
+        assert!(press(&mut view, Event::Key(Key::PageDown)));
+        assert_eq!(view.cursor_pos, Some((1, 5)));
+        assert!(press(&mut view, Event::Ctrl(Key::End)));
+        assert_eq!(view.cursor_pos, Some((1, 19)));
+        assert!(view.scroll_base.start_line + view.scroll_base.view_height > 19);
+        assert!(press(&mut view, Event::Key(Key::Home)));
+        assert!(press(&mut view, Event::Ctrl(Key::Home)));
+        assert_eq!(view.scroll_base.start_line, 0);
+        assert!(!press(&mut view, Event::Key(Key::Up)));
+
+        assert_eq!(*moves.borrow(), [(0, 0), (0, 1), (5, 1), (19, 1), (19, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn shift_arrows_select_rectangle() {
+        let mut view = number_view(5);
+        view.layout(Vec2::new(12, 7));
+        view.set_cursor_pos(0, 1);
+
+        view.on_event(Event::Shift(Key::Down));
+        view.on_event(Event::Shift(Key::Down));
+        view.on_event(Event::Shift(Key::Right));
+        assert_eq!(view.selection(), Some(CellRange::new((0, 1), (1, 3))));
+        assert_eq!(view.selection_len(), 6);
+
+        view.on_event(Event::Shift(Key::Up));
+        assert_eq!(view.selection(), CellRange::parse("A2:B3"));
+
+        // Moving without Shift keeps the selection, but starts a new one next.
+        view.on_event(Event::Key(Key::Down));
+        view.on_event(Event::CtrlShift(Key::End));
+        assert_eq!(view.selection(), CellRange::parse("B4:B5"));
+
+        view.set_selection(CellRange::new((1, 0), (4, 9)));
+        assert_eq!(view.selection(), CellRange::parse("B1:B5"));
+    }
+
+    #[test]
+    fn frozen_columns_stay_pinned() {
+        let mut view = number_view(2);
+        view.push_column("cube".to_string(), column("Cube"));
+        view.push_column("half".to_string(), column("Half"));
+        view.set_frozen_columns(1);
+        view.render_to_string(Vec2::new(16, 4));
+
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::Key(Key::End));
+        assert_eq!(view.scroll_offset(), Vec2::new(1, 0));
+        assert_eq!(view.render_to_string(Vec2::new(16, 4)), [
+            "N ║ Cube │ Half",
+            "────────────────",
+            "1 ║      │",
+            "2 ║      │",
+        ].join("\n"));
+
+        view.on_event(Event::Key(Key::Home));
+        view.on_event(Event::Key(Key::Right));
+        assert_eq!(view.scroll_offset(), Vec2::new(0, 0));
+        assert!(view.render_to_string(Vec2::new(16, 4)).starts_with("N ║ Square │ Cub\n"));
+    }
+
+    #[test]
+    fn scrolls_to_records_and_columns() {
+        let mut view = number_view(20);
+        view.push_column("cube".to_string(), column("Cube"));
+        view.push_column("half".to_string(), column("Half"));
+        view.render_to_string(Vec2::new(16, 6));
+        assert_eq!(view.visible_range(), (0..4, 0..2));
+
+        view.scroll_to_row(10);
+        assert!(view.scroll_to_column("half"));
+        assert!(!view.scroll_to_column("nope"));
+        assert_eq!(view.visible_range(), (7..11, 2..4));
+
+        view.set_cursor_pos(0, 0);
+        view.scroll_to_row(19);
+        view.scroll_to_cursor();
+        assert_eq!(view.visible_range(), (0..4, 0..2));
+    }
+
+    #[test]
+    fn pages_and_scrollbar_follow_columns() {
+        let mut view = number_view(2);
+        view.push_column("cube".to_string(), column("Cube"));
+        view.push_column("half".to_string(), column("Half"));
+        view.set_horizontal_scrollbar(true);
+        view.render_to_string(Vec2::new(16, 5));
+        view.set_cursor_pos(0, 0);
+
+        view.on_event(Event::Ctrl(Key::Right));
+        assert_eq!(view.cursor_pos, Some((2, 0)));
+        view.on_event(Event::Ctrl(Key::Right));
+        assert_eq!(view.cursor_pos, Some((3, 0)));
+        assert_eq!(view.scroll_offset(), Vec2::new(2, 0));
+        assert_eq!(view.render_to_string(Vec2::new(16, 5)), [
+            "Cube │ Half",
+            "────────────────",
+            "     │",
+            "     │",
+            "------▒▒▒▒▒▒▒▒▒▒",
+        ].join("\n"));
+
+        view.on_event(Event::Ctrl(Key::Left));
+        assert_eq!(view.cursor_pos, Some((1, 0)));
+        assert_eq!(view.scroll_offset(), Vec2::new(1, 0));
+    }
+
+    #[test]
+    fn builds_in_one_expression() {
+        let mut siv = Cursive::dummy();
+        siv.add_layer(
+            SpreadsheetView::<u32>::new()
+                .column("n".to_string(), column("N"))
+                .records((1..=3).map(|n| vec![("n".to_string(), n)].into_iter().collect()))
+                .on_sort(|s, key, _| s.set_user_data(key.to_string()))
+                .on_select(|_, _, _| {})
+                .on_submit(|_, _, _, _| {})
+                .with_name("numbers"),
+        );
+
+        siv.call_on_name("numbers", |view: &mut SpreadsheetView<u32>| {
+            assert_eq!(view.len_records(), 3);
+            view.set_cursor_pos(0, 0);
+            view.on_event(Event::AltChar('S'))
+        })
+        .unwrap()
+        .process(&mut siv);
+        assert_eq!(siv.take_user_data::<String>().unwrap(), "n");
+    }
+
+    #[test]
+    fn viewport_matches_last_draw() {
+        let mut view = number_view(5);
+        view.set_focus_indicator(Some(FocusIndicator::Delimiter));
+        let size = Vec2::new(12, 4);
+        view.layout(size);
+
+        let backend = cursive::backend::puppet::Backend::init(Some(size));
+        let theme = cursive::theme::load_default();
+        view.draw(&Printer::new(size, &theme, &*backend).focused(false));
+        assert_eq!(view.viewport_to_string(), "N │ Square\n────────────\n1 │      1 ▒\n2 │      4 |");
+
+        view.render_to_string(size);
+        assert!(view.viewport_to_string().contains("━"));
+
+        // Columns changed since the last layout are drawn once it is laid out again.
+        view.push_column("cube".to_string(), column("Cube"));
+        view.set_column_aggregate("cube", Aggregate::Sum);
+        assert!(view.viewport_to_string().starts_with("N │ Square\n"));
+        view.remove_column("n");
+        view.viewport_to_string();
+        assert!(view.render_to_string(Vec2::new(20, 6)).starts_with("Square │ Cube\n"));
+    }
+
+    #[test]
+    fn read_only_indicator_and_toggle() {
+        let toggles = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(1);
+        view.set_read_only_indicator(Some("RO"));
+        view.set_on_read_only_toggle({
+            let toggles = toggles.clone();
+            move |_, read_only| toggles.borrow_mut().push(read_only)
+        });
+        assert_eq!(view.render_to_string(Vec2::new(14, 2)), "N │ Square\n───────── RO ─");
+
+        view.on_event(Event::CtrlChar('r')).process(&mut Cursive::dummy());
+        assert!(!view.is_read_only());
+        assert_eq!(view.render_to_string(Vec2::new(14, 2)), "N │ Square\n──────────────");
+
+        view.on_event(Event::CtrlChar('r')).process(&mut Cursive::dummy());
+        assert_eq!(*toggles.borrow(), [false, true]);
+
+        view.set_bindings(Bindings::vim());
+        view.on_event(Event::AltChar('r')).process(&mut Cursive::dummy());
+        assert!(!view.is_read_only());
+    }
+
+    #[test]
+    fn paste_adds_columns_and_reports_batch() {
+        let mut view = number_view(1);
+        view.set_read_only(false);
+        view.set_from_str_parser();
+        view.push_column("D".to_string(), column("Taken"));
+        view.set_on_paste(|paste| {
+            paste.append_rows = true;
+            paste.append_columns = true;
+            true
+        });
+        let batches = Rc::new(RefCell::new(Vec::new()));
+        view.set_on_batch_edit({
+            let batches = batches.clone();
+            move |_, range| batches.borrow_mut().push(range.to_string())
+        });
+
+        view.set_cursor_pos(1, 0);
+        let result = view.paste_text("1	2	3
+4	5	6	7").unwrap();
+        result.process(&mut Cursive::dummy());
+        assert_eq!(view.column_keys(), ["n", "sq", "D", "D2", "E"]);
+        assert_eq!(view.columns["D2"].title(), "D2");
+        assert_eq!(view.records[1]["E"], 7);
+        assert_eq!(*batches.borrow(), ["B1:E2"]);
+
+        // Nothing is added when a cell does not parse.
+        assert!(view.paste_text("1	2	3	4	x").is_err());
+        assert_eq!(view.len_columns(), 5);
+
+        // The range follows the pasted record when the paste re-sorts it.
+        view.sort_records("sq", true);
+        view.set_cursor_pos(1, 0);
+        view.paste_text("9").unwrap().process(&mut Cursive::dummy());
+        assert_eq!(view.records[1]["sq"], 9);
+        assert_eq!(batches.borrow().last().unwrap(), "B2");
+    }
+
+    #[test]
+    fn paste_hook_transforms_or_cancels() {
+        let mut view = number_view(2);
+        view.set_read_only(false);
+        view.set_from_str_parser();
+        view.set_cursor_pos(1, 1);
+
+        assert!(view.paste_text("5\tx").is_ok());
+        assert_eq!(view.records[1]["sq"], 5);
+
+        // Text that does not parse leaves every cell alone.
+        view.set_cursor_pos(0, 1);
+        assert_eq!(view.paste_text("6\tx").err().unwrap(), "Row 2, column Square: invalid digit found in string");
+        assert_eq!(view.records[1]["n"], 2);
+
+        view.set_on_paste(|paste| {
+            paste.append_rows = true;
+            for cells in &mut paste.cells {
+                cells.retain(|cell| cell != "x");
+            }
+            paste.cells.len() < 3
+        });
+        view.set_cursor_pos(0, 0);
+        view.paste_text("7\tx\n8\n9").unwrap();
+        assert_eq!(view.records[0]["n"], 1);
+
+        view.paste_text("7\tx\n8\tx\n").unwrap();
+        view.set_cursor_pos(0, 1);
+        view.paste_text("9\n10").unwrap();
+        assert_eq!(view.len_records(), 3);
+        assert_eq!(view.records[0]["n"], 7);
+        assert_eq!(view.records[1]["n"], 9);
+        assert_eq!(view.records[2]["n"], 10);
+    }
+
+    #[test]
+    fn visible_cells_are_cached() {
+        let mut view = number_view(10);
+        view.render_to_string(Vec2::new(14, 5));
+        let mut cached: Vec<usize> = view.visible_cells.borrow().keys().copied().collect();
+        cached.sort();
+        assert_eq!(cached, [0, 1, 2]);
+
+        view.scroll_base.start_line = 5;
+        view.render_to_string(Vec2::new(14, 5));
+        assert!(view.visible_cells.borrow().keys().all(|&row| (5..8).contains(&row)));
+
+        view.set_from_str_parser();
+        view.write_cell_text(6, "sq", "0").unwrap();
+        assert!(view.render_to_string(Vec2::new(14, 5)).lines().nth(3).unwrap().starts_with("7  │      0"));
+    }
+
+    #[test]
+    fn changes_forget_only_the_cells_they_touch() {
+        let mut view = number_view(10);
+        view.hide_column("n");
+        view.render_to_string(Vec2::new(14, 5));
+        assert_eq!(view.visible_cells.borrow()[&0], [None, Some("1".to_string())]);
+
+        view.set_cell(1, "sq", 0);
+        view.push_record(vec![("n".to_string(), 11)].into_iter().collect());
+        let mut cached: Vec<usize> = view.visible_cells.borrow().keys().copied().collect();
+        cached.sort();
+        assert_eq!(cached, [0, 2]);
+        assert!(view.render_to_string(Vec2::new(14, 5)).lines().nth(3).unwrap().starts_with("     0"));
+
+        view.remove_record(0);
+        assert!(view.visible_cells.borrow().is_empty());
+    }
+
+    #[test]
+    fn render_grid() {
+        let mut view = number_view(3);
+        assert_eq!(view.render_to_string(Vec2::new(14, 6)), [
+            "N │ Square",
+            "──────────────",
+            "1 │      1",
+            "2 │      4",
+            "3 │      9",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn render_sort_glyph_on_narrow_columns() {
+        let mut view = number_view(2);
+        view.push_column("q".to_string(), ColumnDef { width: ColumnWidth::Fixed(6), ..column("Quantity") });
+        view.sort_records("sq", false);
+        assert_eq!(view.render_to_string(Vec2::new(30, 1)), "N │ Square ▼ │ Quanti");
+
+        let mut headers = Vec::new();
+        view.sort_records("q", true);
+        for truncation in [HeaderTruncation::Middle, HeaderTruncation::End, HeaderTruncation::Clip] {
+            view.set_header_truncation(truncation);
+            headers.push(view.render_to_string(Vec2::new(30, 1)));
+        }
+        assert_eq!(headers, ["N │ Square │ Qu…y ▲", "N │ Square │ Qua… ▲", "N │ Square │ Quanti"]);
+    }
+
+    #[test]
+    fn render_bands_and_tall_rows() {
+        let mut view = number_view(3);
+        view.set_banding(Some((2, Banding::Line)));
+        view.set_row_height(2);
+        assert_eq!(view.render_to_string(Vec2::new(10, 9)), [
+            "N │ Square",
+            "──────────",
+            "1 │      1",
+            "  │",
+            "2 │      4",
+            "  │",
+            "──────────",
+            "3 │      9",
+            "  │",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn apply_event_runs_callbacks() {
+        let mut siv = Cursive::dummy();
+        let mut view = editable_view();
+        view.set_on_edit(|s, row, key, _, new| s.set_user_data(format!("{}:{}={}", row, key, new)));
+
+        view.apply_event(Event::Key(Key::Enter));
+        view.apply_event(Event::Char('?'));
+        view.apply_event(Event::Key(Key::Tab));
+        view.apply_event(Event::Key(Key::Enter)).process(&mut siv);
+
+        assert_eq!(siv.take_user_data::<String>().unwrap(), "0:name=?alpha");
+        assert_eq!(view.cursor_pos(), Some((0, 0)));
+        assert!(view.selected_cells().is_empty());
+        assert_eq!(view.scroll_offset(), Vec2::zero());
+    }
+
+    #[test]
+    fn vim_bindings_move_and_pick_columns() {
+        let mut view = number_view(3);
+        view.set_bindings(Bindings::vim());
+        view.render_to_string(Vec2::new(20, 6));
+        view.set_cursor_pos(0, 0);
+
+        view.on_event(Event::Char('j'));
+        view.on_event(Event::Char('l'));
+        assert_eq!(view.cursor_pos(), Some((1, 1)));
+        view.on_event(Event::Char('G'));
+        assert_eq!(view.cursor_pos(), Some((1, 2)));
+
+        view.set_column_select(true);
+        view.on_event(Event::Char('h'));
+        assert_eq!(view.highlighted_column(), Some("n"));
+    }
+
+    #[test]
+    fn bound_keys_trigger_actions() {
+        let mut view = editable_view();
+        assert!(view.on_event(Event::Key(Key::F1)).has_callback());
+
+        view.unbind(&Event::Key(Key::Enter));
+        assert!(!view.on_event(Event::Key(Key::Enter)).is_consumed());
+
+        view.bind(Event::Char('i'), SpreadsheetAction::EditCell);
+        view.on_event(Event::Char('i'));
+        assert!(view.is_editing());
+    }
+
+    #[test]
+    fn band_lines_map_to_records() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("n".to_string(), column("N"));
+        view.extend_records((0..7).map(|n| vec![("n".to_string(), n)].into_iter().collect()));
+        view.set_banding(Some((3, Banding::Line)));
+
+        // Three records, a separator, three records, a separator, one record.
+        assert_eq!(view.content_lines(), 9);
+        assert_eq!(view.line_to_row(2), Some((2, 0)));
+        assert_eq!(view.line_to_row(3), None);
+        assert_eq!(view.line_to_row(4), Some((3, 0)));
+        assert_eq!(view.line_to_row(8), Some((6, 0)));
+        assert_eq!(view.row_to_line(6), 8);
+
+        view.set_row_height(2);
+        assert_eq!(view.content_lines(), 16);
+        assert_eq!(view.line_to_row(6), None);
+        assert_eq!(view.line_to_row(8), Some((3, 1)));
+        assert_eq!(view.row_to_line(4), 9);
+
+        view.set_banding(Some((2, Banding::Stripe)));
+        assert_eq!(view.content_lines(), 14);
+        view.set_banding(Some((0, Banding::Line)));
+        assert_eq!(view.banding(), None);
+    }
+
+    #[test]
+    fn undo_and_redo_changes() {
+        let mut view = editable_view();
+        view.push_column("note".to_string(), column("Note"));
+        view.set_undo_enabled(true);
+        assert!(!view.can_undo());
+
+        view.write_cell_text(0, "name", "beta").unwrap();
+        view.push_record(vec![("name".to_string(), "aardvark".to_string())].into_iter().collect());
+        view.sort_records("name", true);
+        view.remove_column("note");
+        assert_eq!(view.records[0]["name"], "aardvark");
+
+        // Undoing follows the records through the sort.
+        assert!(view.undo());
+        assert_eq!(view.column_keys(), ["name", "note"]);
+        assert!(view.undo());
+        assert_eq!(view.len_records(), 1);
+        assert!(view.on_event(Event::CtrlChar('z')).is_consumed());
+        assert_eq!(view.records[0]["name"], "alpha");
+        assert!(!view.undo());
+
+        assert!(view.on_event(Event::CtrlChar('y')).is_consumed());
+        assert_eq!(view.records[0]["name"], "beta");
+        assert!(view.redo());
+        assert_eq!(view.len_records(), 2);
+
+        // A new change cannot be redone past, and read-only views ignore the keys.
+        view.write_cell_text(0, "name", "gamma").unwrap();
+        assert!(!view.can_redo());
+        view.set_read_only(true);
+        assert!(!view.on_event(Event::CtrlChar('z')).is_consumed());
+    }
+
+    #[test]
+    fn undo_covers_replaced_and_taken_records() {
+        let mut view = editable_view();
+        view.set_key_column(Some("name"));
+        view.set_undo_enabled(true);
+
+        view.replace_record(0, vec![("name".to_string(), "beta".to_string())].into_iter().collect());
+        view.upsert_record(vec![("name".to_string(), "gamma".to_string())].into_iter().collect());
+        view.take_records();
+        assert!(view.undo());
+        assert_eq!(view.len_records(), 2);
+        assert!(view.undo());
+        assert_eq!(view.len_records(), 1);
+        assert!(view.undo());
+        assert_eq!(view.records[0]["name"], "alpha");
+
+        // Pasting new columns can be undone too.
+        view.set_on_paste(|paste| {
+            paste.append_columns = true;
+            true
+        });
+        view.paste_text("x\ty").unwrap();
+        assert_eq!(view.column_keys().len(), 2);
+        while view.undo() {}
+        assert_eq!(view.column_keys(), ["name"]);
+
+        view.set_records(Vec::new());
+        assert!(!view.can_undo());
+        assert!(view.undo_enabled());
+    }
+
+    #[test]
+    fn filter_hides_records() {
+        let mut view = number_view(6);
+        view.set_cursor_pos(0, 1);
+        view.set_filter(|record| record["n"] % 2 == 0);
+        assert_eq!(view.len_shown_records(), 3);
+        assert_eq!(view.len_records(), 6);
+        assert_eq!(view.render_to_string(Vec2::new(12, 5)), [
+            "N │ Square",
+            "────────────",
+            "2 │      4",
+            "4 │     16",
+            "6 │     36",
+        ].join("\n"));
+
+        // The cursor moves between the records shown, keeping their indices.
+        view.on_event(Event::Key(Key::Down));
+        assert_eq!(view.cursor_pos(), Some((0, 3)));
+        view.set_selection(CellRange::new((0, 0), (0, 5)));
+        assert_eq!(view.selection_len(), 3);
+
+        // Records edited so that the filter no longer holds for them are hidden.
+        view.set_from_str_parser();
+        view.write_cell_text(3, "n", "5").unwrap();
+        assert!(!view.is_record_shown(3));
+        assert_eq!(view.cursor_pos(), Some((0, 5)));
+        assert_eq!(view.selection_len(), 2);
+
+        view.clear_filter();
+        assert_eq!(view.len_shown_records(), 6);
+        assert_eq!(view.row_to_line(5), 5);
+    }
+
+    #[test]
+    fn search_jumps_between_matches() {
+        let mut view = number_view(20);
+        view.layout(Vec2::new(12, 7));
+        assert_eq!(view.search("1"), Ok(true));
+        assert_eq!(view.cursor_pos(), Some((0, 0)));
+
+        view.on_event(Event::Key(Key::F3));
+        view.on_event(Event::Key(Key::F3));
+        assert_eq!(view.cursor_pos(), Some((1, 3)));
+        view.on_event(Event::Shift(Key::F3));
+        assert_eq!(view.cursor_pos(), Some((1, 0)));
+        assert!(view.search_previous());
+        assert!(view.search_previous());
+        assert_eq!(view.cursor_pos(), Some((1, 18)));
+
+        // The prompt searches as the query is typed, and Esc puts the cursor back.
+        view.on_event(Event::CtrlChar('f'));
+        view.on_event(Event::Char('8'));
+        view.on_event(Event::Char('1'));
+        assert_eq!(view.cursor_pos(), Some((1, 8)));
+        assert_eq!(view.scroll_offset(), Vec2::new(1, 7));
+        assert!(view.render_to_string(Vec2::new(12, 7)).ends_with("\n/81_________"));
+        view.on_event(Event::Key(Key::Esc));
+        assert_eq!(view.cursor_pos(), Some((1, 18)));
+        assert_eq!(view.search_query(), None);
+
+        view.set_search_ignore_case(true);
+        assert_eq!(view.search("x"), Ok(false));
+        assert!(!view.search_next());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn search_with_regex() {
+        let mut view = number_view(20);
+        view.set_search_regex(true);
+        assert!(view.search("(").is_err());
+        assert_eq!(view.search("^1.$"), Ok(true));
+        assert_eq!(view.cursor_pos(), Some((1, 3)));
+        assert!(view.search_next());
+        assert_eq!(view.cursor_pos(), Some((0, 9)));
+    }
+
+    #[test]
+    fn copy_cut_and_paste_cells() {
+        let mut view = number_view(3);
+        view.set_cursor_pos(1, 1);
+        assert_eq!(view.copy_selection_to_string(), "4");
+
+        // Read-only views copy, but do not cut or paste.
+        view.set_selection(CellRange::new((0, 1), (1, 2)));
+        assert!(view.on_event(Event::CtrlChar('c')).is_consumed());
+        assert_eq!(view.clipboard_text(), Some("2\t4\n3\t9"));
+        assert!(!view.on_event(Event::CtrlChar('x')).is_consumed());
+
+        view.set_read_only(false);
+        view.set_from_str_parser();
+        view.set_cursor_pos(1, 2);
+        assert!(view.on_event(Event::CtrlChar('v')).is_consumed());
+        assert_eq!(view.len_records(), 4);
+        assert_eq!(view.records[2]["sq"], 2);
+        assert_eq!(view.records[3]["sq"], 3);
+        assert!(!view.records[3].contains_key("n"));
+
+        view.set_selection(CellRange::new((0, 0), (0, 1)));
+        assert!(view.cut_selection());
+        assert_eq!(view.clipboard_text(), Some("1\n2"));
+        assert!(!view.records[0].contains_key("n") && !view.records[1].contains_key("n"));
+        assert_eq!(view.records[1]["sq"], 4);
+    }
+
+    #[test]
+    fn column_and_cell_styles_stack() {
+        let mut view = number_view(3);
+        let blue = Style::from(ColorStyle::new(Color::Dark(BaseColor::Blue), PaletteColor::View));
+        let red = Style::from(ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View));
+        view.columns["sq"].set_style(blue.combine(Effect::Bold));
+        view.add_row_style(Effect::Underline.into(), |record| record["n"] == 2);
+        view.set_cell_style(move |row, key, &value| if key == "sq" && value > 1 && row < 2 { Some(red) } else { None });
+
+        let column = &view.columns["sq"];
+        assert_eq!(view.content_style(0, "sq", column, view.row_style(0)), blue.combine(Effect::Bold));
+        assert_eq!(view.content_style(1, "sq", column, view.row_style(1)), red.combine(Effect::Bold).combine(Effect::Underline));
+        assert_eq!(view.content_style(1, "n", &view.columns["n"], view.row_style(1)), Style::from(Effect::Underline));
+
+        view.set_monochrome(true);
+        assert_eq!(view.content_style(0, "sq", &view.columns["sq"], view.row_style(0)), Style::from(Effect::Bold));
+    }
+
+    #[test]
+    fn columns_move_with_the_cursor() {
+        let mut view = number_view(2);
+        view.push_column("cube".to_string(), column("Cube"));
+        view.set_cursor_pos(2, 1);
+        view.set_selection(CellRange::new((0, 0), (0, 1)));
+
+        assert!(view.move_column("cube", 0));
+        assert_eq!(view.column_keys(), ["cube", "n", "sq"]);
+        assert_eq!(view.cursor_pos(), Some((0, 1)));
+        assert!(view.selected_cells().contains(&(1, 1)));
+        assert!(!view.move_column("nope", 0));
+
+        assert!(view.on_event(Event::Alt(Key::Right)).is_consumed());
+        assert!(view.on_event(Event::Alt(Key::Right)).is_consumed());
+        assert!(!view.on_event(Event::Alt(Key::Right)).is_consumed());
+        assert_eq!(view.column_keys(), ["n", "sq", "cube"]);
+        assert_eq!(view.cursor_pos(), Some((2, 1)));
+    }
+
+    #[test]
+    fn rows_and_columns_select_whole() {
+        let mut view = number_view(4);
+        view.set_cursor_pos(1, 1);
+        assert!(view.on_event(Event::AltChar('v')).is_consumed());
+        assert_eq!(view.selection_mode(), SelectionMode::Row);
+
+        view.on_event(Event::Key(Key::Ins));
+        view.on_event(Event::Key(Key::Down));
+        view.on_event(Event::Key(Key::Down));
+        view.on_event(Event::Key(Key::Ins));
+        assert_eq!(view.selected_rows(), [1, 3]);
+        assert_eq!(view.selection_len(), 4);
+        view.on_event(Event::Key(Key::Ins));
+        assert_eq!(view.selected_rows(), [1]);
+
+        view.on_event(Event::Shift(Key::Up));
+        assert_eq!(view.selected_rows(), [2, 3]);
+        assert_eq!(view.selected_columns(), [0, 1]);
+        assert!(view.is_cursor_cell(0, 2));
+        assert!(!view.is_cursor_cell(0, 1));
+
+        view.on_event(Event::AltChar('v'));
+        assert_eq!(view.selection_mode(), SelectionMode::Column);
+        assert!(!view.has_selection());
+        view.on_event(Event::Key(Key::Ins));
+        assert_eq!(view.selected_columns(), [1]);
+        assert_eq!(view.selected_rows(), [0, 1, 2, 3]);
+        assert!(view.is_cursor_cell(1, 0));
+    }
+
+    #[test]
+    fn column_select_mode_picks_columns() {
+        let picked = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(3);
+        view.set_on_column_select({
+            let picked = picked.clone();
+            move |_, key| picked.borrow_mut().push(format!("select {}", key))
+        });
+        view.set_cursor_pos(0, 1);
+
+        let mut siv = Cursive::dummy();
+        assert!(view.on_event(Event::AltChar('c')).is_consumed());
+        assert_eq!(view.highlighted_column(), Some("n"));
+        assert!(!view.on_event(Event::Key(Key::Left)).is_consumed());
+        view.on_event(Event::Key(Key::Right)).process(&mut siv);
+        assert!(!view.on_event(Event::Key(Key::Right)).is_consumed());
+        assert_eq!(view.cursor_pos(), Some((0, 1)));
+
+        view.on_event(Event::Key(Key::Enter));
+        assert_eq!(view.sorted_by(), Some(("sq", true)));
+        view.set_on_column_submit({
+            let picked = picked.clone();
+            move |_, key| picked.borrow_mut().push(format!("submit {}", key))
+        });
+        view.on_event(Event::Key(Key::Enter)).process(&mut siv);
+        assert_eq!(*picked.borrow(), ["select sq", "submit sq"]);
+
+        view.on_event(Event::Key(Key::Esc));
+        assert!(!view.is_column_select());
+        assert_eq!(view.highlighted_column(), None);
+        assert_eq!(view.cursor_pos(), Some((1, 1)));
+    }
+
+    #[test]
+    fn records_sort_by_several_columns() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("a".to_string(), column("A"));
+        view.push_column("b".to_string(), column("B"));
+        for (a, b) in &[(2, 1), (1, 2), (2, 3), (1, 1)] {
+            view.push_record(vec![("a".to_string(), *a), ("b".to_string(), *b)].into_iter().collect());
+        }
+        let pairs = |view: &SpreadsheetView<u32>| -> Vec<(u32, u32)> {
+            view.records.iter().map(|record| (record["a"], record["b"])).collect()
+        };
+
+        view.sort_by_spec(&[("a", SortOrder::Ascending), ("nope", SortOrder::Ascending), ("b", SortOrder::Descending)]);
+        assert_eq!(pairs(&view), [(1, 2), (1, 1), (2, 3), (2, 1)]);
+        assert_eq!(view.sort_spec(), [("a".to_string(), SortOrder::Ascending), ("b".to_string(), SortOrder::Descending)]);
+        assert_eq!(view.sorted_by(), Some(("a", true)));
+
+        view.set_cursor_pos(1, 0);
+        view.on_event(Event::AltChar('O'));
+        assert_eq!(pairs(&view), [(1, 1), (1, 2), (2, 1), (2, 3)]);
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::AltChar('O'));
+        assert_eq!(pairs(&view), [(2, 1), (2, 3), (1, 1), (1, 2)]);
+        assert!(view.render_to_string(Vec2::new(20, 3)).starts_with("A ▼ │ B ▲\n"));
+
+        view.on_event(Event::AltChar('o'));
+        assert_eq!(view.sort_spec(), [("a".to_string(), SortOrder::Ascending)]);
+        view.remove_column("a");
+        assert!(view.sort_spec().is_empty());
+    }
+
+    #[test]
+    fn columns_compare_and_format_their_own_way() {
+        let mut view = number_view(3);
+        view.set_formatter("sq", |sq| format!("{} m²", sq));
+        view.set_comparator("n", |a, b| (a % 2).cmp(&(b % 2)).then(a.cmp(b)));
+
+        view.sort_records("n", true);
+        let ns: Vec<u32> = view.records.iter().map(|record| record["n"]).collect();
+        assert_eq!(ns, [2, 1, 3]);
+        assert_eq!(view.binary_search_by_key("n", &3), Some(Ok(2)));
+
+        let text = view.render_to_string(Vec2::new(20, 5));
+        assert!(text.contains("\n2   │   4 m²\n"));
+        assert!(view.search("9 m").unwrap());
+    }
+
+    #[test]
+    fn records_are_spliced() {
+        let record = |n: u32| vec![("n".to_string(), n)].into_iter().collect::<Record<u32>>();
+        let values = |view: &SpreadsheetView<u32>| view.iter_column("n").map(|n| *n.unwrap()).collect::<Vec<_>>();
+        let mut view = number_view(3);
+        view.set_undo_enabled(true);
+        view.set_cursor_pos(0, 2);
+        view.sort_records("n", true);
+
+        view.insert_record(1, record(10));
+        assert_eq!(values(&view), [1, 10, 2, 3]);
+        assert_eq!(view.sorted_by(), None);
+        view.insert_records(4, (20..22).map(record));
+        assert_eq!(values(&view), [1, 10, 2, 3, 20, 21]);
+
+        assert_eq!(view.replace_record(0, record(5)).unwrap()["sq"], 1);
+        assert_eq!(view.replace_record(9, record(5)), None);
+        assert_eq!(values(&view), [5, 10, 2, 3, 20, 21]);
+
+        let drained = view.drain_records(1..=2);
+        assert_eq!(drained.iter().map(|r| r["n"]).collect::<Vec<_>>(), [10, 2]);
+        assert_eq!(values(&view), [5, 3, 20, 21]);
+        assert_eq!(view.cursor_pos(), Some((0, 2)));
+
+        assert!(view.undo());
+        assert_eq!(values(&view), [5, 10, 2, 3, 20, 21]);
+        // Replacing the record is undone cell by cell.
+        assert!(view.undo());
+        assert!(view.undo());
+        assert_eq!(values(&view), [1, 10, 2, 3, 20, 21]);
+        assert!(view.undo());
+        assert_eq!(values(&view), [1, 10, 2, 3]);
+    }
+
+    #[test]
+    fn cells_are_read_and_changed() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut view = number_view(3);
+        view.set_on_data_changed({
+            let events = events.clone();
+            move |event| events.borrow_mut().push(event.clone())
+        });
+        view.sort_records("sq", true);
+
+        assert_eq!(view.get_cell(1, "sq"), Some(&4));
+        assert_eq!(view.get_cell(1, "nope"), None);
+        *view.get_cell_mut(1, "sq").unwrap() = 40;
+        assert_eq!(view.sorted_by(), None);
+
+        assert_eq!(view.set_cell(0, "sq", 10), Some(1));
+        assert_eq!(view.take_cell(2, "n"), Some(3));
+        assert_eq!(view.set_cell(5, "sq", 10), None);
+        assert_eq!(view.iter_column("sq").collect::<Vec<_>>(), [Some(&10), Some(&40), Some(&9)]);
+        assert_eq!(view.iter_column("n").collect::<Vec<_>>(), [Some(&1), Some(&2), None]);
+        assert_eq!(view.iter_rows().count(), 3);
+        assert_eq!(*events.borrow(), [
+            ChangeEvent::Sorted { key: "sq".to_string(), ascending: true },
+            ChangeEvent::CellChanged { row: 0, key: "sq".to_string() },
+            ChangeEvent::CellChanged { row: 2, key: "n".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn column_defs_build() {
+        let def = ColumnDef::new("Price")
+            .width(ColumnWidth::Bound(4, 10))
+            .align(HAlign::Right)
+            .selected(true);
+        assert_eq!(def.title(), "Price");
+        assert!(matches!(def.get_width(), ColumnWidth::Bound(4, 10)));
+        assert!(matches!(def.get_alignment(), HAlign::Right));
+        assert!(def.is_selected());
+
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("price".to_string(), def);
+        view.push_record(vec![("price".to_string(), 7)].into_iter().collect());
+        assert_eq!(view.render_to_string(Vec2::new(10, 3)), "Price\n──────────\n    7");
+    }
+
+    #[test]
+    fn columns_resize_within_their_bounds() {
+        let mut view = number_view(3);
+        view.set_column_width("sq", ColumnWidth::Max(8));
+        assert!(matches!(view.column_width("sq"), Some(ColumnWidth::Max(8))));
+        view.layout(Vec2::new(30, 5));
+        assert_eq!(view.column_widths, [1, 6]);
+
+        view.set_cursor_pos(1, 0);
+        assert!(view.on_event(Event::AltShift(Key::Right)).is_consumed());
+        assert!(view.on_event(Event::AltShift(Key::Right)).is_consumed());
+        assert!(!view.on_event(Event::AltShift(Key::Right)).is_consumed());
+        view.on_event(Event::AltShift(Key::Left));
+        view.layout(Vec2::new(30, 5));
+        assert_eq!(view.column_widths, [1, 7]);
+
+        let mouse = |event, x: usize, y: usize| Event::Mouse {
+            offset: Vec2::zero(),
+            position: Vec2::new(x, y),
+            event,
+        };
+        assert!(view.on_event(mouse(MouseEvent::Press(MouseButton::Left), 2, 0)).is_consumed());
+        view.on_event(mouse(MouseEvent::Hold(MouseButton::Left), 4, 0));
+        view.on_event(mouse(MouseEvent::Release(MouseButton::Left), 4, 0));
+        view.on_event(mouse(MouseEvent::Hold(MouseButton::Left), 9, 0));
+        view.layout(Vec2::new(30, 5));
+        assert_eq!(view.column_widths, [4, 7]);
+
+        view.set_column_width("sq", ColumnWidth::Auto);
+        view.layout(Vec2::new(30, 5));
+        assert_eq!(view.column_widths, [4, 6]);
+    }
+}
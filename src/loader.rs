@@ -0,0 +1,147 @@
+//! Loading records in the background, without blocking the UI thread.
+
+use std::fmt::Display;
+use std::sync::mpsc::{self, Sender, TryRecvError};
+
+use cursive::{CbSink, Printer};
+use cursive::theme::ColorStyle;
+
+use crate::{Record, SpreadsheetView};
+
+/// Sends records to the view it was made by, from any thread. The view adds
+/// them the next time it is laid out, and shows that it is loading until every
+/// loader made by `loader_sink` is dropped.
+pub struct RecordLoader<D> {
+    sender: Sender<Vec<Record<D>>>,
+    cb_sink: Option<CbSink>,
+}
+
+impl<D> RecordLoader<D> {
+    /// Wakes the event loop of the given Cursive root every time records are
+    /// sent, so that they are shown right away rather than on the next event.
+    pub fn with_cb_sink(mut self, cb_sink: CbSink) -> Self {
+        self.cb_sink = Some(cb_sink);
+        self
+    }
+
+    /// Sends a record to be added to the view. Returns `false` if the view is
+    /// gone, or has stopped loading.
+    pub fn send(&self, record: Record<D>) -> bool {
+        self.send_all(vec![record])
+    }
+
+    /// Sends records to be added to the view at once, like `send`.
+    pub fn send_all(&self, records: Vec<Record<D>>) -> bool {
+        if self.sender.send(records).is_err() {
+            return false;
+        }
+        if let Some(cb_sink) = self.cb_sink.as_ref() {
+            // The callback does nothing; the event loop redraws after it.
+            let _ = cb_sink.send(Box::new(|_| {}));
+        }
+        true
+    }
+}
+
+impl<D> Clone for RecordLoader<D> {
+    fn clone(&self) -> Self {
+        Self { sender: self.sender.clone(), cb_sink: self.cb_sink.clone() }
+    }
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Returns a loader that a background thread can stream records into this
+    /// view with. Until it and its clones are dropped, a footer at the bottom
+    /// of the view shows how many records have loaded so far. Replaces any
+    /// loader made before, dropping the records it has not delivered yet.
+    pub fn loader_sink(&mut self) -> RecordLoader<D> {
+        let (sender, receiver) = mpsc::channel();
+        self.loader = Some(receiver);
+        RecordLoader { sender, cb_sink: None }
+    }
+
+    /// Returns `true` if records are still being loaded through a loader.
+    pub fn is_loading(&self) -> bool {
+        self.loader.is_some()
+    }
+
+    /// Stops loading records, dropping the records not delivered yet. The
+    /// loaders find out the next time they send records.
+    pub fn stop_loading(&mut self) {
+        self.loader = None;
+    }
+
+    /// Adds the records the loaders have sent since the last layout.
+    pub(crate) fn receive_loaded_records(&mut self) {
+        let receiver = match self.loader.take() {
+            Some(receiver) => receiver,
+            None => return,
+        };
+
+        let mut done = false;
+        let mut records = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(batch) => records.extend(batch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    done = true;
+                    break;
+                },
+            }
+        }
+
+        if !records.is_empty() {
+            self.extend_records(records);
+        }
+        if !done {
+            self.loader = Some(receiver);
+        }
+    }
+
+    /// Draws the footer shown while records load, on the first line of the
+    /// given printer.
+    pub(crate) fn draw_loading_footer(&self, printer: &Printer) {
+        let len = self.records.len();
+        let text = format!("Loading… {} record{}", len, if len == 1 { "" } else { "s" });
+        let style = if self.monochrome { ColorStyle::primary() } else { ColorStyle::secondary() };
+        printer.with_color(style, |p| {
+            p.print_hline((0, 0), p.size.x, " ");
+            p.print((0, 0), &text);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    #[test]
+    fn loaders_stream_records() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        let loader = view.loader_sink();
+        assert!(view.is_loading());
+
+        let record = |n: u32| vec![("n".to_string(), n)].into_iter().collect();
+        loader.send(record(1));
+        view.layout(Vec2::new(20, 6));
+        assert_eq!(view.len_records(), 1);
+        assert!(view.render_to_string(Vec2::new(20, 6)).ends_with("\nLoading… 1 record"));
+
+        thread::spawn(move || {
+            loader.send_all((2..=4).map(record).collect());
+        }).join().unwrap();
+        view.layout(Vec2::new(20, 6));
+        assert_eq!(view.len_records(), 4);
+        assert!(!view.is_loading());
+        assert!(!view.render_to_string(Vec2::new(20, 6)).contains("Loading"));
+    }
+}
@@ -0,0 +1,186 @@
+//! What happens to values too wide for their column, and the heights of the
+//! records whose values take up several lines, by wrapping or by having line
+//! breaks of their own.
+
+use std::fmt::Display;
+
+use cursive::utils::lines::simple::make_lines;
+
+use crate::SpreadsheetView;
+use crate::text::ellipsize_end;
+
+/// How the values too wide for their column are shown.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Cuts off the end of the value. The default.
+    #[default]
+    Clip,
+    /// Replaces the end of the value with an ellipsis.
+    Truncate,
+    /// Breaks the value into lines at spaces where it can, making the record
+    /// as tall as it needs to be to show every line. Records from a source do
+    /// not grow, and are clipped instead.
+    Wrap,
+}
+
+/// Breaks `text` into the lines it takes up in a column of the given width.
+pub(crate) fn wrap_lines(text: &str, width: usize) -> Vec<&str> {
+    if width == 0 || text.is_empty() {
+        return vec![text];
+    }
+    make_lines(text, width).into_iter().map(|row| text[row.start..row.end].trim_end()).collect()
+}
+
+/// Returns the lines a cell of the given width shows its text on. Only the
+/// text of multi-line columns has line breaks, each starting a new line.
+pub(crate) fn display_lines(text: &str, width: usize, overflow: Overflow) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.split('\n') {
+        match overflow {
+            Overflow::Clip => lines.push(line.to_string()),
+            Overflow::Truncate => lines.push(ellipsize_end(line, width)),
+            Overflow::Wrap => lines.extend(wrap_lines(line, width).into_iter().map(str::to_string)),
+        }
+    }
+    lines
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Returns the line of a record its values start on, which is the middle
+    /// one for records as tall as `row_height`.
+    pub(crate) fn text_line_offset(&self) -> usize {
+        (self.row_height - 1) / 2
+    }
+
+    /// Sets the most lines a record grows to from values that wrap or have
+    /// line breaks, past which their lines are cut off. Records are never
+    /// shorter than `row_height`. Passing `None`, the default, lets records
+    /// grow to fit every line.
+    pub fn set_max_row_height(&mut self, max_row_height: Option<usize>) {
+        self.max_row_height = max_row_height.map(|height| height.max(1));
+    }
+
+    /// Returns the most lines a record grows to, if limited.
+    pub fn max_row_height(&self) -> Option<usize> {
+        self.max_row_height
+    }
+
+    /// Recalculates how many lines the records shown take up, from the values
+    /// of the columns that wrap at their current width or are multi-line.
+    /// Unless a column is either, every record is `row_height` lines tall and
+    /// nothing is stored.
+    pub(crate) fn compute_record_heights(&mut self) {
+        self.record_lines.clear();
+        let wrapped: Vec<(String, usize, Overflow)> = self.columns.iter()
+            .zip(&self.column_widths)
+            .filter(|((_, def), _)| def.overflow == Overflow::Wrap || def.multiline)
+            .map(|((key, def), &width)| (key.clone(), width, def.overflow))
+            .collect();
+        if wrapped.is_empty() || self.has_source() {
+            return;
+        }
+
+        let mut lines = 0;
+        self.record_lines.push(lines);
+        for position in 0..self.len_shown_records() {
+            let row = self.shown_row(position).unwrap_or(position);
+            let text_lines = wrapped.iter()
+                .map(|(key, width, overflow)| display_lines(&self.cell_text(row, key), *width, *overflow).len())
+                .max()
+                .unwrap_or(1);
+            let height = self.text_line_offset() + text_lines;
+            lines += self.max_row_height.map_or(height, |max| height.min(max)).max(self.row_height);
+            self.record_lines.push(lines);
+        }
+    }
+
+    /// Returns the number of lines taken up by the records shown before the
+    /// given position, not counting band separators.
+    pub(crate) fn lines_before(&self, position: usize) -> usize {
+        match self.record_lines.get(position) {
+            Some(&lines) => lines,
+            // Positions past those of the last layout are as tall as usual.
+            None => {
+                let counted = self.record_lines.len().saturating_sub(1);
+                self.record_lines.last().copied().unwrap_or(0) + (position - counted) * self.row_height
+            },
+        }
+    }
+
+    /// Returns the number of lines taken up by the record shown at the given
+    /// position.
+    pub(crate) fn record_height_at(&self, position: usize) -> usize {
+        self.lines_before(position + 1) - self.lines_before(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::event::{Event, Key};
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::{ColumnDef, ColumnWidth};
+
+    #[test]
+    fn long_values_overflow_as_set() {
+        let mut view = SpreadsheetView::<String>::new();
+        view.push_column("a".to_string(), ColumnDef::new("A").width(ColumnWidth::Fixed(6)));
+        view.push_column("b".to_string(), ColumnDef::new("B").width(ColumnWidth::Fixed(6)).overflow(Overflow::Truncate));
+        view.push_column("c".to_string(), ColumnDef::new("C").width(ColumnWidth::Fixed(6)).overflow(Overflow::Wrap));
+        let text = "one two three".to_string();
+        let record = |text: &str| vec![
+            ("a".to_string(), text.to_string()),
+            ("b".to_string(), text.to_string()),
+            ("c".to_string(), text.to_string()),
+        ].into_iter().collect();
+        view.extend_records(vec![record(&text), record("x")]);
+        assert_eq!(view.columns.get_index(2).unwrap().1.get_overflow(), Overflow::Wrap);
+
+        let size = Vec2::new(30, 8);
+        view.layout(size);
+        let lines: Vec<String> = view.render_to_string(size).lines().map(|line| line.trim_end().to_string()).collect();
+        assert_eq!(lines[2..6], ["one tw │ one t… │ one", "       │        │ two", "       │        │ three", "x      │ x      │ x"]);
+        assert_eq!(view.content_lines(), 4);
+        assert_eq!(view.row_to_line(1), 3);
+        assert_eq!(view.line_to_row(2), Some((0, 2)));
+        assert_eq!(view.line_to_row(3), Some((1, 0)));
+    }
+
+    #[test]
+    fn line_breaks_make_records_taller() {
+        let mut view = SpreadsheetView::<String>::new();
+        view.push_column("id".to_string(), ColumnDef::new("Id"));
+        view.push_column("notes".to_string(), ColumnDef::new("Notes").multiline(true));
+        let record = |id: &str, notes: &str| vec![
+            ("id".to_string(), id.to_string()),
+            ("notes".to_string(), notes.to_string()),
+        ].into_iter().collect();
+        view.extend_records(vec![record("1", "one\r\ntwo\nthree"), record("2", "\x1b"), record("3", "a\nb")]);
+
+        let size = Vec2::new(20, 6);
+        view.layout(size);
+        let lines: Vec<String> = view.render_to_string(size).lines().map(|line| line.trim_end().to_string()).collect();
+        let expected = ["Id │ Notes", "─", "1  │ one ", "   │ two ", "   │ three "];
+        assert!(lines.iter().zip(&expected).all(|(line, expected)| line.starts_with(expected)));
+        assert_eq!(view.content_lines(), 6);
+
+        // The cursor moves by records, scrolling by as many lines as needed.
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::Key(Key::Down));
+        view.on_event(Event::Key(Key::Down));
+        assert_eq!(view.cursor_pos(), Some((0, 2)));
+        let text = view.render_to_string(size);
+        assert!(text.contains("2  │ ␛"));
+        assert!(text.contains("3  │ a"));
+        view.on_event(Event::Key(Key::PageUp));
+        assert_eq!(view.cursor_pos(), Some((0, 0)));
+
+        view.set_max_row_height(Some(2));
+        view.layout(size);
+        assert_eq!(view.content_lines(), 5);
+        assert!(!view.render_to_string(size).contains("three"));
+    }
+}
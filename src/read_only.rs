@@ -0,0 +1,101 @@
+//! Columns and cells that cannot be edited, in views that can be.
+
+use std::fmt::Display;
+
+use crate::SpreadsheetView;
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Sets whether the cells of the column with the specified key cannot be
+    /// edited, like `ColumnDef::read_only`.
+    pub fn set_column_read_only(&mut self, key: &str, read_only: bool) {
+        if let Some(def) = self.columns.get_mut(key) {
+            def.read_only = read_only;
+        }
+    }
+
+    /// Sets whether the cell at the given row and column cannot be edited,
+    /// even if the view can be. The editor does not open on such cells, pastes
+    /// and cuts leave them as they are, and they are drawn dimmed. The mark
+    /// follows the record when the records are sorted.
+    pub fn set_cell_read_only(&mut self, row: usize, key: &str, read_only: bool) {
+        if read_only {
+            if row < self.records.len() && self.columns.contains_key(key) {
+                self.read_only_cells.insert((row, key.to_string()));
+            }
+        }
+        else {
+            self.read_only_cells.remove(&(row, key.to_string()));
+        }
+    }
+
+    /// Returns `true` if the cell at the given row and column cannot be
+    /// edited by itself or because its column is read-only, regardless of
+    /// whether the view can be.
+    pub fn is_cell_read_only(&self, row: usize, key: &str) -> bool {
+        self.columns.get(key).is_some_and(|def| def.read_only)
+            || (!self.read_only_cells.is_empty() && self.read_only_cells.contains(&(row, key.to_string())))
+    }
+
+    /// Returns `true` if the cell under the cursor cannot be edited.
+    pub(crate) fn cursor_cell_read_only(&self) -> bool {
+        let key = self.cursor_pos.and_then(|(x, y)| Some((self.columns.get_index(x)?.0, y)));
+        key.is_some_and(|(key, y)| self.is_cell_read_only(y, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::event::{Event, Key};
+    use cursive::view::View;
+
+    use crate::ColumnDef;
+
+    fn names() -> SpreadsheetView<String> {
+        let mut view = SpreadsheetView::new();
+        view.push_column("id".to_string(), ColumnDef::new("ID").read_only(true));
+        view.push_column("name".to_string(), ColumnDef::new("Name"));
+        for (id, name) in [("1", "ann"), ("2", "bob")] {
+            view.push_record(vec![("id".to_string(), id.to_string()), ("name".to_string(), name.to_string())].into_iter().collect());
+        }
+        view.set_read_only(false);
+        view.set_from_str_parser();
+        view
+    }
+
+    #[test]
+    fn read_only_cells_refuse_edits() {
+        let mut view = names();
+        view.set_cursor_pos(0, 0);
+        view.on_event(Event::Key(Key::Enter));
+        assert!(!view.is_editing());
+
+        view.set_cell_read_only(1, "name", true);
+        assert!(view.is_cell_read_only(1, "name"));
+        assert!(view.set_cell_text(1, "name", "cy").is_err());
+        view.set_cursor_pos(1, 1);
+        view.on_event(Event::Key(Key::Enter));
+        assert!(!view.is_editing());
+
+        // The mark follows the record, and pastes go around it.
+        view.sort_records("name", false);
+        assert!(view.is_cell_read_only(0, "name"));
+        view.set_cursor_pos(0, 0);
+        view.paste_text("9\tdee\n8\teve").unwrap();
+        let cells: Vec<(&str, &str)> = (0..2).map(|row| {
+            (view.get_cell(row, "id").unwrap().as_str(), view.get_cell(row, "name").unwrap().as_str())
+        }).collect();
+        // The batch sorts the records again.
+        assert_eq!(cells, [("1", "eve"), ("2", "bob")]);
+        assert!(view.is_cell_read_only(1, "name"));
+
+        let def = view.columns["id"].clone();
+        assert_eq!(view.content_style(0, "id", &def, Default::default()), view.style.read_only);
+        view.set_column_read_only("id", false);
+        view.set_cell_read_only(1, "name", false);
+        view.set_cursor_pos(1, 0);
+        view.on_event(Event::Key(Key::Enter));
+        assert!(view.is_editing());
+    }
+}
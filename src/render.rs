@@ -0,0 +1,129 @@
+//! Drawing views into memory instead of onto a terminal.
+
+use std::cell::RefCell;
+
+use cursive::Printer;
+use cursive::backend::Backend;
+use cursive::event::Event;
+use cursive::theme::{self, Color, ColorPair, Effect};
+use cursive::vec::Vec2;
+use cursive::view::View;
+use unicode_width::UnicodeWidthChar;
+
+/// A backend that prints into a grid of cells held in memory.
+struct BufferBackend {
+    size: Vec2,
+    /// Each cell holds the character printed there, along with any following
+    /// zero-width characters. The cells covered by the right half of a wide
+    /// character hold an empty string.
+    cells: RefCell<Vec<Vec<String>>>,
+    color: RefCell<ColorPair>,
+}
+
+impl BufferBackend {
+    fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            cells: RefCell::new(vec![vec![" ".to_string(); size.x]; size.y]),
+            color: RefCell::new(ColorPair {
+                front: Color::TerminalDefault,
+                back: Color::TerminalDefault,
+            }),
+        }
+    }
+
+    /// Returns the contents of the buffer, one line per row of cells, with
+    /// trailing whitespace removed.
+    fn into_string(self) -> String {
+        self.cells.into_inner().iter()
+            .map(|row| row.concat().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Backend for BufferBackend {
+    fn poll_event(&mut self) -> Option<Event> {
+        None
+    }
+
+    fn finish(&mut self) {}
+
+    fn refresh(&mut self) {}
+
+    fn has_colors(&self) -> bool {
+        false
+    }
+
+    fn screen_size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn print_at(&self, pos: Vec2, text: &str) {
+        let mut cells = self.cells.borrow_mut();
+        let row = match cells.get_mut(pos.y) {
+            Some(row) => row,
+            None => return,
+        };
+
+        let mut x = pos.x;
+        for c in text.chars() {
+            let width = c.width().unwrap_or(0);
+            if width == 0 {
+                if let Some(cell) = x.checked_sub(1).and_then(|prev| row.get_mut(prev)) {
+                    cell.push(c);
+                }
+                continue;
+            }
+
+            if x + width > row.len() {
+                break;
+            }
+
+            row[x] = c.to_string();
+            for cell in &mut row[x + 1..x + width] {
+                cell.clear();
+            }
+
+            x += width;
+        }
+    }
+
+    fn clear(&self, _color: Color) {
+        for row in self.cells.borrow_mut().iter_mut() {
+            for cell in row.iter_mut() {
+                *cell = " ".to_string();
+            }
+        }
+    }
+
+    fn set_color(&self, colors: ColorPair) -> ColorPair {
+        self.color.replace(colors)
+    }
+
+    fn set_effect(&self, _effect: Effect) {}
+
+    fn unset_effect(&self, _effect: Effect) {}
+
+    fn name(&self) -> &str {
+        "buffer"
+    }
+}
+
+/// Lays out and draws `view` at the given size using the default theme, and
+/// returns what was drawn as plain text, one line per row of the screen.
+pub(crate) fn render_to_string<V: View>(view: &mut V, size: Vec2) -> String {
+    view.layout(size);
+    draw_to_string(view, size, true)
+}
+
+/// Draws `view` as it was last laid out, at the given size and with the given
+/// focus, and returns what was drawn as plain text.
+pub(crate) fn draw_to_string<V: View>(view: &V, size: Vec2, focused: bool) -> String {
+    let backend = BufferBackend::new(size);
+    let theme = theme::load_default();
+
+    view.draw(&Printer::new(size, &theme, &backend).focused(focused));
+
+    backend.into_string()
+}
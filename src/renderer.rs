@@ -0,0 +1,120 @@
+//! Drawing the cells of a column with something other than their text, e.g.
+//! as progress bars, sparklines, checkboxes, or color swatches.
+
+use std::fmt::Display;
+use std::rc::Rc;
+
+use cursive::Printer;
+
+use crate::SpreadsheetView;
+use crate::text::{display_text, truncate};
+
+/// Draws the values of a column in place of their text. The printer is
+/// already styled for the cell, and is one line high and `width` columns
+/// wide; `selected` is `true` if the cell is under the cursor or selected.
+pub trait CellRenderer<D> {
+    /// Draws a value on the first line of its cell.
+    fn draw(&self, printer: &Printer, width: usize, selected: bool, value: &D);
+}
+
+/// Draws values as their `Display`, cut off to the width of the cell, which
+/// is what cells without a renderer show.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DisplayRenderer;
+
+impl<D: Display> CellRenderer<D> for DisplayRenderer {
+    fn draw(&self, printer: &Printer, width: usize, _selected: bool, value: &D) {
+        printer.print((0, 0), truncate(&display_text(value), width));
+    }
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Draws the values of the column with the specified key with the given
+    /// renderer, in place of their text. Cells holding formulas, cells being
+    /// edited, and empty cells are still drawn as text, and the width of the
+    /// column is still fitted to the text of its values.
+    pub fn set_renderer<R>(&mut self, key: &str, renderer: R)
+    where
+        R: CellRenderer<D> + 'static,
+    {
+        self.renderers.insert(key.to_string(), Rc::new(renderer));
+    }
+
+    /// Draws the values of the column with the specified key as text again,
+    /// undoing `set_renderer`.
+    pub fn clear_renderer(&mut self, key: &str) {
+        self.renderers.remove(key);
+    }
+
+    /// Draws the value of a cell with the renderer of its column. Returns
+    /// `false` if the cell is to be drawn as text instead.
+    pub(crate) fn draw_rendered_cell(&self, printer: &Printer, row: usize, key: &str, width: usize, selected: bool) -> bool {
+        let renderer = match self.renderers.get(key) {
+            Some(renderer) => renderer,
+            None => return false,
+        };
+
+        let printer = &printer.cropped((width, 1));
+        if let Some(source) = self.source.as_ref() {
+            return match source.get(row, key) {
+                Some(value) => {
+                    printer.print_hline((0, 0), width, " ");
+                    renderer.draw(printer, width, selected, &value);
+                    true
+                },
+                None => false,
+            };
+        }
+
+        match self.records.get(row).and_then(|record| record.get(key)) {
+            Some(value) if self.formula_at(row, key).is_none() => {
+                printer.print_hline((0, 0), width, " ");
+                renderer.draw(printer, width, selected, value);
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::vec::Vec2;
+
+    use crate::{ColumnDef, ColumnWidth};
+
+    /// Draws percentages as bars of `#`.
+    struct ProgressBar;
+
+    impl CellRenderer<u32> for ProgressBar {
+        fn draw(&self, printer: &Printer, width: usize, selected: bool, value: &u32) {
+            let filled = width * (*value).min(100) as usize / 100;
+            printer.print_hline((0, 0), filled, if selected { "=" } else { "#" });
+        }
+    }
+
+    #[test]
+    fn renderers_draw_cells() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("done".to_string(), ColumnDef::new("Done").width(ColumnWidth::Fixed(10)));
+        view.push_column("left".to_string(), ColumnDef::new("Left"));
+        for done in [50, 100] {
+            view.push_record(vec![("done".to_string(), done), ("left".to_string(), 100 - done)].into_iter().collect());
+        }
+        view.set_renderer("done", ProgressBar);
+        view.set_cursor_pos(1, 1);
+
+        let text = view.render_to_string(Vec2::new(20, 5));
+        assert!(text.contains("\n#####      │ 50\n########## │ 0"));
+
+        view.set_cursor_pos(0, 1);
+        assert!(view.render_to_string(Vec2::new(20, 5)).contains("\n========== │ 0"));
+
+        view.set_renderer("done", DisplayRenderer);
+        assert!(view.render_to_string(Vec2::new(20, 5)).contains("\n50         │ 50\n"));
+        view.clear_renderer("done");
+        assert!(view.render_to_string(Vec2::new(20, 5)).contains("\n100        │ 0"));
+    }
+}
@@ -0,0 +1,264 @@
+//! Searching the text of the cells.
+
+use std::fmt::Display;
+
+use cursive::Printer;
+use cursive::event::{Event, EventResult, Key};
+use cursive::vec::Vec2;
+use cursive::view::View;
+use cursive::views::EditView;
+
+use crate::SpreadsheetView;
+
+/// A search for cells, as typed by the user.
+pub(crate) struct Search {
+    query: String,
+    pattern: Pattern,
+}
+
+/// What cells are searched for.
+enum Pattern {
+    /// Cells containing the given text, which is lowercase if `ignore_case`.
+    Text { needle: String, ignore_case: bool },
+    /// Cells with text matching the given regular expression.
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Search {
+    fn new(query: &str, ignore_case: bool, use_regex: bool) -> Result<Self, String> {
+        #[cfg(feature = "regex")]
+        let pattern = if use_regex {
+            regex::RegexBuilder::new(query)
+                .case_insensitive(ignore_case)
+                .build()
+                .map(Pattern::Regex)
+                .map_err(|err| err.to_string())?
+        }
+        else {
+            Pattern::text(query, ignore_case)
+        };
+        #[cfg(not(feature = "regex"))]
+        let pattern = {
+            let _ = use_regex;
+            Pattern::text(query, ignore_case)
+        };
+
+        Ok(Self { query: query.to_string(), pattern })
+    }
+
+    /// Returns `true` if the cell with the given text is searched for.
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.pattern {
+            Pattern::Text { needle, ignore_case: true } => text.to_lowercase().contains(needle.as_str()),
+            Pattern::Text { needle, ignore_case: false } => text.contains(needle.as_str()),
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+impl Pattern {
+    fn text(query: &str, ignore_case: bool) -> Self {
+        let needle = if ignore_case { query.to_lowercase() } else { query.to_string() };
+        Self::Text { needle, ignore_case }
+    }
+}
+
+/// The prompt the user types a search into, drawn over the last line of the
+/// view.
+pub(crate) struct SearchPrompt {
+    edit_view: EditView,
+    /// The position of the cursor when the prompt was opened, which matches
+    /// are looked for from as the search is typed.
+    origin: Option<(usize, usize)>,
+}
+
+impl SearchPrompt {
+    pub fn draw(&self, printer: &Printer) {
+        let y = printer.size.y.saturating_sub(1);
+        printer.print_hline((0, y), printer.size.x, " ");
+        printer.print((0, y), "/");
+        self.edit_view.draw(&printer.offset((1, y)).cropped((printer.size.x.saturating_sub(1), 1)));
+    }
+
+    pub fn layout(&mut self, size: Vec2) {
+        self.edit_view.layout(Vec2::new(size.x.saturating_sub(1), 1));
+    }
+}
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Sets whether searches ignore the difference between uppercase and
+    /// lowercase letters. Off by default.
+    pub fn set_search_ignore_case(&mut self, ignore_case: bool) {
+        self.search_ignore_case = ignore_case;
+    }
+
+    /// Returns `true` if searches ignore case.
+    pub fn search_ignore_case(&self) -> bool {
+        self.search_ignore_case
+    }
+
+    /// Sets whether searches are regular expressions, rather than text to
+    /// look for in the cells. Off by default.
+    #[cfg(feature = "regex")]
+    pub fn set_search_regex(&mut self, regex: bool) {
+        self.search_regex = regex;
+    }
+
+    /// Returns `true` if searches are regular expressions.
+    #[cfg(feature = "regex")]
+    pub fn search_regex(&self) -> bool {
+        self.search_regex
+    }
+
+    /// Searches for the cells containing `query`, highlighting them, and moves
+    /// the cursor to the first of them at or after the cursor. Returns whether
+    /// any cell matches, or the error in `query` if it is not a valid regular
+    /// expression. An empty query clears the search.
+    pub fn search(&mut self, query: &str) -> Result<bool, String> {
+        let cursor = self.cursor_pos;
+        self.search_from(query, cursor)
+    }
+
+    fn search_from(&mut self, query: &str, origin: Option<(usize, usize)>) -> Result<bool, String> {
+        if query.is_empty() {
+            self.clear_search();
+            return Ok(false);
+        }
+
+        #[cfg(feature = "regex")]
+        let use_regex = self.search_regex;
+        #[cfg(not(feature = "regex"))]
+        let use_regex = false;
+
+        self.search = Some(Search::new(query, self.search_ignore_case, use_regex)?);
+
+        match self.find_match(origin, true, true) {
+            Some((x, y)) => {
+                self.jump_to_cell(x, y);
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Clears the search, removing the highlighting of the matching cells.
+    pub fn clear_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Returns the current search, if any.
+    pub fn search_query(&self) -> Option<&str> {
+        self.search.as_ref().map(|search| search.query.as_str())
+    }
+
+    /// Moves the cursor to the next cell matching the search, in row and then
+    /// column order, wrapping around after the last cell. Returns `false` if
+    /// there is no search, or no cell matches it.
+    pub fn search_next(&mut self) -> bool {
+        self.step_search(true)
+    }
+
+    /// Moves the cursor to the previous cell matching the search, like
+    /// `search_next`.
+    pub fn search_previous(&mut self) -> bool {
+        self.step_search(false)
+    }
+
+    fn step_search(&mut self, forward: bool) -> bool {
+        match self.find_match(self.cursor_pos, forward, false) {
+            Some((x, y)) => {
+                self.jump_to_cell(x, y);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns `true` if the cell with the given text matches the search.
+    pub(crate) fn is_search_match(&self, text: &str) -> bool {
+        self.search.as_ref().is_some_and(|search| search.is_match(text))
+    }
+
+    /// Returns the `(column, row)` position of the first cell matching the
+    /// search going forward or backward from `from`, wrapping around. Only
+    /// records shown are searched. `from` itself is searched first if
+    /// `include_from`, or last otherwise.
+    fn find_match(&self, from: Option<(usize, usize)>, forward: bool, include_from: bool) -> Option<(usize, usize)> {
+        let search = self.search.as_ref()?;
+        let num_cols = self.columns.len();
+        let num_cells = num_cols * self.len_shown_records();
+        if num_cells == 0 {
+            return None;
+        }
+
+        let start = from.map_or(0, |(x, y)| self.shown_position(y).min(self.len_shown_records() - 1) * num_cols + x);
+        let steps = if include_from { 0..num_cells } else { 1..num_cells + 1 };
+
+        steps
+            .map(|step| if forward { (start + step) % num_cells } else { (start + num_cells - step % num_cells) % num_cells })
+            .map(|cell| (cell % num_cols, self.shown_row(cell / num_cols).unwrap_or_default()))
+            .find(|&(x, y)| {
+                let key = self.columns.get_index(x).map(|(key, _)| key.as_str()).unwrap_or_default();
+                search.is_match(&self.cell_text(y, key))
+            })
+    }
+
+    /// Moves the cursor to the given cell, scrolling it into view.
+    fn jump_to_cell(&mut self, x: usize, y: usize) {
+        self.set_cursor_pos(x, y);
+        self.scroll_to_row(y);
+        self.scroll_to_column_index(x);
+    }
+
+    /// Opens the search prompt, searching as the user types.
+    pub(crate) fn open_search_prompt(&mut self) -> EventResult {
+        if self.columns.is_empty() {
+            return EventResult::Ignored;
+        }
+
+        let mut edit_view = EditView::new();
+        edit_view.layout(Vec2::new(self.last_size.x.saturating_sub(1), 1));
+        self.search_prompt = Some(SearchPrompt { edit_view, origin: self.cursor_pos });
+        EventResult::Consumed(None)
+    }
+
+    /// Handles an event while the search prompt is open. Enter closes the
+    /// prompt, keeping the search, and Esc closes it, clearing the search and
+    /// putting the cursor back.
+    pub(crate) fn on_search_prompt_event(&mut self, event: Event) -> EventResult {
+        let prompt = match self.search_prompt.as_mut() {
+            Some(prompt) => prompt,
+            None => return EventResult::Ignored,
+        };
+
+        match event {
+            Event::Key(Key::Enter) => {
+                self.search_prompt = None;
+            },
+            Event::Key(Key::Esc) => {
+                let origin = prompt.origin;
+                self.search_prompt = None;
+                self.clear_search();
+                if let Some((x, y)) = origin {
+                    self.jump_to_cell(x, y);
+                }
+            },
+            event => {
+                let old_query = prompt.edit_view.get_content();
+                let result = prompt.edit_view.on_event(event);
+                let query = prompt.edit_view.get_content();
+
+                if query != old_query {
+                    let origin = prompt.origin;
+                    // Regular expressions are invalid while half typed.
+                    let _ = self.search_from(&query, origin);
+                }
+                return result;
+            },
+        }
+
+        EventResult::Consumed(None)
+    }
+}
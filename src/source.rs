@@ -0,0 +1,181 @@
+//! Records that live outside of the view, for datasets too big to hold as
+//! `Record`s.
+
+use std::fmt::Display;
+use std::ops::Range;
+
+use crate::{SortOrder, SpreadsheetView};
+
+/// Where a view gets its records from in place of holding them, e.g. a table
+/// in a database or a file too large to read whole. The view only asks for the
+/// cells it draws, so memory use stays flat and drawing takes time in
+/// proportion to the height of the view rather than the number of records.
+///
+/// Cells from a source are read-only, and the filter of the view does not
+/// apply to them.
+pub trait SpreadsheetSource<D> {
+    /// Returns the number of records.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there are no records.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value of the cell of the record at the given index in the
+    /// column with the given key, if it has one.
+    fn get(&self, row: usize, key: &str) -> Option<D>;
+
+    /// Sorts the records by the column with the given key, e.g. by running the
+    /// query again with a different order. Returns `false` if the source
+    /// cannot sort by that column, which the default does for every column.
+    fn sort(&mut self, key: &str, ascending: bool) -> bool {
+        let _ = (key, ascending);
+        false
+    }
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Creates a view that shows the records of the given source, rather than
+    /// records of its own.
+    pub fn with_source<S>(source: S) -> Self
+    where
+        S: SpreadsheetSource<D> + 'static,
+    {
+        let mut view = Self::new();
+        view.set_source(source);
+        view
+    }
+
+    /// Shows the records of the given source in place of the records of this
+    /// view, which are removed. Sets the cursor to the first cell.
+    pub fn set_source<S>(&mut self, source: S)
+    where
+        S: SpreadsheetSource<D> + 'static,
+    {
+        self.clear_records();
+        self.source = Some(Box::new(source));
+        self.sort_spec.clear();
+        self.set_cursor_pos(0, 0);
+    }
+
+    /// Stops showing the records of the source set with `set_source`, if any.
+    pub fn clear_source(&mut self) {
+        if self.source.take().is_some() {
+            self.sort_spec.clear();
+            self.set_cursor_pos(0, 0);
+        }
+    }
+
+    /// Returns `true` if the records come from a source.
+    pub fn has_source(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Returns the number of records, whether they come from a source or are
+    /// held by the view.
+    pub(crate) fn num_records(&self) -> usize {
+        self.source.as_ref().map_or(self.records.len(), |source| source.len())
+    }
+
+    /// Sorts the records of the source by the column with the given key, if
+    /// the source can. Only sorting by one column is left to sources. Returns
+    /// `false` if the source cannot sort by that column.
+    pub(crate) fn sort_source(&mut self, key: &str, ascending: bool) -> bool {
+        let sorted = self.source.as_mut().is_some_and(|source| source.sort(key, ascending));
+        if sorted {
+            self.sort_spec = vec![(key.to_string(), SortOrder::from_ascending(ascending))];
+        }
+        sorted
+    }
+
+    /// Returns the indices of the records of the source in view as of the last
+    /// layout, which are all the column widths are fitted to.
+    pub(crate) fn source_rows_in_view(&self) -> Range<usize> {
+        let first = self.scroll_base.start_line / self.row_height;
+        let last = (self.scroll_base.start_line + self.scroll_base.view_height) / self.row_height + 1;
+        first.min(self.num_records())..last.min(self.num_records())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use cursive::event::{Event, Key};
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::{ColumnDef, SelectionMode};
+
+    /// The squares of the first million numbers, counting the cells asked for.
+    struct Squares {
+        reads: Rc<Cell<usize>>,
+        descending: bool,
+    }
+
+    impl SpreadsheetSource<u64> for Squares {
+        fn len(&self) -> usize {
+            1_000_000
+        }
+
+        fn get(&self, row: usize, key: &str) -> Option<u64> {
+            self.reads.set(self.reads.get() + 1);
+            let n = if self.descending { 1_000_000 - row as u64 } else { row as u64 + 1 };
+            match key {
+                "n" => Some(n),
+                "sq" => Some(n * n),
+                _ => None,
+            }
+        }
+
+        fn sort(&mut self, key: &str, ascending: bool) -> bool {
+            self.descending = !ascending;
+            key == "n" || key == "sq"
+        }
+    }
+
+    #[test]
+    fn sources_are_read_as_drawn() {
+        let reads = Rc::new(Cell::new(0));
+        let mut view = SpreadsheetView::with_source(Squares { reads: reads.clone(), descending: false });
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        view.push_column("sq".to_string(), ColumnDef::new("Square"));
+        assert_eq!(view.len_records(), 1_000_000);
+
+        view.layout(Vec2::new(30, 5));
+        let text = view.render_to_string(Vec2::new(30, 5));
+        assert!(text.starts_with("N │ Square\n"));
+        assert!(text.contains("\n3 │ 9 "));
+        assert!(reads.get() < 100);
+
+        view.sort_records("sq", false);
+        assert_eq!(view.sorted_by(), Some(("sq", false)));
+        assert!(view.render_to_string(Vec2::new(30, 3)).contains("\n1000000 │ 1000000000000 "));
+    }
+
+    #[test]
+    fn source_columns_select_whole() {
+        struct Rows;
+
+        impl SpreadsheetSource<u32> for Rows {
+            fn len(&self) -> usize {
+                4
+            }
+
+            fn get(&self, row: usize, _key: &str) -> Option<u32> {
+                Some(row as u32)
+            }
+        }
+
+        let mut view = SpreadsheetView::with_source(Rows);
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        view.set_selection_mode(SelectionMode::Column);
+        view.set_cursor_pos(0, 1);
+        view.on_event(Event::Key(Key::Ins));
+        assert_eq!(view.selected_rows(), [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,163 @@
+//! Snapshots of the state of a view, to save a session and restore it later.
+//!
+//! With the `serde` feature, snapshots and column definitions can be
+//! serialized and deserialized.
+
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use cursive::align::HAlign;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{ColumnDef, Record, SortOrder, SpreadsheetView};
+#[cfg(feature = "serde")]
+use crate::ColumnWidth;
+
+/// The columns, records, sort, and cursor of a view, as taken by `state` and
+/// put back by `restore_state`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpreadsheetState<D> {
+    /// The keys and definitions of the columns, in order.
+    pub columns: Vec<(String, ColumnDef)>,
+    /// The records, in the order they are shown.
+    pub records: Vec<Record<D>>,
+    /// The keys of the columns the records are sorted by, with the order of
+    /// each, most significant first.
+    pub sort_spec: Vec<(String, SortOrder)>,
+    /// The position of the cursor, as `(column, row)`.
+    pub cursor: Option<(usize, usize)>,
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Takes a snapshot of the columns, records, sort, and cursor of this view.
+    pub fn state(&self) -> SpreadsheetState<D>
+    where
+        D: Clone,
+    {
+        SpreadsheetState {
+            columns: self.columns.iter().map(|(key, def)| (key.clone(), def.clone())).collect(),
+            records: self.records.clone(),
+            sort_spec: self.sort_spec.clone(),
+            cursor: self.cursor_pos,
+        }
+    }
+
+    /// Puts back the columns, records, sort, and cursor of a snapshot taken by
+    /// `state`, replacing those of this view.
+    pub fn restore_state(&mut self, state: SpreadsheetState<D>) {
+        self.set_columns(state.columns);
+        self.sort_spec.clear();
+        self.set_records(state.records);
+        self.sort_spec = state.sort_spec;
+        self.keep_sort_by_columns();
+        match state.cursor {
+            Some((x, y)) => self.set_cursor_pos(x, y),
+            None => self.cursor_pos = None,
+        }
+    }
+}
+
+/// The parts of a column definition that are saved.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SavedColumnDef {
+    title: String,
+    width: ColumnWidth,
+    alignment: SavedAlignment,
+    selected: bool,
+    grow_only: bool,
+    resized_width: Option<usize>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SavedAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Saves the title, width, alignment, and flags of a column. Its style and its
+/// data are not saved.
+#[cfg(feature = "serde")]
+impl Serialize for ColumnDef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let alignment = match self.alignment {
+            HAlign::Left => SavedAlignment::Left,
+            HAlign::Center => SavedAlignment::Center,
+            HAlign::Right => SavedAlignment::Right,
+        };
+
+        SavedColumnDef {
+            title: self.title.clone(),
+            width: self.width,
+            alignment,
+            selected: self.selected,
+            grow_only: self.grow_only,
+            resized_width: self.resized_width,
+        }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for ColumnDef {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let saved = SavedColumnDef::deserialize(deserializer)?;
+        let alignment = match saved.alignment {
+            SavedAlignment::Left => HAlign::Left,
+            SavedAlignment::Center => HAlign::Center,
+            SavedAlignment::Right => HAlign::Right,
+        };
+
+        let mut def = ColumnDef::new(&saved.title).width(saved.width).align(alignment).selected(saved.selected);
+        def.grow_only = saved.grow_only;
+        def.resized_width = saved.resized_width;
+        Ok(def)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ColumnWidth;
+
+    fn squares_view() -> SpreadsheetView<u32> {
+        let mut view = SpreadsheetView::new();
+        view.push_column("n".to_string(), ColumnDef::new("N").width(ColumnWidth::Min(3)));
+        view.push_column("sq".to_string(), ColumnDef::new("Square"));
+        for n in 1..=3 {
+            view.push_record(vec![("n".to_string(), n), ("sq".to_string(), n * n)].into_iter().collect());
+        }
+        view.sort_records("n", false);
+        view.set_cursor_pos(1, 2);
+        view
+    }
+
+    #[test]
+    fn state_restores_views() {
+        let state = squares_view().state();
+        let mut view = SpreadsheetView::new();
+        view.restore_state(state);
+
+        assert_eq!(view.column_keys(), ["n", "sq"]);
+        assert!(matches!(view.columns["n"].get_width(), ColumnWidth::Min(3)));
+        assert_eq!(view.records[0]["sq"], 9);
+        assert_eq!(view.sorted_by(), Some(("n", false)));
+        assert_eq!(view.cursor_pos(), Some((1, 2)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn state_round_trips_through_json() {
+        let json = serde_json::to_string(&squares_view().state()).unwrap();
+        let state: SpreadsheetState<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.columns[1].1.title(), "Square");
+        assert_eq!(state.records[2]["n"], 1);
+        assert_eq!(state.sort_spec, [("n".to_string(), SortOrder::Descending)]);
+        assert_eq!(state.cursor, Some((1, 2)));
+    }
+}
@@ -0,0 +1,121 @@
+//! The status bar at the bottom of a view, describing the cell under the
+//! cursor and the selection like the status bar of desktop spreadsheets.
+
+use std::fmt::Display;
+
+use cursive::Printer;
+use cursive::theme::ColorStyle;
+
+use crate::{Aggregate, SpreadsheetView};
+use crate::formula::{FormulaValue, Sheet};
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Sets whether a status bar at the bottom of the view shows the address
+    /// of the cell under the cursor, the number of selected cells, and the
+    /// aggregates set with `set_status_aggregates` of the values among them.
+    /// Off by default.
+    pub fn set_status_bar(&mut self, status_bar: bool) {
+        self.status_bar = status_bar;
+    }
+
+    /// Builder version of `set_status_bar`.
+    pub fn status_bar(mut self, status_bar: bool) -> Self {
+        self.set_status_bar(status_bar);
+        self
+    }
+
+    /// Returns `true` if the status bar is shown.
+    pub fn has_status_bar(&self) -> bool {
+        self.status_bar
+    }
+
+    /// Sets the aggregates of the selected numbers shown in the status bar, in
+    /// order. The sum and the average by default.
+    pub fn set_status_aggregates(&mut self, aggregates: &[Aggregate]) {
+        self.status_aggregates = aggregates.to_vec();
+    }
+
+    /// Returns the text of the status bar, e.g. `R12:C3 Price  4 selected
+    /// Sum: 10  Average: 2.5`. Cells of records hidden by the filter do not
+    /// count, and the aggregates are left out unless there are numbers among
+    /// the selected cells.
+    pub fn status_text(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some((x, y)) = self.cursor_pos {
+            let title = self.columns.get_index(x).map_or("", |(_, def)| def.title.as_str());
+            parts.push(format!("R{}:C{} {}", y + 1, x + 1, title).trim_end().to_string());
+        }
+
+        let mut cells: Vec<(usize, usize)> = self.selected_cells.iter()
+            .copied()
+            .filter(|&(_, y)| self.is_record_shown(y))
+            .collect();
+        if !cells.is_empty() {
+            // In reading order, so that sums add up the same on every draw.
+            cells.sort_unstable_by_key(|&(x, y)| (y, x));
+            parts.push(format!("{} selected", cells.len()));
+
+            let evaluation = self.evaluation();
+            let values: Vec<FormulaValue> = cells.into_iter()
+                .map(|cell| evaluation.value(cell))
+                .filter(|value| *value != FormulaValue::Empty)
+                .collect();
+            if values.iter().any(|value| matches!(value, FormulaValue::Number(_))) {
+                for aggregate in &self.status_aggregates {
+                    parts.push(format!("{}: {}", aggregate.label(), aggregate.compute(&values)));
+                }
+            }
+        }
+
+        parts.join("  ")
+    }
+
+    /// Draws the status bar, on the first line of the given printer.
+    pub(crate) fn draw_status_bar(&self, printer: &Printer) {
+        let style = if self.monochrome { ColorStyle::primary() } else { ColorStyle::secondary() };
+        printer.with_color(style, |p| {
+            p.print_hline((0, 0), p.size.x, " ");
+            p.print((0, 0), &self.status_text());
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::event::{Event, Key};
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    #[test]
+    fn status_bar_sums_up_selection() {
+        let mut view = SpreadsheetView::<String>::new().status_bar(true);
+        view.push_column("name".to_string(), ColumnDef::new("Name"));
+        view.push_column("price".to_string(), ColumnDef::new("Price"));
+        for (name, price) in [("tea", "2"), ("cake", "3.5"), ("jam", "")] {
+            view.push_record(vec![
+                ("name".to_string(), name.to_string()),
+                ("price".to_string(), price.to_string()),
+            ].into_iter().collect());
+        }
+        assert_eq!(view.status_text(), "");
+
+        view.set_cursor_pos(0, 0);
+        assert_eq!(view.status_text(), "R1:C1 Name");
+        view.on_event(Event::Shift(Key::Right));
+        view.on_event(Event::Shift(Key::Down));
+        view.on_event(Event::Shift(Key::Down));
+        assert_eq!(view.status_text(), "R3:C2 Price  6 selected  Sum: 5.5  Average: 2.75");
+
+        view.set_status_aggregates(&[Aggregate::Min, Aggregate::Max]);
+        assert!(view.status_text().ends_with("  Min: 2  Max: 3.5"));
+        view.set_filter(|record| record["name"] != "cake");
+        assert!(view.status_text().ends_with("  4 selected  Min: 2  Max: 2"));
+
+        let text = view.render_to_string(Vec2::new(50, 6));
+        assert!(text.ends_with("\nR3:C2 Price  4 selected  Min: 2  Max: 2"));
+    }
+}
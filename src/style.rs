@@ -0,0 +1,109 @@
+//! The colors and effects a view is drawn with, for matching the look of the
+//! rest of an application.
+
+use std::fmt::Display;
+
+use cursive::Printer;
+use cursive::theme::{BaseColor, Color, ColorStyle, PaletteColor, Style};
+
+use crate::SpreadsheetView;
+
+/// The styles the parts of a view are drawn in. The defaults use the entries
+/// of the palette of the active theme, so that views follow the theme unless
+/// their style is overridden. None of them apply in monochrome, which only
+/// uses effects.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpreadsheetStyle {
+    /// The column titles.
+    pub header: Style,
+    /// The lines between the columns, under the header, and between bands.
+    pub grid: Style,
+    /// The cell under the cursor, and the cell being edited, while the view
+    /// has focus.
+    pub cursor: Style,
+    /// The cell under the cursor while the view does not have focus.
+    pub cursor_inactive: Style,
+    /// The selected cells.
+    pub selection: Style,
+    /// The cells matching the search.
+    pub search_match: Style,
+    /// Whether every other record is drawn in `alternate_row`, as if banded by
+    /// stripes of a single record. Banding set with `set_banding` takes
+    /// precedence. Off by default.
+    pub zebra: bool,
+    /// The records of every other stripe, when striped.
+    pub alternate_row: Style,
+    /// The cells of read-only columns, and the read-only cells, in views that
+    /// can be edited.
+    pub read_only: Style,
+}
+
+impl Default for SpreadsheetStyle {
+    fn default() -> Self {
+        Self {
+            header: Style::none(),
+            grid: Style::none(),
+            cursor: ColorStyle::highlight().into(),
+            cursor_inactive: ColorStyle::highlight_inactive().into(),
+            selection: ColorStyle::new(PaletteColor::View, PaletteColor::Secondary).into(),
+            search_match: ColorStyle::new(Color::Dark(BaseColor::Black), Color::Light(BaseColor::Yellow)).into(),
+            zebra: false,
+            alternate_row: ColorStyle::secondary().into(),
+            read_only: ColorStyle::secondary().into(),
+        }
+    }
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Sets the styles this view is drawn in.
+    pub fn set_style(&mut self, style: SpreadsheetStyle) {
+        self.style = style;
+    }
+
+    /// Returns the styles this view is drawn in.
+    pub fn style(&self) -> &SpreadsheetStyle {
+        &self.style
+    }
+
+    /// Returns the style the lines of the grid are drawn in.
+    fn grid_style(&self) -> Style {
+        if self.monochrome { Style::none() } else { self.style.grid }
+    }
+
+    /// Prints part of the grid, such as a separator between two columns.
+    pub(crate) fn print_grid(&self, printer: &Printer, x: usize, text: &str) {
+        printer.with_style(self.grid_style(), |p| p.print((x, 0), text));
+    }
+
+    /// Prints a horizontal line of the grid across the given printer, on the
+    /// line at `y`.
+    pub(crate) fn print_grid_hline(&self, printer: &Printer, y: usize, text: &str) {
+        printer.with_style(self.grid_style(), |p| p.print_hline((0, y), p.size.x, text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    #[test]
+    fn styles_can_be_overridden() {
+        let mut view = SpreadsheetView::<u32>::new();
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        view.extend_records((1..=3).map(|n| vec![("n".to_string(), n)].into_iter().collect()));
+        assert_eq!(*view.style(), SpreadsheetStyle::default());
+
+        let red = Style::from(ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View));
+        view.set_style(SpreadsheetStyle { zebra: true, alternate_row: red, ..SpreadsheetStyle::default() });
+        view.set_cursor_pos(0, 0);
+        let text = view.render_to_string(Vec2::new(10, 5));
+        assert!(text.contains("\n2"));
+        assert!(view.is_striped(1));
+        assert!(!view.is_striped(2));
+        assert_eq!(view.cell_style(true, false, false, false, false, true), red);
+    }
+}
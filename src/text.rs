@@ -0,0 +1,176 @@
+//! Helpers for turning cell values into text that is safe to print.
+
+use std::fmt::{self, Display, Write};
+
+use cursive::Printer;
+use cursive::align::HAlign;
+use unicode_width::UnicodeWidthChar;
+use unicode_width::UnicodeWidthStr;
+
+/// Formats a value for display, escaping any terminal control characters.
+pub(crate) fn display_text<D: Display + ?Sized>(value: &D) -> String {
+    escape_controls(&value.to_string())
+}
+
+/// Returns the width `display_text` would give a value, without building the
+/// text, so that measuring cells does not copy their contents.
+pub(crate) fn display_width<D: Display + ?Sized>(value: &D) -> usize {
+    let mut counter = WidthCounter(0);
+    let _ = write!(counter, "{}", value);
+    counter.0
+}
+
+/// Adds up the width of the text written into it, counting control characters
+/// as their one-column stand-ins.
+struct WidthCounter(usize);
+
+impl Write for WidthCounter {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.0 += text.chars()
+            .map(|c| if c.is_control() { 1 } else { c.width().unwrap_or(0) })
+            .sum::<usize>();
+        Ok(())
+    }
+}
+
+/// Replaces control characters with visible stand-ins, so that untrusted data
+/// (e.g. ANSI escape sequences in imported logs) cannot restyle or break the
+/// terminal: C0 controls become their Unicode control pictures (`\x1b` becomes
+/// `␛`), DEL becomes `␡`, and C1 controls become `�`.
+pub(crate) fn escape_controls(text: &str) -> String {
+    // Most text has nothing to escape.
+    if !text.chars().any(char::is_control) {
+        return text.to_string();
+    }
+
+    text.chars()
+        .map(|c| match c as u32 {
+            code @ 0x00..=0x1f => std::char::from_u32(0x2400 + code).unwrap_or(c),
+            0x7f => '␡',
+            0x80..=0x9f => '�',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Escapes control characters like `escape_controls`, except for line breaks,
+/// which are kept as `\n` alone.
+pub(crate) fn escape_lines(text: &str) -> String {
+    text.split('\n')
+        .map(|line| escape_controls(line.strip_suffix('\r').unwrap_or(line)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the width of the widest line of `text`.
+pub(crate) fn widest_line(text: &str) -> usize {
+    text.split('\n').map(UnicodeWidthStr::width).max().unwrap_or(0)
+}
+
+/// Prints `text` aligned within a field of the given width, truncating it if
+/// it does not fit.
+pub(crate) fn print_aligned(printer: &Printer, text: &str, width: usize, alignment: &HAlign) {
+    let text = truncate(text, width);
+    let offset = alignment.get_offset(text.width(), width);
+    printer.print_hline((0, 0), width, " ");
+    printer.print((offset, 0), text);
+}
+
+/// Shortens `text` to fit in the given width by replacing its end with an
+/// ellipsis.
+pub(crate) fn ellipsize_end(text: &str, width: usize) -> String {
+    if text.width() <= width { text.to_string() }
+    else if width == 0 { String::new() }
+    else { format!("{}{}", truncate(text, width - 1), ELLIPSIS) }
+}
+
+/// Shortens `text` to fit in the given width by replacing its middle with an
+/// ellipsis, keeping about as much of its start as of its end.
+pub(crate) fn ellipsize_middle(text: &str, width: usize) -> String {
+    if text.width() <= width || width < 2 {
+        return ellipsize_end(text, width);
+    }
+
+    let head = truncate(text, width / 2);
+    let tail = truncate_start(text, width - 1 - head.width());
+    format!("{}{}{}", head, ELLIPSIS, tail)
+}
+
+/// Stands in for the part of a text cut out to make it fit.
+const ELLIPSIS: &str = "…";
+
+/// Returns the longest suffix of `text` that fits in the given width.
+fn truncate_start(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in text.char_indices().rev() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &text[i + c.len_utf8()..];
+        }
+    }
+
+    text
+}
+
+/// Splits tab-separated text, as copied from other spreadsheets, into rows of
+/// cells.
+pub(crate) fn split_tsv(text: &str) -> Vec<Vec<String>> {
+    text.lines().map(|line| line.split('\t').map(str::to_string).collect()).collect()
+}
+
+/// Returns the longest prefix of `text` that fits in the given width.
+pub(crate) fn truncate(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &text[..i];
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_respects_width() {
+        assert_eq!(truncate("hello", 3), "hel");
+        assert_eq!(truncate("hi", 3), "hi");
+        assert_eq!(truncate("日本語", 5), "日本");
+        assert_eq!(truncate_start("日本語", 5), "本語");
+    }
+
+    #[test]
+    fn tsv_rows_and_cells() {
+        assert_eq!(split_tsv("a\tb\r\n\tc\n"), [vec!["a", "b"], vec!["", "c"]]);
+        assert!(split_tsv("").is_empty());
+    }
+
+    #[test]
+    fn ellipsize_keeps_ends() {
+        assert_eq!(ellipsize_end("Quantity", 5), "Quan…");
+        assert_eq!(ellipsize_middle("Quantity", 5), "Qu…ty");
+        assert_eq!(ellipsize_middle("Quantity", 6), "Qua…ty");
+        assert_eq!(ellipsize_middle("Quantity", 8), "Quantity");
+        assert_eq!(ellipsize_middle("Quantity", 1), "…");
+        assert_eq!(ellipsize_end("Qty", 0), "");
+    }
+
+    #[test]
+    fn controls_are_escaped() {
+        assert_eq!(escape_controls("\x1b[31mred\x1b[0m"), "␛[31mred␛[0m");
+        assert_eq!(escape_controls("a\tb\r\n"), "a␉b␍␊");
+        assert_eq!(escape_controls("del\x7f c1\u{9b}"), "del␡ c1�");
+        assert_eq!(escape_controls("plain – text"), "plain – text");
+        assert_eq!(escape_lines("a\tb\r\nc\n"), "a␉b\nc\n");
+        assert_eq!(widest_line("ab\nabc\n"), 3);
+        assert_eq!(display_text(&"\x07").width(), 1);
+
+        for text in &["\x1b[31mred", "日本語", "a\u{301}\u{9b}", ""] {
+            assert_eq!(display_width(text), display_text(text).width());
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! A ready-made cell type for columns of mixed data.
+
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt::{self, Display};
+use std::mem;
+use std::str::FromStr;
+
+use cursive::align::HAlign;
+
+use crate::SpreadsheetView;
+
+/// The value of a cell that may hold a number, text, a flag, or a date. Values
+/// order by kind first (empty, then flags, numbers, dates, and text), and
+/// numbers order by value whether they are integers or not.
+#[derive(Clone, Debug, Default)]
+pub enum CellValue {
+    #[default]
+    Empty,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    #[cfg(feature = "chrono")]
+    Date(chrono::NaiveDate),
+    Text(String),
+}
+
+impl CellValue {
+    /// Returns `true` if this is `CellValue::Empty`.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::Empty)
+    }
+
+    /// Returns `true` if this is an integer or a float.
+    pub fn is_number(&self) -> bool {
+        matches!(self, Self::Int(_) | Self::Float(_))
+    }
+
+    /// Returns this value as a float, if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Int(i) => Some(i as f64),
+            Self::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Returns the alignment that suits this kind of value: right for
+    /// numbers, centered for flags, and left for everything else.
+    pub fn alignment(&self) -> HAlign {
+        match self {
+            Self::Int(_) | Self::Float(_) => HAlign::Right,
+            Self::Bool(_) => HAlign::Center,
+            _ => HAlign::Left,
+        }
+    }
+
+    /// Returns the position of this kind of value in the sort order.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Empty => 0,
+            Self::Bool(_) => 1,
+            Self::Int(_) | Self::Float(_) => 2,
+            #[cfg(feature = "chrono")]
+            Self::Date(_) => 3,
+            Self::Text(_) => 4,
+        }
+    }
+}
+
+impl Display for CellValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => Ok(()),
+            Self::Bool(b) => b.fmt(f),
+            Self::Int(i) => i.fmt(f),
+            Self::Float(x) => x.fmt(f),
+            #[cfg(feature = "chrono")]
+            Self::Date(d) => d.format("%Y-%m-%d").fmt(f),
+            Self::Text(s) => s.fmt(f),
+        }
+    }
+}
+
+impl FromStr for CellValue {
+    type Err = Infallible;
+
+    /// Reads a blank string as `Empty`, `true` and `false` as flags, whole
+    /// numbers without a decimal point as integers and other numbers as
+    /// floats, ISO 8601 dates as dates, and anything else as text.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return Ok(Self::Empty);
+        }
+        if let Ok(b) = trimmed.parse() {
+            return Ok(Self::Bool(b));
+        }
+        if let Ok(i) = trimmed.parse() {
+            return Ok(Self::Int(i));
+        }
+        if let Ok(x) = trimmed.parse::<f64>() {
+            if x.is_finite() {
+                return Ok(Self::Float(x));
+            }
+        }
+        #[cfg(feature = "chrono")]
+        {
+            if let Ok(d) = chrono::NaiveDate::parse_from_str(trimmed, "%Y-%m-%d") {
+                return Ok(Self::Date(d));
+            }
+        }
+        Ok(Self::Text(text.to_string()))
+    }
+}
+
+impl Ord for CellValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Int(a), Self::Int(b)) => a.cmp(b),
+            #[cfg(feature = "chrono")]
+            (Self::Date(a), Self::Date(b)) => a.cmp(b),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            (a, b) => match (a.as_f64(), b.as_f64()) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                _ => a.rank().cmp(&b.rank()),
+            },
+        }
+    }
+}
+
+impl PartialOrd for CellValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for CellValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CellValue {}
+
+impl From<bool> for CellValue {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl From<i64> for CellValue {
+    fn from(i: i64) -> Self {
+        Self::Int(i)
+    }
+}
+
+impl From<f64> for CellValue {
+    fn from(x: f64) -> Self {
+        Self::Float(x)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDate> for CellValue {
+    fn from(d: chrono::NaiveDate) -> Self {
+        Self::Date(d)
+    }
+}
+
+impl From<String> for CellValue {
+    fn from(s: String) -> Self {
+        Self::Text(s)
+    }
+}
+
+impl From<&str> for CellValue {
+    fn from(s: &str) -> Self {
+        Self::Text(s.to_string())
+    }
+}
+
+impl<T: Into<CellValue>> From<Option<T>> for CellValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Empty, Into::into)
+    }
+}
+
+impl SpreadsheetView<CellValue> {
+    /// Aligns every column by the kind of values it holds: right if all of
+    /// its values are numbers, centered if they are all flags, and left
+    /// otherwise. Empty cells do not count. Columns without values are left
+    /// as they are.
+    pub fn align_columns_by_value(&mut self) {
+        for (key, def) in self.columns.iter_mut() {
+            let mut alignments = self.records.iter()
+                .filter_map(|record| record.get(key))
+                .filter(|value| !value.is_empty())
+                .map(CellValue::alignment);
+
+            if let Some(first) = alignments.next() {
+                let mixed = alignments.any(|a| mem::discriminant(&a) != mem::discriminant(&first));
+                def.alignment = if mixed { HAlign::Left } else { first };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ColumnDef;
+
+    #[test]
+    fn parses_and_orders_by_kind() {
+        let mut values: Vec<CellValue> = ["b", "10", "", "2.5", "true", "a", "-3"].iter()
+            .map(|text| text.parse().unwrap())
+            .collect();
+        values.sort();
+
+        let texts: Vec<String> = values.iter().map(ToString::to_string).collect();
+        assert_eq!(texts, ["", "true", "-3", "2.5", "10", "a", "b"]);
+        assert_eq!(CellValue::Int(2), CellValue::Float(2.0));
+        assert!(CellValue::from(None::<i64>).is_empty());
+    }
+
+    #[test]
+    fn aligns_columns_by_value() {
+        let mut view = SpreadsheetView::new();
+        for key in &["qty", "done", "note"] {
+            view.push_column(key.to_string(), ColumnDef::new(key));
+        }
+        view.push_record(vec![
+            ("qty".to_string(), CellValue::Int(4)),
+            ("done".to_string(), CellValue::Bool(true)),
+            ("note".to_string(), CellValue::Float(1.5)),
+        ].into_iter().collect());
+        view.push_record(vec![
+            ("qty".to_string(), CellValue::Empty),
+            ("note".to_string(), CellValue::from("n/a")),
+        ].into_iter().collect());
+
+        view.align_columns_by_value();
+        assert!(matches!(view.columns["qty"].alignment, HAlign::Right));
+        assert!(matches!(view.columns["done"].alignment, HAlign::Center));
+        assert!(matches!(view.columns["note"].alignment, HAlign::Left));
+    }
+}
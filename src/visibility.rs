@@ -0,0 +1,200 @@
+//! Hiding columns from the layout, without losing their values or their
+//! definitions.
+
+use std::fmt::Display;
+
+use cursive::event::EventResult;
+use cursive::view::Nameable;
+use cursive::views::{Dialog, SelectView};
+
+use crate::SpreadsheetView;
+
+/// The name of the list of columns in the popup of `column_visibility_popup`.
+const COLUMN_LIST_NAME: &str = "spreadsheet_view_columns";
+
+/// Returns the label of a column in the popup of `column_visibility_popup`.
+fn visibility_label(title: &str, hidden: bool) -> String {
+    format!("[{}] {}", if hidden { ' ' } else { 'x' }, title)
+}
+
+impl<D: Display + Ord> SpreadsheetView<D> {
+    /// Hides the column with the specified key, leaving it out of the layout
+    /// while keeping its values and its definition. A cursor on the column
+    /// moves to the next column shown.
+    pub fn hide_column(&mut self, key: &str) {
+        self.set_column_hidden(key, true);
+    }
+
+    /// Shows the column with the specified key again, if it was hidden.
+    pub fn show_column(&mut self, key: &str) {
+        self.set_column_hidden(key, false);
+    }
+
+    /// Hides or shows the column with the specified key.
+    pub fn set_column_hidden(&mut self, key: &str, hidden: bool) {
+        let def = match self.columns.get_mut(key) {
+            Some(def) => def,
+            None => return,
+        };
+
+        def.hidden = hidden;
+        self.selection_anchor = None;
+        if let Some(x) = self.columns.get_index_of(key).filter(|_| hidden) {
+            self.selected_cells.retain(|&(selected, _)| selected != x);
+        }
+        if let Some((x, y)) = self.cursor_pos {
+            self.set_cursor_pos(x, y);
+        }
+    }
+
+    /// Returns `true` if the column with the specified key is hidden.
+    pub fn is_column_hidden(&self, key: &str) -> bool {
+        self.columns.get(key).is_some_and(|def| def.hidden)
+    }
+
+    /// Returns `true` if the column at the given index is not hidden.
+    pub(crate) fn is_column_shown(&self, x: usize) -> bool {
+        self.columns.get_index(x).is_some_and(|(_, def)| !def.hidden)
+    }
+
+    /// Returns the column at the given index if it is shown, or else the
+    /// nearest one shown in the given direction, or the other way if there is
+    /// none that way.
+    pub(crate) fn shown_column_near(&self, x: usize, right: bool) -> Option<usize> {
+        if self.is_column_shown(x) {
+            return Some(x);
+        }
+
+        let mut after = (x + 1..self.columns.len()).filter(|&x| self.is_column_shown(x));
+        let mut before = (0..x).rev().filter(|&x| self.is_column_shown(x));
+        if right { after.next().or_else(|| before.next()) } else { before.next().or_else(|| after.next()) }
+    }
+}
+
+impl<D: Display + Ord + Clone + 'static> SpreadsheetView<D> {
+    /// Returns a callback that shows a popup listing every column of this
+    /// view with a checkbox, which choosing a column toggles to hide or show
+    /// it. This view must be found under the given name in the view tree (see
+    /// `Nameable::with_name`).
+    pub fn column_visibility_popup(&self, name: &str) -> EventResult {
+        let name = name.to_string();
+        let columns: Vec<(String, String, bool)> = self.columns.iter()
+            .map(|(key, def)| (key.clone(), def.title.clone(), def.hidden))
+            .collect();
+
+        EventResult::with_cb(move |s| {
+            let mut list = SelectView::new();
+            for (key, title, hidden) in columns.iter() {
+                list.add_item(visibility_label(title, *hidden), key.clone());
+            }
+
+            let name = name.clone();
+            list.set_on_submit(move |s, key: &String| {
+                let toggled = s.call_on_name(&name, |view: &mut Self| {
+                    let hidden = !view.is_column_hidden(key);
+                    view.set_column_hidden(key, hidden);
+                    view.columns.get(key).map(|def| (def.title.clone(), hidden))
+                });
+
+                if let Some((title, hidden)) = toggled.flatten() {
+                    let key = key.clone();
+                    s.call_on_name(COLUMN_LIST_NAME, move |list: &mut SelectView<String>| {
+                        if let Some(id) = list.selected_id() {
+                            list.remove_item(id);
+                            list.insert_item(id, visibility_label(&title, hidden), key);
+                            list.set_selection(id);
+                        }
+                    });
+                }
+            });
+
+            s.add_layer(Dialog::around(list.with_name(COLUMN_LIST_NAME))
+                .title("Columns")
+                .dismiss_button("Close"));
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cursive::Cursive;
+    use cursive::event::{Event, Key};
+    use cursive::view::View;
+    use cursive::vec::Vec2;
+
+    use crate::ColumnDef;
+
+    fn numbers() -> SpreadsheetView<u32> {
+        let mut view = SpreadsheetView::new();
+        view.push_column("n".to_string(), ColumnDef::new("N"));
+        view.push_column("sq".to_string(), ColumnDef::new("Square"));
+        view.push_column("cube".to_string(), ColumnDef::new("Cube"));
+        view.extend_records((1..=3).map(|n| {
+            vec![("n".to_string(), n), ("sq".to_string(), n * n), ("cube".to_string(), n * n * n)].into_iter().collect()
+        }));
+        view
+    }
+
+    #[test]
+    fn hidden_columns_are_left_out() {
+        let mut view = numbers();
+        view.set_cursor_pos(1, 0);
+        view.hide_column("sq");
+        assert!(view.is_column_hidden("sq"));
+        assert_eq!(view.cursor_pos(), Some((2, 0)));
+        assert!(view.render_to_string(Vec2::new(20, 5)).starts_with("N │ Cube\n"));
+        assert_eq!(view.get_cell(1, "sq"), Some(&4));
+
+        // The cursor skips the hidden column.
+        view.on_event(Event::Key(Key::Left));
+        assert_eq!(view.cursor_pos(), Some((0, 0)));
+        view.on_event(Event::Key(Key::Right));
+        assert_eq!(view.cursor_pos(), Some((2, 0)));
+
+        view.show_column("sq");
+        assert!(view.render_to_string(Vec2::new(20, 5)).starts_with("N │ Square │ Cube\n"));
+    }
+
+    #[test]
+    fn hidden_columns_take_no_room_when_scrolling() {
+        let mut view = numbers();
+        for i in 0..4 {
+            let key = format!("h{}", i);
+            view.push_column(key.clone(), ColumnDef::new("Hidden").hidden(true));
+            assert!(view.move_column(&key, 1));
+        }
+        view.set_horizontal_scrollbar(true);
+        view.layout(Vec2::new(14, 6));
+
+        // The hidden columns take up no room, so the first columns still fit.
+        assert!(view.scroll_to_column("sq"));
+        assert_eq!(view.scroll_offset(), Vec2::zero());
+        let text = view.render_to_string(Vec2::new(14, 6));
+        assert!(text.starts_with("N │ Square │ C\n"));
+        assert_eq!(text.matches('▒').count(), 11);
+    }
+
+    #[test]
+    fn popup_toggles_columns() {
+        let mut siv = Cursive::dummy();
+        let view = numbers();
+        let popup = view.column_visibility_popup("numbers");
+        siv.add_layer(view.with_name("numbers"));
+        if let EventResult::Consumed(Some(cb)) = popup {
+            cb(&mut siv);
+        }
+
+        siv.call_on_name(COLUMN_LIST_NAME, |list: &mut SelectView<String>| {
+            assert_eq!(list.get_item(1), Some(("[x] Square", &"sq".to_string())));
+            list.set_selection(1);
+        });
+        siv.on_event(Event::Key(Key::Enter));
+        let hidden = siv.call_on_name("numbers", |view: &mut SpreadsheetView<u32>| view.is_column_hidden("sq"));
+        assert_eq!(hidden, Some(true));
+        siv.call_on_name(COLUMN_LIST_NAME, |list: &mut SelectView<String>| {
+            assert_eq!(list.get_item(1).map(|(label, _)| label), Some("[ ] Square"));
+        });
+    }
+}